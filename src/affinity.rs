@@ -0,0 +1,207 @@
+//! Worker Thread Placement
+//!
+//! Optional CPU affinity and scheduling class for CPU worker threads
+//! (`--cpus`, `--core-type`, `--nice`, `--sched-idle`). Each worker pins itself
+//! to one CPU of the chosen set in turn, and can drop to `SCHED_IDLE` or a
+//! higher nice level so a background search yields to interactive work.
+//! Placement is implemented for Linux only.
+
+use std::fmt;
+
+/// Core type of a hybrid CPU to place workers on (`--core-type`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CoreType {
+    Performance,
+    Efficiency,
+}
+
+/// How CPU worker threads are placed and scheduled
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThreadPolicy {
+    /// CPUs workers are pinned to, one per worker in turn (empty = float)
+    pub cpus: Vec<usize>,
+    /// Nice level for each worker thread
+    pub nice: Option<i32>,
+    /// Run workers in the `SCHED_IDLE` class
+    pub sched_idle: bool,
+}
+
+impl ThreadPolicy {
+    /// No placement or scheduling changes requested
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// CPU the `worker_index`-th worker is pinned to
+    pub fn cpu_for(&self, worker_index: usize) -> Option<usize> {
+        (!self.cpus.is_empty()).then(|| self.cpus[worker_index % self.cpus.len()])
+    }
+
+    /// Apply to the calling thread, which is worker `worker_index`
+    #[cfg(target_os = "linux")]
+    pub fn apply_to_current_thread(&self, worker_index: usize) -> Result<(), String> {
+        if let Some(cpu) = self.cpu_for(worker_index) {
+            linux::pin_current_thread(cpu)?;
+        }
+        if self.sched_idle {
+            linux::set_sched_idle()?;
+        }
+        if let Some(nice) = self.nice {
+            linux::set_nice(nice)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply_to_current_thread(&self, _worker_index: usize) -> Result<(), String> {
+        if self.is_default() {
+            Ok(())
+        } else {
+            Err(
+                "Worker CPU placement and scheduling classes are only supported on Linux"
+                    .to_string(),
+            )
+        }
+    }
+}
+
+impl fmt::Display for ThreadPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if !self.cpus.is_empty() {
+            parts.push(format!("pinned to CPUs {}", format_cpu_list(&self.cpus)));
+        }
+        if self.sched_idle {
+            parts.push("SCHED_IDLE".to_string());
+        }
+        if let Some(nice) = self.nice {
+            parts.push(format!("nice {}", nice));
+        }
+        if parts.is_empty() {
+            parts.push("default".to_string());
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// Format CPU ids in kernel list syntax, e.g. `0-3,8`
+pub fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut sorted = cpus.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for cpu in sorted {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == cpu => *end = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::io;
+
+    pub fn pin_current_thread(cpu: usize) -> Result<(), String> {
+        // SAFETY: cpu_set_t is plain data; the set is fully initialised before use
+        let result = unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            libc::CPU_SET(cpu, &mut set);
+            libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+        };
+        if result != 0 {
+            return Err(format!(
+                "Failed to pin worker to CPU {}: {}",
+                cpu,
+                io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn set_sched_idle() -> Result<(), String> {
+        let param = libc::sched_param { sched_priority: 0 };
+        // SAFETY: pid 0 is the calling thread and `param` outlives the call
+        if unsafe { libc::sched_setscheduler(0, libc::SCHED_IDLE, &param) } != 0 {
+            return Err(format!(
+                "Failed to set SCHED_IDLE: {}",
+                io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Linux nice values are per thread, addressed by thread id
+    pub fn set_nice(nice: i32) -> Result<(), String> {
+        // SAFETY: plain syscalls on the calling thread
+        let result = unsafe {
+            let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+            libc::setpriority(libc::PRIO_PROCESS as _, tid, nice)
+        };
+        if result != 0 {
+            return Err(format!(
+                "Failed to set nice {}: {}",
+                nice,
+                io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_and_round_robin() {
+        assert_eq!(format_cpu_list(&[3, 0, 1, 2, 8, 10, 11]), "0-3,8,10-11");
+        let policy = ThreadPolicy {
+            cpus: vec![4, 5],
+            sched_idle: true,
+            ..Default::default()
+        };
+        assert_eq!(policy.cpu_for(3), Some(5));
+        assert_eq!(policy.to_string(), "pinned to CPUs 4-5, SCHED_IDLE");
+        assert!(ThreadPolicy::default().is_default());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_apply_on_worker_thread() {
+        let cpu = crate::topology::CpuTopology::detect().cpus[0];
+        let policy = ThreadPolicy {
+            cpus: vec![cpu],
+            nice: Some(5),
+            sched_idle: true,
+        };
+
+        std::thread::spawn(move || {
+            policy.apply_to_current_thread(0).unwrap();
+            // SAFETY: read-only queries about the calling thread
+            unsafe {
+                let mut set: libc::cpu_set_t = std::mem::zeroed();
+                libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set);
+                assert!(libc::CPU_ISSET(cpu, &set));
+                assert_eq!(libc::CPU_COUNT(&set), 1);
+                assert_eq!(libc::sched_getscheduler(0), libc::SCHED_IDLE);
+                let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+                assert_eq!(libc::getpriority(libc::PRIO_PROCESS as _, tid), 5);
+            }
+        })
+        .join()
+        .unwrap();
+    }
+}
@@ -0,0 +1,185 @@
+//! Key Archive Scanning
+//!
+//! Walks an output root the same way every run lays it out
+//! (`<root>/<%Y%m%d_%H%M%S>/<PATTERN>_<INDEX>_<TIMESTAMP>_public|private.txt`)
+//! and pairs public and private key files by their shared stem.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PUBLIC_SUFFIX: &str = "_public.txt";
+const PRIVATE_SUFFIX: &str = "_private.txt";
+
+/// Fields encoded in a key filename by `save_key`
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyFileName {
+    pub pattern_id: String,
+    pub index: usize,
+    /// `%Y%m%d_%H%M%S` timestamp of the run that wrote the file
+    pub timestamp: String,
+}
+
+impl KeyFileName {
+    /// Parse a stem such as `AB12CD34_3_20260101_120000`
+    pub fn parse(stem: &str) -> Option<Self> {
+        let mut parts = stem.rsplitn(4, '_');
+        let time = parts.next()?;
+        let date = parts.next()?;
+        let index = parts.next()?.parse().ok()?;
+        let pattern_id = parts.next()?;
+
+        let digits = |s: &str, n: usize| s.len() == n && s.chars().all(|c| c.is_ascii_digit());
+        if !digits(date, 8) || !digits(time, 6) || pattern_id.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            pattern_id: pattern_id.to_string(),
+            index,
+            timestamp: format!("{}_{}", date, time),
+        })
+    }
+}
+
+/// A public/private file pair sharing a stem (either half may be missing)
+#[derive(Debug, Clone)]
+pub struct KeyFilePair {
+    /// Directory + stem without the `_public.txt` / `_private.txt` suffix
+    pub stem: PathBuf,
+    pub public_path: Option<PathBuf>,
+    pub private_path: Option<PathBuf>,
+    pub name: Option<KeyFileName>,
+}
+
+impl KeyFilePair {
+    /// Stem rendered relative to `root` for reports
+    pub fn display_relative(&self, root: &Path) -> String {
+        self.stem
+            .strip_prefix(root)
+            .unwrap_or(&self.stem)
+            .display()
+            .to_string()
+    }
+}
+
+/// Recursively collect every key file under `root`, paired by stem
+///
+/// Pairs are returned in path order so reports are stable between runs.
+pub fn scan_key_files(root: &Path) -> Vec<KeyFilePair> {
+    let mut pairs: BTreeMap<PathBuf, KeyFilePair> = BTreeMap::new();
+
+    fn scan_dir(dir: &Path, pairs: &mut BTreeMap<PathBuf, KeyFilePair>) {
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    scan_dir(&path, pairs);
+                    continue;
+                }
+
+                let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                let (stem_name, is_public) = if let Some(s) = name.strip_suffix(PUBLIC_SUFFIX) {
+                    (s, true)
+                } else if let Some(s) = name.strip_suffix(PRIVATE_SUFFIX) {
+                    (s, false)
+                } else {
+                    continue;
+                };
+
+                let stem = dir.join(stem_name);
+                let pair = pairs.entry(stem.clone()).or_insert_with(|| KeyFilePair {
+                    stem,
+                    public_path: None,
+                    private_path: None,
+                    name: KeyFileName::parse(stem_name),
+                });
+                if is_public {
+                    pair.public_path = Some(path);
+                } else {
+                    pair.private_path = Some(path);
+                }
+            }
+        }
+    }
+
+    scan_dir(root, &mut pairs);
+    pairs.into_values().collect()
+}
+
+/// Read a normalized 64-char lowercase public key, or `None` if the file is malformed
+pub fn read_public_key(path: &Path) -> Option<String> {
+    read_hex_file(path, 64)
+}
+
+/// Read a normalized 128-char lowercase private key, or `None` if the file is malformed
+pub fn read_private_key(path: &Path) -> Option<String> {
+    read_hex_file(path, 128)
+}
+
+fn read_hex_file(path: &Path, len: usize) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let key = content.trim().to_lowercase();
+    if key.len() == len && key.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(key)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_key_filename() {
+        let name = KeyFileName::parse("AB12CD34_3_20260101_120000").unwrap();
+        assert_eq!(name.pattern_id, "AB12CD34");
+        assert_eq!(name.index, 3);
+        assert_eq!(name.timestamp, "20260101_120000");
+
+        // Legacy `meshcore_` prefix stays part of the pattern id
+        let name = KeyFileName::parse("meshcore_7B33BDB3_1_20260130_223639").unwrap();
+        assert_eq!(name.pattern_id, "meshcore_7B33BDB3");
+
+        assert!(KeyFileName::parse("random_name").is_none());
+        assert!(KeyFileName::parse("AB_x_20260101_120000").is_none());
+    }
+
+    #[test]
+    fn test_scan_pairs_files_by_stem() {
+        let dir = tempdir().unwrap();
+        let run = dir.path().join("20260101_000000");
+        fs::create_dir_all(&run).unwrap();
+        fs::write(run.join("AB_1_20260101_000000_public.txt"), "x").unwrap();
+        fs::write(run.join("AB_1_20260101_000000_private.txt"), "y").unwrap();
+        fs::write(run.join("CD_2_20260101_000000_public.txt"), "z").unwrap();
+        fs::write(run.join("notes.txt"), "ignored").unwrap();
+
+        let pairs = scan_key_files(dir.path());
+        assert_eq!(pairs.len(), 2);
+        assert!(pairs[0].public_path.is_some() && pairs[0].private_path.is_some());
+        assert!(pairs[1].private_path.is_none());
+        assert_eq!(
+            pairs[1].display_relative(dir.path()),
+            Path::new("20260101_000000")
+                .join("CD_2_20260101_000000")
+                .display()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_read_public_key_normalizes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("k_public.txt");
+        fs::write(&path, format!("  {}\n", "AB".repeat(32))).unwrap();
+        assert_eq!(read_public_key(&path), Some("ab".repeat(32)));
+
+        fs::write(&path, "abcd").unwrap();
+        assert_eq!(read_public_key(&path), None);
+    }
+}
@@ -0,0 +1,527 @@
+//! Key Health Audit
+//!
+//! Loads a stored keypair and runs the full `keygen::check_key_health`
+//! diagnostics on it, so a rejected key comes with every failing reason
+//! instead of a single message. Pointed at a directory, it audits a whole
+//! key archive: orphans, mismatched pairs, duplicates across runs, invalid
+//! keys and node-ID collisions.
+
+use console::style;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::archive::{self, KeyFilePair};
+use crate::keygen::{self, KeyHealthReport, KeyInfo};
+
+/// Read a hex value either directly or from a file containing it
+fn read_hex_arg(value: &str) -> Result<String, String> {
+    let path = Path::new(value);
+    if path.is_file() {
+        fs::read_to_string(path)
+            .map(|s| s.trim().to_lowercase())
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    } else {
+        Ok(value.trim().to_lowercase())
+    }
+}
+
+/// Decode a 64-byte private key from hex
+pub fn parse_private_hex(hex_str: &str) -> Result<[u8; 64], String> {
+    let bytes = hex::decode(hex_str).map_err(|e| format!("Invalid private key hex: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| format!("Private key must be 64 bytes, got {}", b.len()))
+}
+
+/// Decode a 32-byte public key from hex
+pub fn parse_public_hex(hex_str: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(hex_str).map_err(|e| format!("Invalid public key hex: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| format!("Public key must be 32 bytes, got {}", b.len()))
+}
+
+/// Load the keypair to audit
+///
+/// `private` is a hex string or a `_private.txt` path. The public key comes from
+/// `public` if given, otherwise from the sibling `_public.txt` file, otherwise it
+/// is re-derived from the private key.
+pub fn load_key(private: &str, public: Option<&str>) -> Result<KeyInfo, String> {
+    let private_bytes = parse_private_hex(&read_hex_arg(private)?)?;
+
+    let public_hex = match public {
+        Some(p) => Some(read_hex_arg(p)?),
+        None => private
+            .strip_suffix("_private.txt")
+            .map(|stem| format!("{}_public.txt", stem))
+            .filter(|p| Path::new(p).is_file())
+            .map(|p| read_hex_arg(&p))
+            .transpose()?,
+    };
+
+    match public_hex {
+        Some(public_hex) => {
+            let public_bytes = parse_public_hex(&public_hex)?;
+            Ok(KeyInfo {
+                public_hex: hex::encode(public_bytes),
+                private_hex: hex::encode(private_bytes),
+                public_bytes,
+                private_bytes,
+            })
+        }
+        None => Ok(keygen::keypair_from_private(&private_bytes)),
+    }
+}
+
+/// Load an operator key for signing
+///
+/// Unlike an audit, which must report a mismatched pair, a signer has to
+/// publish the public key its private key really has, so the public key is
+/// always derived; a sibling `_public.txt` that disagrees is rejected.
+pub fn load_signing_key(private: &str) -> Result<KeyInfo, String> {
+    let stored = load_key(private, None)?;
+    let key = keygen::keypair_from_private(&stored.private_bytes);
+    if key.public_bytes != stored.public_bytes {
+        return Err(format!(
+            "Public key file next to {} does not match the private key",
+            private
+        ));
+    }
+    Ok(key)
+}
+
+/// Print a health report in human-readable form
+pub fn print_report(key: &KeyInfo, report: &KeyHealthReport) {
+    println!("  Public Key:  {}", style(&key.public_hex).yellow());
+    println!("  Node ID:     {}", style(&key.public_hex[..2]).magenta());
+    println!();
+    for check in &report.checks {
+        let mark = if check.passed {
+            style("✓").green()
+        } else {
+            style("✗").red()
+        };
+        println!("  {} {:<20} {}", mark, check.name, check.detail);
+    }
+    println!();
+    if report.healthy {
+        println!("{} Key passed all checks", style("✓").green().bold());
+    } else {
+        println!(
+            "{} Key failed {} check(s)",
+            style("✗").red().bold(),
+            report.failures().count()
+        );
+    }
+}
+
+/// Kind of problem found in a key archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// `_public.txt` without a matching `_private.txt`
+    OrphanPublic,
+    /// `_private.txt` without a matching `_public.txt`
+    OrphanPrivate,
+    /// File does not contain a well-formed hex key
+    Malformed,
+    /// Private key derives a different public key than the one stored
+    Mismatch,
+    /// Same public key stored more than once
+    Duplicate,
+    /// Key fails the MeshCore health checks
+    Invalid,
+}
+
+/// A single problem with one key pair
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveIssue {
+    pub kind: IssueKind,
+    pub stem: String,
+    /// Run timestamp parsed from the filename, if it follows the `save_key` layout
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    pub detail: String,
+}
+
+/// Several distinct keys sharing the same node ID
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeIdCollision {
+    pub node_id: String,
+    pub stems: Vec<String>,
+}
+
+/// Machine-readable result of auditing a key archive
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveReport {
+    pub root: String,
+    pub pairs_scanned: usize,
+    pub keys_healthy: usize,
+    pub issues: Vec<ArchiveIssue>,
+    pub node_id_collisions: Vec<NodeIdCollision>,
+}
+
+impl ArchiveReport {
+    /// True when no pair has an issue (node-ID collisions are informational)
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Audit every key pair under `root`
+pub fn audit_archive(root: &Path) -> ArchiveReport {
+    let pairs = archive::scan_key_files(root);
+    let mut issues = Vec::new();
+    let mut keys_healthy = 0;
+    // public key -> first stem it was seen under
+    let mut seen: BTreeMap<String, String> = BTreeMap::new();
+    // node id -> stems of distinct keys
+    let mut node_ids: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for pair in &pairs {
+        let stem = pair.display_relative(root);
+        let Some(key) = audit_pair(pair, &stem, &mut issues) else {
+            continue;
+        };
+
+        if let Some(first) = seen.get(&key.public_hex) {
+            issues.push(ArchiveIssue {
+                kind: IssueKind::Duplicate,
+                stem: stem.clone(),
+                timestamp: pair.name.as_ref().map(|n| n.timestamp.clone()),
+                public_key: Some(key.public_hex.clone()),
+                detail: format!("Same public key as {}", first),
+            });
+            continue;
+        }
+        seen.insert(key.public_hex.clone(), stem.clone());
+        node_ids
+            .entry(key.public_hex[..2].to_string())
+            .or_default()
+            .push(stem.clone());
+
+        let report = keygen::check_key_health(&key);
+        if report.healthy {
+            keys_healthy += 1;
+        } else {
+            let reasons: Vec<String> = report
+                .failures()
+                .map(|c| format!("{}: {}", c.name, c.detail))
+                .collect();
+            issues.push(ArchiveIssue {
+                kind: IssueKind::Invalid,
+                stem,
+                timestamp: pair.name.as_ref().map(|n| n.timestamp.clone()),
+                public_key: Some(key.public_hex.clone()),
+                detail: reasons.join("; "),
+            });
+        }
+    }
+
+    let node_id_collisions = node_ids
+        .into_iter()
+        .filter(|(_, stems)| stems.len() > 1)
+        .map(|(node_id, stems)| NodeIdCollision { node_id, stems })
+        .collect();
+
+    ArchiveReport {
+        root: root.display().to_string(),
+        pairs_scanned: pairs.len(),
+        keys_healthy,
+        issues,
+        node_id_collisions,
+    }
+}
+
+/// Load one pair, recording orphan/malformed/mismatch issues
+///
+/// Returns the key only when both halves are present, well-formed and consistent.
+fn audit_pair(pair: &KeyFilePair, stem: &str, issues: &mut Vec<ArchiveIssue>) -> Option<KeyInfo> {
+    let mut issue = |kind: IssueKind, public_key: Option<String>, detail: &str| {
+        issues.push(ArchiveIssue {
+            kind,
+            stem: stem.to_string(),
+            timestamp: pair.name.as_ref().map(|n| n.timestamp.clone()),
+            public_key,
+            detail: detail.to_string(),
+        });
+    };
+
+    let public_hex = pair.public_path.as_deref().map(archive::read_public_key);
+    let private_hex = pair.private_path.as_deref().map(archive::read_private_key);
+
+    match (public_hex, private_hex) {
+        (Some(Some(public_hex)), None) => {
+            issue(
+                IssueKind::OrphanPublic,
+                Some(public_hex),
+                "Public key has no matching private key file",
+            );
+            None
+        }
+        (None, Some(_)) => {
+            issue(
+                IssueKind::OrphanPrivate,
+                None,
+                "Private key has no matching public key file",
+            );
+            None
+        }
+        (Some(Some(public_hex)), Some(Some(private_hex))) => {
+            let public_bytes = parse_public_hex(&public_hex).ok()?;
+            let private_bytes = parse_private_hex(&private_hex).ok()?;
+            let derived = keygen::keypair_from_private(&private_bytes);
+            if derived.public_bytes != public_bytes {
+                issue(
+                    IssueKind::Mismatch,
+                    Some(public_hex),
+                    &format!("Private key derives {}", derived.public_hex),
+                );
+                return None;
+            }
+            Some(derived)
+        }
+        (public_hex, private_hex) => {
+            let mut bad = Vec::new();
+            if matches!(public_hex, Some(None)) {
+                bad.push("public");
+            }
+            if matches!(private_hex, Some(None)) {
+                bad.push("private");
+            }
+            issue(
+                IssueKind::Malformed,
+                None,
+                &format!("Malformed {} key file", bad.join(" and ")),
+            );
+            None
+        }
+    }
+}
+
+/// Print an archive report in human-readable form
+pub fn print_archive_report(report: &ArchiveReport) {
+    println!("  Root:          {}", report.root);
+    println!("  Pairs scanned: {}", report.pairs_scanned);
+    println!("  Healthy keys:  {}", style(report.keys_healthy).green());
+    println!();
+
+    for issue in &report.issues {
+        println!(
+            "  {} {:<15} {}",
+            style("✗").red(),
+            format!("{:?}", issue.kind),
+            style(&issue.stem).dim()
+        );
+        println!("      {}", issue.detail);
+    }
+
+    if !report.node_id_collisions.is_empty() {
+        println!();
+        println!(
+            "{} {} node ID(s) shared by several keys:",
+            style("ℹ").blue(),
+            report.node_id_collisions.len()
+        );
+        for collision in &report.node_id_collisions {
+            println!(
+                "  {} {}",
+                style(&collision.node_id).magenta(),
+                collision.stems.join(", ")
+            );
+        }
+    }
+
+    println!();
+    if report.is_clean() {
+        println!("{} Archive has no issues", style("✓").green().bold());
+    } else {
+        println!(
+            "{} Archive has {} issue(s)",
+            style("✗").red().bold(),
+            report.issues.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_key_from_hex() {
+        let key = keygen::generate_from_seed(&[1u8; 32]);
+        let loaded = load_key(&key.private_hex, None).unwrap();
+        assert_eq!(loaded.public_hex, key.public_hex);
+    }
+
+    #[test]
+    fn test_load_key_uses_sibling_public_file() {
+        let dir = tempdir().unwrap();
+        let key = keygen::generate_from_seed(&[2u8; 32]);
+        let other = keygen::generate_from_seed(&[3u8; 32]);
+        let priv_path = dir.path().join("AB_1_20260101_000000_private.txt");
+        let pub_path = dir.path().join("AB_1_20260101_000000_public.txt");
+        fs::write(&priv_path, &key.private_hex).unwrap();
+        fs::write(&pub_path, &other.public_hex).unwrap();
+
+        let loaded = load_key(priv_path.to_str().unwrap(), None).unwrap();
+        assert_eq!(loaded.public_hex, other.public_hex);
+        assert!(!keygen::check_key_health(&loaded).healthy);
+    }
+
+    #[test]
+    fn test_load_signing_key_derives_the_public_key() {
+        let dir = tempdir().unwrap();
+        let key = keygen::generate_from_seed(&[2u8; 32]);
+        let other = keygen::generate_from_seed(&[3u8; 32]);
+        let priv_path = dir.path().join("AB_1_20260101_000000_private.txt");
+        let pub_path = dir.path().join("AB_1_20260101_000000_public.txt");
+        fs::write(&priv_path, &key.private_hex).unwrap();
+        let priv_arg = priv_path.to_str().unwrap();
+
+        assert_eq!(
+            load_signing_key(priv_arg).unwrap().public_hex,
+            key.public_hex
+        );
+        fs::write(&pub_path, &key.public_hex).unwrap();
+        assert_eq!(
+            load_signing_key(priv_arg).unwrap().public_hex,
+            key.public_hex
+        );
+        fs::write(&pub_path, &other.public_hex).unwrap();
+        assert!(load_signing_key(priv_arg).is_err());
+    }
+
+    #[test]
+    fn test_load_key_rejects_bad_length() {
+        assert!(load_key("abcd", None).is_err());
+        assert!(load_key("zz", None).is_err());
+    }
+
+    fn write_pair(dir: &Path, stem: &str, public_hex: &str, private_hex: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join(format!("{}_public.txt", stem)), public_hex).unwrap();
+        fs::write(dir.join(format!("{}_private.txt", stem)), private_hex).unwrap();
+    }
+
+    fn usable_key(seed: u8) -> KeyInfo {
+        (seed..=255)
+            .map(|i| keygen::generate_from_seed(&[i; 32]))
+            .find(|k| keygen::is_valid_meshcore_prefix(&k.public_bytes))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_audit_archive_finds_issues() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let run1 = root.join("20260101_000000");
+        let run2 = root.join("20260102_000000");
+
+        let good = usable_key(10);
+        let other = usable_key(40);
+        write_pair(
+            &run1,
+            "A_1_20260101_000000",
+            &good.public_hex,
+            &good.private_hex,
+        );
+        // Same key saved again in a later run
+        write_pair(
+            &run2,
+            "A_1_20260102_000000",
+            &good.public_hex,
+            &good.private_hex,
+        );
+        // Private key belongs to a different public key
+        write_pair(
+            &run2,
+            "B_2_20260102_000000",
+            &other.public_hex,
+            &good.private_hex,
+        );
+        // Orphans and garbage
+        fs::write(
+            run2.join("C_3_20260102_000000_public.txt"),
+            &other.public_hex,
+        )
+        .unwrap();
+        fs::write(
+            run2.join("D_4_20260102_000000_private.txt"),
+            &other.private_hex,
+        )
+        .unwrap();
+        write_pair(&run2, "E_5_20260102_000000", "nothex", &other.private_hex);
+
+        let report = audit_archive(root);
+        assert_eq!(report.pairs_scanned, 6);
+        assert_eq!(report.keys_healthy, 1);
+
+        let kinds: Vec<IssueKind> = report.issues.iter().map(|i| i.kind).collect();
+        let duplicate = report
+            .issues
+            .iter()
+            .find(|i| i.kind == IssueKind::Duplicate)
+            .unwrap();
+        assert_eq!(duplicate.timestamp.as_deref(), Some("20260102_000000"));
+        assert!(kinds.contains(&IssueKind::Duplicate));
+        assert!(kinds.contains(&IssueKind::Mismatch));
+        assert!(kinds.contains(&IssueKind::OrphanPublic));
+        assert!(kinds.contains(&IssueKind::OrphanPrivate));
+        assert!(kinds.contains(&IssueKind::Malformed));
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn test_audit_archive_flags_invalid_and_collisions() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+
+        // Find two healthy keys with the same node ID
+        let mut by_node: BTreeMap<u8, KeyInfo> = BTreeMap::new();
+        let (a, b) = (0u16..)
+            .map(|i| {
+                let mut seed = [0u8; 32];
+                seed[..2].copy_from_slice(&i.to_le_bytes());
+                keygen::generate_from_seed(&seed)
+            })
+            .filter(|k| keygen::is_valid_meshcore_prefix(&k.public_bytes))
+            .find_map(|k| {
+                by_node
+                    .insert(k.public_bytes[0], k.clone())
+                    .map(|prev| (prev, k))
+            })
+            .unwrap();
+        write_pair(root, "A_1_20260101_000000", &a.public_hex, &a.private_hex);
+        write_pair(root, "B_2_20260101_000000", &b.public_hex, &b.private_hex);
+
+        // A consistent pair whose node ID is reserved
+        let reserved = (0u16..)
+            .map(|i| {
+                let mut seed = [1u8; 32];
+                seed[..2].copy_from_slice(&i.to_le_bytes());
+                keygen::generate_from_seed(&seed)
+            })
+            .find(|k| k.public_bytes[0] == 0x00 || k.public_bytes[0] == 0xFF)
+            .unwrap();
+        write_pair(
+            root,
+            "C_3_20260101_000000",
+            &reserved.public_hex,
+            &reserved.private_hex,
+        );
+
+        let report = audit_archive(root);
+        assert_eq!(report.node_id_collisions.len(), 1);
+        assert_eq!(report.node_id_collisions[0].stems.len(), 2);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, IssueKind::Invalid);
+        assert!(report.issues[0].detail.contains("node_id"));
+    }
+}
@@ -0,0 +1,299 @@
+//! Tamper-Evident Audit Log
+//!
+//! Optional append-only JSON-lines log with one entry per accepted key. Each
+//! entry carries the SHA-256 of the previous entry, so editing, removing or
+//! reordering lines breaks the chain. Entries can additionally be signed with
+//! an operator Ed25519 key using `keygen::sign_message`.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::keygen::{self, KeyInfo};
+
+/// `prev_hash` of the first entry in a log
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// The hashed part of an entry (field order is the canonical encoding)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct EntryBody {
+    seq: u64,
+    timestamp: String,
+    host: String,
+    public_key: String,
+    pattern: String,
+    prev_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signer: Option<String>,
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    #[serde(flatten)]
+    body: EntryBody,
+    pub hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl EntryBody {
+    fn hash(&self) -> [u8; 32] {
+        Sha256::digest(serde_json::to_vec(self).unwrap()).into()
+    }
+}
+
+/// Append handle for an audit log file
+pub struct AuditLog {
+    path: PathBuf,
+    host: String,
+    next_seq: u64,
+    prev_hash: String,
+    signer: Option<KeyInfo>,
+}
+
+impl AuditLog {
+    /// Open (or create) a log, resuming the chain from its last entry
+    ///
+    /// The existing chain is verified first so new entries are never appended
+    /// to a log that has already been tampered with.
+    pub fn open(path: &Path, signer: Option<KeyInfo>) -> Result<Self, String> {
+        let (next_seq, prev_hash) = if path.exists() {
+            let verification = verify_log(path, None)?;
+            if let Some(error) = verification.errors.first() {
+                return Err(format!(
+                    "Refusing to append to broken audit log {}: {}",
+                    path.display(),
+                    error
+                ));
+            }
+            (verification.entries, verification.head_hash)
+        } else {
+            (0, GENESIS_HASH.to_string())
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            host: sysinfo::System::host_name().unwrap_or_else(|| "unknown".to_string()),
+            next_seq,
+            prev_hash,
+            signer,
+        })
+    }
+
+    /// Record a generated key
+    pub fn append(&mut self, public_hex: &str, pattern: &str) -> Result<(), String> {
+        let body = EntryBody {
+            seq: self.next_seq,
+            timestamp: chrono::Local::now().to_rfc3339(),
+            host: self.host.clone(),
+            public_key: public_hex.to_string(),
+            pattern: pattern.to_string(),
+            prev_hash: self.prev_hash.clone(),
+            signer: self.signer.as_ref().map(|s| s.public_hex.clone()),
+        };
+        let hash = body.hash();
+        let signature = self.signer.as_ref().map(|s| {
+            hex::encode(keygen::sign_message(
+                &s.private_bytes,
+                &s.public_bytes,
+                &hash,
+            ))
+        });
+        let entry = LogEntry {
+            body,
+            hash: hex::encode(hash),
+            signature,
+        };
+
+        let mut line = serde_json::to_string(&entry).unwrap();
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+        file.write_all(line.as_bytes())
+            .and_then(|_| file.sync_data())
+            .map_err(|e| format!("Failed to append to {}: {}", self.path.display(), e))?;
+
+        self.next_seq += 1;
+        self.prev_hash = entry.hash;
+        Ok(())
+    }
+}
+
+/// Result of checking an audit log
+#[derive(Debug, Clone, Serialize)]
+pub struct LogVerification {
+    pub entries: u64,
+    pub signed_entries: u64,
+    /// Hash of the last entry (the value the next entry must chain to)
+    pub head_hash: String,
+    pub errors: Vec<String>,
+}
+
+/// Check every link, hash and signature in a log
+///
+/// With `expected_signer`, every entry must also be signed by that key.
+pub fn verify_log(path: &Path, expected_signer: Option<&str>) -> Result<LogVerification, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let mut result = LogVerification {
+        entries: 0,
+        signed_entries: 0,
+        head_hash: GENESIS_HASH.to_string(),
+        errors: Vec::new(),
+    };
+
+    for (line_no, line) in content.lines().enumerate() {
+        let line_no = line_no + 1;
+        let entry: LogEntry = match serde_json::from_str(line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                result
+                    .errors
+                    .push(format!("line {}: unreadable entry ({})", line_no, e));
+                break;
+            }
+        };
+
+        if entry.body.seq != result.entries {
+            result.errors.push(format!(
+                "line {}: sequence {} where {} was expected",
+                line_no, entry.body.seq, result.entries
+            ));
+        }
+        if entry.body.prev_hash != result.head_hash {
+            result.errors.push(format!(
+                "line {}: chain broken (prev_hash mismatch)",
+                line_no
+            ));
+        }
+        let hash = entry.body.hash();
+        if hex::encode(hash) != entry.hash {
+            result.errors.push(format!(
+                "line {}: entry hash does not match contents",
+                line_no
+            ));
+        }
+
+        match (&entry.body.signer, &entry.signature) {
+            (Some(signer), Some(signature)) => {
+                let valid = crate::audit::parse_public_hex(signer)
+                    .ok()
+                    .zip(
+                        hex::decode(signature)
+                            .ok()
+                            .and_then(|s| <[u8; 64]>::try_from(s).ok()),
+                    )
+                    .map(|(public, sig)| keygen::verify_signature(&public, &hash, &sig))
+                    .unwrap_or(false);
+                if valid {
+                    result.signed_entries += 1;
+                } else {
+                    result
+                        .errors
+                        .push(format!("line {}: invalid signature", line_no));
+                }
+            }
+            (None, None) => {}
+            _ => result.errors.push(format!(
+                "line {}: signer and signature must both be set",
+                line_no
+            )),
+        }
+
+        if let Some(expected) = expected_signer {
+            if entry.body.signer.as_deref() != Some(&expected.to_lowercase()) {
+                result.errors.push(format!(
+                    "line {}: not signed by the expected operator key",
+                    line_no
+                ));
+            }
+        }
+
+        result.entries += 1;
+        result.head_hash = entry.hash;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_chain_appends_across_reopen() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+
+        let mut log = AuditLog::open(&path, None).unwrap();
+        log.append(&"ab".repeat(32), "Prefix 'AB'").unwrap();
+        drop(log);
+
+        let mut log = AuditLog::open(&path, None).unwrap();
+        log.append(&"cd".repeat(32), "Prefix 'CD'").unwrap();
+
+        let result = verify_log(&path, None).unwrap();
+        assert_eq!(result.entries, 2);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+    }
+
+    #[test]
+    fn test_detects_edited_and_removed_entries() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let mut log = AuditLog::open(&path, None).unwrap();
+        for i in 0..3u8 {
+            log.append(&format!("{:02x}", i + 1).repeat(32), "p")
+                .unwrap();
+        }
+
+        let original = fs::read_to_string(&path).unwrap();
+
+        // Swap the public key of the middle entry
+        let edited = original.replacen(&"02".repeat(32), &"99".repeat(32), 1);
+        fs::write(&path, edited).unwrap();
+        assert!(!verify_log(&path, None).unwrap().errors.is_empty());
+
+        // Drop the middle entry entirely
+        let lines: Vec<&str> = original.lines().collect();
+        fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        let result = verify_log(&path, None).unwrap();
+        assert!(result.errors.iter().any(|e| e.contains("chain broken")));
+
+        // A broken log cannot be appended to
+        assert!(AuditLog::open(&path, None).is_err());
+    }
+
+    #[test]
+    fn test_signed_entries() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let operator = keygen::generate_from_seed(&[11u8; 32]);
+        let other = keygen::generate_from_seed(&[12u8; 32]);
+
+        let mut log = AuditLog::open(&path, Some(operator.clone())).unwrap();
+        log.append(&"ab".repeat(32), "p").unwrap();
+        log.append(&"cd".repeat(32), "p").unwrap();
+
+        let result = verify_log(&path, Some(&operator.public_hex)).unwrap();
+        assert_eq!(result.signed_entries, 2);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let result = verify_log(&path, Some(&other.public_hex)).unwrap();
+        assert_eq!(result.errors.len(), 2);
+
+        // Re-signing with a different key after editing is caught by the signer check
+        let mut forged = AuditLog::open(&path, Some(other)).unwrap();
+        forged.append(&"ef".repeat(32), "p").unwrap();
+        let result = verify_log(&path, Some(&operator.public_hex)).unwrap();
+        assert_eq!(result.errors.len(), 1);
+    }
+}
@@ -0,0 +1,178 @@
+//! Key Blocklist
+//!
+//! `--blocklist FILE` rejects matching keys before they are accepted, after
+//! MeshCore validation. One entry per line, `#` starts a comment:
+//!
+//! ```text
+//! DEADBEEF        # substring anywhere in the key
+//! b00b            # words are transliterated: O→0, I/L→1, Z→2, S→5, G→6, T→7
+//! prefix:00       # key starts with
+//! node:FF         # node ID (first byte) reserved, e.g. for gateways
+//! node:F0-FE      # a range of node IDs
+//! ```
+
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+use crate::dictionary::to_hex;
+use crate::keygen::KeyInfo;
+
+/// Why a key was blocked
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockReason {
+    Contains(String),
+    Prefix(String),
+    NodeId(u8),
+}
+
+impl fmt::Display for BlockReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Contains(hex) => write!(f, "contains {}", hex),
+            Self::Prefix(hex) => write!(f, "starts with {}", hex),
+            Self::NodeId(id) => write!(f, "node ID {:02X} is reserved", id),
+        }
+    }
+}
+
+/// Forbidden substrings, prefixes and node IDs
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Blocklist {
+    substrings: Vec<String>,
+    prefixes: Vec<String>,
+    /// Inclusive node ID ranges
+    node_ids: Vec<(u8, u8)>,
+}
+
+impl Blocklist {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read blocklist {}: {}", path.display(), e))?;
+        Self::parse(&content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut blocklist = Self::default();
+        for (number, line) in content.lines().enumerate() {
+            let entry = line.split('#').next().unwrap_or("").trim();
+            if entry.is_empty() {
+                continue;
+            }
+            let hex = |word: &str| {
+                to_hex(word.trim(), true)
+                    .filter(|hex| !hex.is_empty())
+                    .ok_or_else(|| format!("line {}: '{}' is not hex", number + 1, word))
+            };
+            match entry.split_once(':') {
+                Some(("prefix", word)) => blocklist.prefixes.push(hex(word)?),
+                Some(("node", range)) => {
+                    let byte = |s: &str| {
+                        u8::from_str_radix(s.trim(), 16).map_err(|_| {
+                            format!("line {}: invalid node ID '{}'", number + 1, s.trim())
+                        })
+                    };
+                    let (lo, hi) = match range.split_once('-') {
+                        Some((lo, hi)) => (byte(lo)?, byte(hi)?),
+                        None => (byte(range)?, byte(range)?),
+                    };
+                    if lo > hi {
+                        return Err(format!("line {}: empty node range '{}'", number + 1, range));
+                    }
+                    blocklist.node_ids.push((lo, hi));
+                }
+                Some((kind, _)) => {
+                    return Err(format!(
+                        "line {}: unknown entry type '{}' (expected prefix or node)",
+                        number + 1,
+                        kind
+                    ))
+                }
+                None => blocklist.substrings.push(hex(entry)?),
+            }
+        }
+        Ok(blocklist)
+    }
+
+    /// Number of entries
+    pub fn entry_count(&self) -> usize {
+        self.substrings.len() + self.prefixes.len() + self.node_ids.len()
+    }
+
+    /// Whether every key with node ID `id` is blocked, by a node range or a
+    /// prefix of at most two characters
+    pub fn blocks_node_id(&self, id: u8) -> bool {
+        let hex = format!("{:02X}", id);
+        self.node_ids
+            .iter()
+            .any(|&(lo, hi)| (lo..=hi).contains(&id))
+            || self
+                .prefixes
+                .iter()
+                .any(|p| p.len() <= 2 && hex.starts_with(p.as_str()))
+    }
+
+    /// The first rule `key` breaks, if any
+    pub fn check(&self, key: &KeyInfo) -> Option<BlockReason> {
+        let node_id = key.public_bytes[0];
+        if self
+            .node_ids
+            .iter()
+            .any(|&(lo, hi)| (lo..=hi).contains(&node_id))
+        {
+            return Some(BlockReason::NodeId(node_id));
+        }
+        let hex = key.public_hex.to_uppercase();
+        if let Some(prefix) = self.prefixes.iter().find(|p| hex.starts_with(p.as_str())) {
+            return Some(BlockReason::Prefix(prefix.clone()));
+        }
+        self.substrings
+            .iter()
+            .find(|s| hex.contains(s.as_str()))
+            .map(|s| BlockReason::Contains(s.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(hex: &str) -> KeyInfo {
+        KeyInfo {
+            public_hex: hex.to_lowercase(),
+            private_hex: String::new(),
+            public_bytes: crate::pattern::key_bytes(hex),
+            private_bytes: [0; 64],
+        }
+    }
+
+    #[test]
+    fn test_blocklist_rules() {
+        let blocklist =
+            Blocklist::parse("# reserved\nb00b\nprefix:00 # low\nnode:F0-FE\nnode:42\n\n").unwrap();
+        assert_eq!(blocklist.entry_count(), 4);
+
+        let check = |hex: String| blocklist.check(&key(&hex)).map(|r| r.to_string());
+        assert_eq!(check(format!("AB{}", "1".repeat(62))), None);
+        assert_eq!(
+            check(format!("AB{}B00B", "1".repeat(58))),
+            Some("contains B00B".to_string())
+        );
+        assert_eq!(
+            check(format!("00{}", "1".repeat(62))),
+            Some("starts with 00".to_string())
+        );
+        assert_eq!(
+            check(format!("F7{}", "1".repeat(62))),
+            Some("node ID F7 is reserved".to_string())
+        );
+        assert!(check(format!("42{}", "1".repeat(62))).is_some());
+        assert_eq!(check(format!("FF{}", "1".repeat(62))), None);
+        assert!(blocklist.blocks_node_id(0x00) && blocklist.blocks_node_id(0xF7));
+        assert!(!blocklist.blocks_node_id(0xAB) && !blocklist.blocks_node_id(0xFF));
+
+        for bad in ["rude", "node:GG", "node:FF-10", "suffix:AB", "prefix:"] {
+            assert!(Blocklist::parse(bad).is_err(), "{}", bad);
+        }
+    }
+}
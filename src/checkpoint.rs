@@ -0,0 +1,187 @@
+//! Search Checkpoints
+//!
+//! `--checkpoint FILE` records a search's progress (keys found, attempts and
+//! active time) so a long job can be stopped and started again, e.g. across
+//! several nights of a `--schedule`. Restarting with the same file and pattern
+//! continues toward the same target: earlier keys count, are not found again,
+//! and earlier attempts and active time carry into `--max-time` and the stats.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+use crate::blocklist::Blocklist;
+use crate::pattern::PatternConfig;
+use crate::plugin::Plugin;
+
+/// Current checkpoint format
+const CHECKPOINT_VERSION: u32 = 2;
+
+/// Progress of one search
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Checkpoint {
+    pub version: u32,
+    /// `PatternConfig::description()` of the search
+    pub pattern: String,
+    /// `inputs_digest` of the search; the description alone only gives a
+    /// wordlist's size, so an edited wordlist would otherwise look the same
+    pub inputs: String,
+    pub target_keys: usize,
+    /// Public keys found so far
+    pub keys: Vec<String>,
+    pub total_attempts: u64,
+    /// Time spent searching, excluding pauses
+    pub active_seconds: f64,
+    pub updated: String,
+}
+
+impl Checkpoint {
+    /// A checkpoint with no progress yet
+    pub fn new(pattern: &str, inputs: &str, target_keys: usize) -> Self {
+        Self {
+            version: CHECKPOINT_VERSION,
+            pattern: pattern.to_string(),
+            inputs: inputs.to_string(),
+            target_keys,
+            keys: Vec::new(),
+            total_attempts: 0,
+            active_seconds: 0.0,
+            updated: chrono::Local::now().to_rfc3339(),
+        }
+    }
+
+    /// Load `path` if it exists, or start fresh
+    ///
+    /// An existing checkpoint must belong to the same pattern, inputs and target.
+    pub fn load_or_new(
+        path: &Path,
+        pattern: &str,
+        inputs: &str,
+        target_keys: usize,
+    ) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::new(pattern, inputs, target_keys));
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let checkpoint: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid checkpoint {}: {}", path.display(), e))?;
+
+        if checkpoint.version != CHECKPOINT_VERSION {
+            return Err(format!(
+                "Unsupported checkpoint version {} in {}",
+                checkpoint.version,
+                path.display()
+            ));
+        }
+        if checkpoint.pattern != pattern || checkpoint.target_keys != target_keys {
+            return Err(format!(
+                "Checkpoint {} belongs to a different search ({}, {} key(s))",
+                path.display(),
+                checkpoint.pattern,
+                checkpoint.target_keys
+            ));
+        }
+        if checkpoint.inputs != inputs {
+            return Err(format!(
+                "Checkpoint {} was made with a different wordlist, blocklist or plugin",
+                path.display()
+            ));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Keys still to find
+    pub fn remaining(&self) -> usize {
+        self.target_keys.saturating_sub(self.keys.len())
+    }
+
+    /// Write atomically (temporary file, then rename) so an interrupted save
+    /// never leaves a truncated checkpoint behind
+    pub fn save(&mut self, path: &Path) -> Result<(), String> {
+        self.updated = chrono::Local::now().to_rfc3339();
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self).unwrap())
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| format!("Failed to write checkpoint {}: {}", path.display(), e))
+    }
+}
+
+/// SHA-256 over everything that decides which keys match: the full pattern
+/// (dictionary words included), the blocklist and the plugin with its fuel
+pub fn inputs_digest(
+    pattern: &PatternConfig,
+    blocklist: Option<&Blocklist>,
+    plugin: Option<&Plugin>,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(pattern).unwrap());
+    hasher.update(serde_json::to_vec(&blocklist).unwrap());
+    if let Some(plugin) = plugin {
+        hasher.update(plugin.digest());
+        hasher.update(plugin.fuel().to_le_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resume_and_mismatch() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("search.checkpoint");
+
+        let mut checkpoint = Checkpoint::load_or_new(&path, "Prefix 'AB'", "abc", 3).unwrap();
+        assert_eq!(checkpoint.remaining(), 3);
+        checkpoint.keys.push("ab".repeat(32));
+        checkpoint.total_attempts = 1234;
+        checkpoint.active_seconds = 60.5;
+        checkpoint.save(&path).unwrap();
+
+        let resumed = Checkpoint::load_or_new(&path, "Prefix 'AB'", "abc", 3).unwrap();
+        assert_eq!(resumed.remaining(), 2);
+        assert_eq!(resumed.total_attempts, 1234);
+        assert!(!path.with_extension("tmp").exists());
+
+        assert!(Checkpoint::load_or_new(&path, "Prefix 'CD'", "abc", 3).is_err());
+        assert!(Checkpoint::load_or_new(&path, "Prefix 'AB'", "abc", 5).is_err());
+        assert!(Checkpoint::load_or_new(&path, "Prefix 'AB'", "def", 3).is_err());
+    }
+
+    #[test]
+    fn test_inputs_digest_covers_words_and_blocklist() {
+        use crate::dictionary::{parse_wordlist, Anchor, Dictionary};
+        use crate::pattern::PatternMode;
+
+        let dictionary = |words: &str| PatternConfig {
+            mode: PatternMode::Dictionary,
+            dictionary: Some(Dictionary::new(
+                parse_wordlist(words, false, 3),
+                Anchor::Start,
+            )),
+            ..PatternConfig::default()
+        };
+        let cafe = dictionary("cafe\nbeef");
+        let face = dictionary("face\nbeef");
+        // Same word count and anchor, so the same description
+        assert_eq!(cafe.description(), face.description());
+        assert_ne!(
+            inputs_digest(&cafe, None, None),
+            inputs_digest(&face, None, None)
+        );
+
+        let blocklist = Blocklist::parse("dead").unwrap();
+        assert_ne!(
+            inputs_digest(&cafe, None, None),
+            inputs_digest(&cafe, Some(&blocklist), None)
+        );
+        assert_eq!(
+            inputs_digest(&cafe, Some(&blocklist), None),
+            inputs_digest(&cafe, Some(&Blocklist::parse("dead # note").unwrap()), None)
+        );
+    }
+}
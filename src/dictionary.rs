@@ -0,0 +1,402 @@
+//! Hexspeak Dictionary Search
+//!
+//! `--dictionary FILE` accepts any key that starts or ends with a word from a
+//! wordlist instead of one fixed prefix. Words are uppercased and, with
+//! `--leet`, transliterated into hex digits (O→0, I/L→1, Z→2, S→5, G→6, T→7);
+//! words that still contain non-hex letters, or are shorter than
+//! `--min-word-len`, are dropped.
+//!
+//! All words are compiled into two nibble tries, one read from the start of
+//! the key and one (of the reversed words) read from the end, so a key is
+//! checked against the whole list by walking at most the longest word's
+//! length of nibbles from each end.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::pattern::{key_nibble, KEY_NIBBLES};
+
+/// Where in the key a word may appear (`--dict-anchor`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Anchor {
+    Start,
+    End,
+    Both,
+}
+
+/// Where a word was found
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WordPosition {
+    Start,
+    End,
+}
+
+/// One usable word
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DictWord {
+    /// The word as written in the wordlist
+    pub word: String,
+    /// Hex digits searched for
+    pub hex: String,
+}
+
+/// A word found in a key
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct WordMatch {
+    pub word: String,
+    pub hex: String,
+    pub position: WordPosition,
+    /// Hex character offset of the word in the key
+    pub offset: usize,
+}
+
+impl std::fmt::Display for WordMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let position = match self.position {
+            WordPosition::Start => "start",
+            WordPosition::End => "end",
+        };
+        if self.word.eq_ignore_ascii_case(&self.hex) {
+            write!(f, "{} at {}", self.hex, position)
+        } else {
+            write!(f, "{} ({}) at {}", self.hex, self.word, position)
+        }
+    }
+}
+
+/// Multi-word nibble automaton, anchored at one end of the key
+#[derive(Clone, Debug)]
+struct NibbleTrie {
+    /// Child node per nibble; 0 means none (the root is never a child)
+    children: Vec<[u32; 16]>,
+    /// Word ending at each node
+    terminal: Vec<Option<u32>>,
+    depth: usize,
+}
+
+impl NibbleTrie {
+    fn new() -> Self {
+        Self {
+            children: vec![[0; 16]],
+            terminal: vec![None],
+            depth: 0,
+        }
+    }
+
+    fn insert(&mut self, nibbles: impl Iterator<Item = u8>, word: u32) {
+        let mut node = 0;
+        let mut len = 0;
+        for nibble in nibbles {
+            let next = self.children[node][nibble as usize];
+            node = if next == 0 {
+                self.children.push([0; 16]);
+                self.terminal.push(None);
+                let child = self.children.len() - 1;
+                self.children[node][nibble as usize] = child as u32;
+                child
+            } else {
+                next as usize
+            };
+            len += 1;
+        }
+        self.terminal[node].get_or_insert(word);
+        self.depth = self.depth.max(len);
+    }
+
+    /// Longest word along `nibble(0), nibble(1), ...`
+    #[inline(always)]
+    fn longest(&self, nibble: impl Fn(usize) -> u8) -> Option<u32> {
+        let mut node = 0;
+        let mut found = None;
+        for i in 0..self.depth {
+            node = self.children[node][nibble(i) as usize] as usize;
+            if node == 0 {
+                break;
+            }
+            if let Some(word) = self.terminal[node] {
+                found = Some(word);
+            }
+        }
+        found
+    }
+}
+
+/// Serialized form: the words only, tries are rebuilt on load
+#[derive(Serialize, Deserialize)]
+struct DictionaryWords {
+    words: Vec<DictWord>,
+    anchor: Anchor,
+}
+
+/// Compiled wordlist
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "DictionaryWords", into = "DictionaryWords")]
+pub struct Dictionary {
+    words: Vec<DictWord>,
+    anchor: Anchor,
+    start: NibbleTrie,
+    end: NibbleTrie,
+}
+
+impl TryFrom<DictionaryWords> for Dictionary {
+    type Error = String;
+
+    /// Word spellings are normalized to uppercase, and anything that is not
+    /// 1-64 hex digits is rejected before the tries are built
+    fn try_from(d: DictionaryWords) -> Result<Self, Self::Error> {
+        let words = d
+            .words
+            .into_iter()
+            .map(|w| {
+                match to_hex(&w.hex, false).filter(|h| !h.is_empty() && h.len() <= KEY_NIBBLES) {
+                    Some(hex) => Ok(DictWord { word: w.word, hex }),
+                    None => Err(format!("'{}' is not 1-{} hex digits", w.hex, KEY_NIBBLES)),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new(words, d.anchor))
+    }
+}
+
+impl From<Dictionary> for DictionaryWords {
+    fn from(d: Dictionary) -> Self {
+        Self {
+            words: d.words,
+            anchor: d.anchor,
+        }
+    }
+}
+
+impl Dictionary {
+    /// Compile `words`, whose `hex` must be uppercase hex digits
+    pub fn new(words: Vec<DictWord>, anchor: Anchor) -> Self {
+        let mut start = NibbleTrie::new();
+        let mut end = NibbleTrie::new();
+        for (i, w) in words.iter().enumerate() {
+            let nibbles: Vec<u8> = w.hex.bytes().map(hex_nibble).collect();
+            if anchor != Anchor::End {
+                start.insert(nibbles.iter().copied(), i as u32);
+            }
+            if anchor != Anchor::Start {
+                end.insert(nibbles.iter().rev().copied(), i as u32);
+            }
+        }
+        Self {
+            words,
+            anchor,
+            start,
+            end,
+        }
+    }
+
+    /// Load a wordlist (one word per line, `#` comments)
+    pub fn load(path: &Path, leet: bool, min_len: usize, anchor: Anchor) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read wordlist {}: {}", path.display(), e))?;
+        let words = parse_wordlist(&content, leet, min_len);
+        if words.is_empty() {
+            return Err(format!(
+                "No usable words in {}: words must spell at least {} hex characters{}",
+                path.display(),
+                min_len,
+                if leet { "" } else { " (try --leet)" }
+            ));
+        }
+        Ok(Self::new(words, anchor))
+    }
+
+    pub fn words(&self) -> &[DictWord] {
+        &self.words
+    }
+
+    pub fn anchor(&self) -> Anchor {
+        self.anchor
+    }
+
+    /// Longest word at an allowed end of the key; the start wins ties
+    #[inline(always)]
+    fn find(&self, nibble: impl Fn(usize) -> u8) -> Option<(u32, WordPosition)> {
+        let start = self.start.longest(&nibble);
+        let end = self.end.longest(|i| nibble(KEY_NIBBLES - 1 - i));
+        match (start, end) {
+            (Some(s), Some(e))
+                if self.words[e as usize].hex.len() > self.words[s as usize].hex.len() =>
+            {
+                Some((e, WordPosition::End))
+            }
+            (Some(s), _) => Some((s, WordPosition::Start)),
+            (None, Some(e)) => Some((e, WordPosition::End)),
+            (None, None) => None,
+        }
+    }
+
+    /// Hot path check on raw public key bytes
+    #[inline(always)]
+    pub fn matches_bytes(&self, public_bytes: &[u8; 32]) -> bool {
+        self.find(|i| key_nibble(public_bytes, i)).is_some()
+    }
+
+    /// Hex length of the longest word at an allowed end (0 if none)
+    #[inline(always)]
+    pub fn match_len(&self, public_bytes: &[u8; 32]) -> usize {
+        self.find(|i| key_nibble(public_bytes, i))
+            .map_or(0, |(index, _)| self.words[index as usize].hex.len())
+    }
+
+    /// The word a key (as hex) matched, for reporting
+    pub fn word_match(&self, hex: &str) -> Option<WordMatch> {
+        let hex = hex.as_bytes();
+        if hex.len() != KEY_NIBBLES || !hex.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+        let (index, position) = self.find(|i| hex_nibble(hex[i].to_ascii_uppercase()))?;
+        let word = &self.words[index as usize];
+        Some(WordMatch {
+            word: word.word.clone(),
+            hex: word.hex.clone(),
+            position,
+            offset: match position {
+                WordPosition::Start => 0,
+                WordPosition::End => KEY_NIBBLES - word.hex.len(),
+            },
+        })
+    }
+
+    /// Chance that a random key matches any word
+    pub fn probability(&self) -> f64 {
+        let sides = if self.anchor == Anchor::Both {
+            2.0
+        } else {
+            1.0
+        };
+        let p: f64 = self
+            .words
+            .iter()
+            .map(|w| 16.0_f64.powi(-(w.hex.len() as i32)))
+            .sum();
+        (p * sides).min(1.0)
+    }
+}
+
+/// Usable words of a wordlist, deduplicated by their hex spelling
+pub fn parse_wordlist(content: &str, leet: bool, min_len: usize) -> Vec<DictWord> {
+    let mut seen = HashSet::new();
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|word| {
+            let hex = to_hex(word, leet)?;
+            (hex.len() >= min_len.max(1) && hex.len() <= KEY_NIBBLES).then(|| DictWord {
+                word: word.to_string(),
+                hex,
+            })
+        })
+        .filter(|w| seen.insert(w.hex.clone()))
+        .collect()
+}
+
+/// Spell a word in uppercase hex digits, transliterating letters with `leet`
+pub fn to_hex(word: &str, leet: bool) -> Option<String> {
+    word.chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            c @ ('0'..='9' | 'A'..='F') => Some(c),
+            'O' if leet => Some('0'),
+            'I' | 'L' if leet => Some('1'),
+            'Z' if leet => Some('2'),
+            'S' if leet => Some('5'),
+            'G' if leet => Some('6'),
+            'T' if leet => Some('7'),
+            _ => None,
+        })
+        .collect()
+}
+
+#[inline(always)]
+fn hex_nibble(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        _ => c - b'A' + 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::key_bytes;
+
+    #[test]
+    fn test_wordlist_transliteration_and_filtering() {
+        let list = "# fun words\ncoffee\nBADA55\ndeadbeef\nbee\nhello\nC0FFEE\n\nsalt\n";
+
+        let plain: Vec<String> = parse_wordlist(list, false, 4)
+            .into_iter()
+            .map(|w| w.hex)
+            .collect();
+        assert_eq!(plain, vec!["BADA55", "DEADBEEF", "C0FFEE"]);
+
+        let leet = parse_wordlist(list, true, 4);
+        let hex: Vec<&str> = leet.iter().map(|w| w.hex.as_str()).collect();
+        // "C0FFEE" duplicates "coffee"; "hello" has an H
+        assert_eq!(hex, vec!["C0FFEE", "BADA55", "DEADBEEF", "5A17"]);
+        assert_eq!(leet[0].word, "coffee");
+    }
+
+    #[test]
+    fn test_matches_longest_word_at_either_end() {
+        let words = parse_wordlist("dead\ndeadbeef\nc0ffee\n", false, 4);
+        let dictionary = Dictionary::new(words.clone(), Anchor::Both);
+
+        let start = format!("DEADBEEF{}", "1".repeat(56));
+        assert!(dictionary.matches_bytes(&key_bytes(&start)));
+        let m = dictionary.word_match(&start).unwrap();
+        assert_eq!(
+            (m.hex.as_str(), m.position, m.offset),
+            ("DEADBEEF", WordPosition::Start, 0)
+        );
+
+        let end = format!("{}c0ffee", "1".repeat(58));
+        assert!(dictionary.matches_bytes(&key_bytes(&end)));
+        let m = dictionary.word_match(&end).unwrap();
+        assert_eq!((m.position, m.offset), (WordPosition::End, 58));
+        assert_eq!(m.to_string(), "C0FFEE at end");
+
+        let neither = format!("1DEAD{}", "1".repeat(59));
+        assert!(!dictionary.matches_bytes(&key_bytes(&neither)));
+
+        let start_only = Dictionary::new(words, Anchor::Start);
+        assert!(!start_only.matches_bytes(&key_bytes(&end)));
+    }
+
+    #[test]
+    fn test_serde_round_trip_rebuilds_tries() {
+        let dictionary = Dictionary::new(parse_wordlist("coffee", true, 4), Anchor::End);
+        let json = serde_json::to_string(&dictionary).unwrap();
+        let loaded: Dictionary = serde_json::from_str(&json).unwrap();
+
+        let key = format!("{}C0FFEE", "0".repeat(58));
+        assert!(loaded.matches_bytes(&key_bytes(&key)));
+        assert_eq!(
+            loaded.word_match(&key).unwrap().to_string(),
+            "C0FFEE (coffee) at end"
+        );
+        assert!((loaded.probability() - 16f64.powi(-6)).abs() < 1e-12);
+
+        // Hand-edited spellings are normalized or rejected, never trusted
+        let lower = json.replace("C0FFEE", "c0ffee");
+        let loaded: Dictionary = serde_json::from_str(&lower).unwrap();
+        assert_eq!(loaded.words()[0].hex, "C0FFEE");
+        for bad in ["C0FFEX", "", &"A".repeat(65)] {
+            let json = json.replace("C0FFEE", bad);
+            assert!(
+                serde_json::from_str::<Dictionary>(&json).is_err(),
+                "{}",
+                bad
+            );
+        }
+    }
+}
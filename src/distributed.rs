@@ -0,0 +1,972 @@
+//! Distributed Search over TCP
+//!
+//! A coordinator (a normal search started with `--coordinator <ADDR>`) hands
+//! out disjoint counter ranges to agents on other machines. Agents derive each
+//! candidate deterministically as `SHA-256(run_key || counter)` and report only
+//! attempt counts and the counters of hits; the coordinator re-derives those
+//! keys itself, so private keys never cross the network. `run_key` comes from
+//! the shared cluster secret, which also authenticates both ends through an
+//! HMAC challenge-response.
+//!
+//! Messages are newline-delimited JSON. Found keys are fed into the same
+//! channel the local `WorkerPool` uses, so `main` dedupes, validates, saves
+//! and stops exactly as it does for local workers.
+
+use crossbeam_channel::Sender;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashSet, VecDeque};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::keygen::{self, KeyInfo};
+use crate::pattern::{matches_pattern_bytes, PatternConfig};
+
+/// Counters per work unit (a few seconds to minutes of work per agent)
+const UNIT_SIZE: u64 = 1 << 22;
+
+/// Units kept leased to each agent so it never idles waiting for the next one
+const UNITS_AHEAD: usize = 2;
+
+/// Counters an agent thread claims at a time
+const CHUNK_SIZE: u64 = 1_000;
+
+/// Socket poll interval, also the granularity of stop checks
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How often agents report progress
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Agents silent for this long are dropped and their units re-leased
+const AGENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest wait between agent reconnection attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Shortest cluster secret accepted; a captured handshake lets anyone test
+/// guesses offline, so the secret must be random rather than memorable
+pub const MIN_SECRET_BYTES: usize = 32;
+
+/// A disjoint range of counters `[start, start + len)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkUnit {
+    pub id: u64,
+    pub start: u64,
+    pub len: u64,
+}
+
+/// Wire protocol
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    // Coordinator -> agent
+    Challenge {
+        nonce: String,
+    },
+    Welcome {
+        agent_id: u64,
+        proof: String,
+        run_id: String,
+        pattern: PatternConfig,
+    },
+    Work {
+        unit: WorkUnit,
+    },
+    Stop,
+    Rejected {
+        reason: String,
+    },
+    // Agent -> coordinator
+    Hello {
+        name: String,
+        nonce: String,
+        proof: String,
+    },
+    Progress {
+        attempts: u64,
+        rate: f64,
+    },
+    Hit {
+        counter: u64,
+    },
+    Done {
+        unit_id: u64,
+    },
+}
+
+/// HMAC-SHA256 (RFC 2104)
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > 64 {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.iter().map(|b| b ^ byte).collect::<Vec<u8>>();
+
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(data)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// Proof that the sender knows the secret, bound to the peer's nonce and role
+fn auth_proof(secret: &[u8], role: &str, nonce: &str) -> String {
+    hex::encode(hmac_sha256(
+        secret,
+        format!("{}:{}", role, nonce).as_bytes(),
+    ))
+}
+
+/// Compare proofs without an early exit
+fn proofs_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// A fresh random cluster secret, hex-encoded for a secret file
+pub fn generate_secret() -> String {
+    random_hex(MIN_SECRET_BYTES)
+}
+
+/// Decode a secret file's contents, rejecting anything short enough to guess
+pub fn parse_secret(text: &str) -> Result<Vec<u8>, String> {
+    let secret = hex::decode(text.trim())
+        .map_err(|_| "Cluster secret must be hex (create one with `cluster-secret`)".to_string())?;
+    if secret.len() < MIN_SECRET_BYTES {
+        return Err(format!(
+            "Cluster secret is {} bytes; at least {} random bytes are required",
+            secret.len(),
+            MIN_SECRET_BYTES
+        ));
+    }
+    Ok(secret)
+}
+
+/// Per-run seed key; derived from the secret so it never goes over the wire
+fn run_key(secret: &[u8], run_id: &str) -> [u8; 32] {
+    hmac_sha256(secret, format!("seed:{}", run_id).as_bytes())
+}
+
+/// The keypair for a counter within a run
+pub fn derive_key(run_key: &[u8; 32], counter: u64) -> KeyInfo {
+    let seed: [u8; 32] = Sha256::new()
+        .chain_update(run_key)
+        .chain_update(counter.to_le_bytes())
+        .finalize()
+        .into();
+    keygen::generate_from_seed(&seed)
+}
+
+/// A TCP connection speaking the line protocol
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// Partial line kept across read timeouts
+    pending: String,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> Result<Self, String> {
+        stream
+            .set_read_timeout(Some(POLL_INTERVAL))
+            .map_err(|e| format!("Failed to configure socket: {}", e))?;
+        let _ = stream.set_nodelay(true);
+        let writer = stream
+            .try_clone()
+            .map_err(|e| format!("Failed to configure socket: {}", e))?;
+        Ok(Self {
+            reader: BufReader::new(stream),
+            writer,
+            pending: String::new(),
+        })
+    }
+
+    fn send(&mut self, message: &Message) -> Result<(), String> {
+        let mut line = serde_json::to_string(message).unwrap();
+        line.push('\n');
+        self.writer
+            .write_all(line.as_bytes())
+            .map_err(|e| format!("Connection lost: {}", e))
+    }
+
+    /// Next message, or `None` if nothing arrived within the poll interval
+    fn poll(&mut self) -> Result<Option<Message>, String> {
+        match self.reader.read_line(&mut self.pending) {
+            Ok(0) => Err("Connection closed".to_string()),
+            Ok(_) if self.pending.ends_with('\n') => {
+                let line = std::mem::take(&mut self.pending);
+                serde_json::from_str(&line)
+                    .map(Some)
+                    .map_err(|e| format!("Invalid message: {}", e))
+            }
+            Ok(_) => Ok(None),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(None),
+            Err(e) => Err(format!("Connection lost: {}", e)),
+        }
+    }
+
+    /// Wait up to `timeout` for the next message
+    fn recv(&mut self, timeout: Duration) -> Result<Message, String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(message) = self.poll()? {
+                return Ok(message);
+            }
+            if Instant::now() >= deadline {
+                return Err("Timed out waiting for peer".to_string());
+            }
+        }
+    }
+}
+
+/// Live statistics for one agent, as shown next to the local per-core rates
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeStats {
+    pub name: String,
+    pub attempts: u64,
+    pub rate: f64,
+    pub connected: bool,
+}
+
+struct CoordinatorState {
+    next_counter: u64,
+    next_unit_id: u64,
+    /// Units returned by disconnected agents, handed out before fresh ones
+    requeued: VecDeque<WorkUnit>,
+    nodes: Vec<NodeStats>,
+    /// Open sessions per entry of `nodes`, so a reconnect overlapping the
+    /// old session's timeout keeps the node marked connected
+    sessions: Vec<usize>,
+    /// Hit counters already forwarded, so re-leased ranges are not reported twice
+    seen_hits: HashSet<u64>,
+}
+
+impl CoordinatorState {
+    fn lease(&mut self) -> WorkUnit {
+        self.requeued.pop_front().unwrap_or_else(|| {
+            let unit = WorkUnit {
+                id: self.next_unit_id,
+                start: self.next_counter,
+                len: UNIT_SIZE,
+            };
+            self.next_unit_id += 1;
+            self.next_counter += UNIT_SIZE;
+            unit
+        })
+    }
+
+    /// Whether `counter` was leased to any agent during this run; fresh units
+    /// are handed out in order, so that is everything below `next_counter`
+    fn was_leased(&self, counter: u64) -> bool {
+        counter < self.next_counter
+    }
+
+    /// The stats entry for an agent name, reused when the agent reconnects
+    fn connect(&mut self, name: String) -> usize {
+        let node = match self.nodes.iter().position(|n| n.name == name) {
+            Some(node) => node,
+            None => {
+                self.nodes.push(NodeStats {
+                    name,
+                    attempts: 0,
+                    rate: 0.0,
+                    connected: false,
+                });
+                self.sessions.push(0);
+                self.nodes.len() - 1
+            }
+        };
+        self.sessions[node] += 1;
+        self.nodes[node].connected = true;
+        node
+    }
+
+    fn disconnect(&mut self, node: usize) {
+        self.sessions[node] -= 1;
+        if self.sessions[node] == 0 {
+            self.nodes[node].connected = false;
+            self.nodes[node].rate = 0.0;
+        }
+    }
+}
+
+/// Shared context for connection threads
+struct CoordinatorContext {
+    secret: Vec<u8>,
+    run_id: String,
+    run_key: [u8; 32],
+    pattern: PatternConfig,
+    sender: Sender<KeyInfo>,
+    total_attempts: Arc<AtomicU64>,
+    should_stop: Arc<AtomicBool>,
+    state: Mutex<CoordinatorState>,
+}
+
+/// Accepts agents and feeds their hits into the local result channel
+pub struct Coordinator {
+    context: Arc<CoordinatorContext>,
+    local_addr: std::net::SocketAddr,
+    listener: Option<JoinHandle<()>>,
+}
+
+impl Coordinator {
+    /// Listen on `addr` and start accepting agents
+    pub fn start(
+        addr: &str,
+        secret: &[u8],
+        pattern: PatternConfig,
+        sender: Sender<KeyInfo>,
+        total_attempts: Arc<AtomicU64>,
+        should_stop: Arc<AtomicBool>,
+    ) -> Result<Self, String> {
+        let listener =
+            TcpListener::bind(addr).map_err(|e| format!("Failed to listen on {}: {}", addr, e))?;
+        let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure listener: {}", e))?;
+
+        let run_id = random_hex(16);
+        let context = Arc::new(CoordinatorContext {
+            secret: secret.to_vec(),
+            run_key: run_key(secret, &run_id),
+            run_id,
+            pattern,
+            sender,
+            total_attempts,
+            should_stop,
+            state: Mutex::new(CoordinatorState {
+                next_counter: 0,
+                next_unit_id: 0,
+                requeued: VecDeque::new(),
+                nodes: Vec::new(),
+                sessions: Vec::new(),
+                seen_hits: HashSet::new(),
+            }),
+        });
+
+        let accept_context = context.clone();
+        let handle = thread::Builder::new()
+            .name("coordinator-accept".to_string())
+            .spawn(move || {
+                let mut sessions: Vec<JoinHandle<()>> = Vec::new();
+                while !accept_context.should_stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let _ = stream.set_nonblocking(false);
+                            let ctx = accept_context.clone();
+                            sessions.push(thread::spawn(move || serve_agent(&ctx, stream)));
+                        }
+                        Err(_) => thread::sleep(POLL_INTERVAL / 2),
+                    }
+                }
+                // Sessions notice the stop flag and tell their agents to stop
+                for session in sessions {
+                    let _ = session.join();
+                }
+            })
+            .map_err(|e| format!("Failed to spawn coordinator: {}", e))?;
+
+        Ok(Self {
+            context,
+            local_addr,
+            listener: Some(handle),
+        })
+    }
+
+    /// Address the coordinator is listening on
+    pub fn local_addr(&self) -> std::net::SocketAddr {
+        self.local_addr
+    }
+
+    /// Statistics for every agent seen so far
+    pub fn node_stats(&self) -> Vec<NodeStats> {
+        self.context.state.lock().unwrap().nodes.clone()
+    }
+
+    /// Stop all agents and wait for their sessions to close
+    pub fn shutdown(mut self) {
+        self.context.should_stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.listener.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Handle one agent connection until it disconnects or the search stops
+fn serve_agent(ctx: &CoordinatorContext, stream: TcpStream) {
+    let Ok(mut conn) = Connection::new(stream) else {
+        return;
+    };
+
+    // Challenge-response: the agent proves it knows the secret, then we do
+    let nonce = random_hex(16);
+    if conn
+        .send(&Message::Challenge {
+            nonce: nonce.clone(),
+        })
+        .is_err()
+    {
+        return;
+    }
+    let (name, agent_nonce) = match conn.recv(Duration::from_secs(10)) {
+        Ok(Message::Hello {
+            name,
+            nonce: agent_nonce,
+            proof,
+        }) if proofs_match(&proof, &auth_proof(&ctx.secret, "agent", &nonce)) => {
+            (name, agent_nonce)
+        }
+        _ => {
+            let _ = conn.send(&Message::Rejected {
+                reason: "authentication failed".to_string(),
+            });
+            return;
+        }
+    };
+
+    let (node, mut leases) = {
+        let mut state = ctx.state.lock().unwrap();
+        let node = state.connect(name);
+        let leases: Vec<WorkUnit> = (0..UNITS_AHEAD).map(|_| state.lease()).collect();
+        (node, leases)
+    };
+
+    let welcome = Message::Welcome {
+        agent_id: node as u64,
+        proof: auth_proof(&ctx.secret, "coordinator", &agent_nonce),
+        run_id: ctx.run_id.clone(),
+        pattern: ctx.pattern.clone(),
+    };
+    let mut result = conn.send(&welcome);
+    for unit in &leases {
+        result = result.and_then(|_| conn.send(&Message::Work { unit: *unit }));
+    }
+
+    let mut last_seen = Instant::now();
+    while result.is_ok() {
+        if ctx.should_stop.load(Ordering::Relaxed) {
+            let _ = conn.send(&Message::Stop);
+            break;
+        }
+        let message = match conn.poll() {
+            Ok(Some(message)) => message,
+            Ok(None) if last_seen.elapsed() > AGENT_TIMEOUT => break,
+            Ok(None) => continue,
+            Err(_) => break,
+        };
+        last_seen = Instant::now();
+
+        match message {
+            Message::Progress { attempts, rate } => {
+                ctx.total_attempts.fetch_add(attempts, Ordering::Relaxed);
+                let mut state = ctx.state.lock().unwrap();
+                state.nodes[node].attempts += attempts;
+                state.nodes[node].rate = rate;
+            }
+            Message::Hit { counter } => {
+                // Only accept counters leased during this run (hits held over
+                // from an agent's previous session included), and re-check
+                // the match ourselves
+                {
+                    let mut state = ctx.state.lock().unwrap();
+                    if !state.was_leased(counter) || !state.seen_hits.insert(counter) {
+                        continue;
+                    }
+                }
+                let key = derive_key(&ctx.run_key, counter);
+                if matches_pattern_bytes(&key.public_bytes, &ctx.pattern) {
+                    let _ = ctx.sender.send(key);
+                }
+            }
+            Message::Done { unit_id } => {
+                leases.retain(|u| u.id != unit_id);
+                let unit = ctx.state.lock().unwrap().lease();
+                leases.push(unit);
+                result = conn.send(&Message::Work { unit });
+            }
+            _ => {}
+        }
+    }
+
+    // Unfinished units go back to the pool for the next agent
+    let mut state = ctx.state.lock().unwrap();
+    state.disconnect(node);
+    state.requeued.extend(leases);
+}
+
+/// Units an agent is working through
+#[derive(Default)]
+struct AgentQueue {
+    units: VecDeque<ActiveUnit>,
+    finished: Vec<u64>,
+}
+
+struct ActiveUnit {
+    unit: WorkUnit,
+    /// Next counter to hand to a thread
+    next: u64,
+    /// Counters claimed but not yet checked, or not yet claimed
+    outstanding: u64,
+}
+
+impl AgentQueue {
+    fn claim(&mut self) -> Option<(u64, u64, u64)> {
+        let active = self
+            .units
+            .iter_mut()
+            .find(|a| a.next < a.unit.start + a.unit.len)?;
+        let start = active.next;
+        let end = (start + CHUNK_SIZE).min(active.unit.start + active.unit.len);
+        active.next = end;
+        Some((active.unit.id, start, end))
+    }
+
+    fn complete(&mut self, unit_id: u64, count: u64) {
+        if let Some(pos) = self.units.iter().position(|a| a.unit.id == unit_id) {
+            self.units[pos].outstanding -= count;
+            if self.units[pos].outstanding == 0 {
+                self.units.remove(pos);
+                self.finished.push(unit_id);
+            }
+        }
+    }
+}
+
+/// How an agent session ended
+#[derive(Debug, PartialEq)]
+pub enum SessionEnd {
+    /// The coordinator finished the search
+    Stopped,
+    /// The coordinator refused us, or could not prove it knows the secret
+    Rejected(String),
+    /// The connection dropped; worth reconnecting
+    Disconnected(String),
+}
+
+/// Agent options
+pub struct AgentConfig {
+    pub coordinator: String,
+    pub secret: Vec<u8>,
+    pub name: String,
+    pub workers: usize,
+}
+
+/// Counters shared between the agent's session and its search threads
+struct AgentShared {
+    queue: Mutex<AgentQueue>,
+    run_key: Mutex<Option<([u8; 32], PatternConfig)>>,
+    attempts: AtomicU64,
+    hits: Mutex<Vec<u64>>,
+    session_active: AtomicBool,
+}
+
+/// Run an agent until the coordinator stops it or rejects it, reconnecting
+/// with exponential backoff whenever the connection drops
+///
+/// `on_status` is called with a short status line for the progress display.
+pub fn run_agent(
+    config: &AgentConfig,
+    should_stop: &AtomicBool,
+    on_status: &dyn Fn(&str),
+) -> SessionEnd {
+    let shared = AgentShared {
+        queue: Mutex::new(AgentQueue::default()),
+        run_key: Mutex::new(None),
+        attempts: AtomicU64::new(0),
+        hits: Mutex::new(Vec::new()),
+        session_active: AtomicBool::new(false),
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..config.workers.max(1) {
+            scope.spawn(|| agent_search_loop(&shared, should_stop));
+        }
+
+        let mut backoff = Duration::from_secs(1);
+        let mut run_id: Option<String> = None;
+        let end = loop {
+            if should_stop.load(Ordering::Relaxed) {
+                break SessionEnd::Stopped;
+            }
+            on_status(&format!("connecting to {}", config.coordinator));
+            let end = match TcpStream::connect(&config.coordinator) {
+                Ok(stream) => {
+                    let end =
+                        agent_session(config, &shared, stream, &mut run_id, should_stop, on_status);
+                    if !matches!(end, SessionEnd::Disconnected(_)) {
+                        break end;
+                    }
+                    backoff = Duration::from_secs(1);
+                    end
+                }
+                Err(e) => SessionEnd::Disconnected(e.to_string()),
+            };
+
+            // Work leased on the dropped connection is re-leased by the coordinator
+            shared.session_active.store(false, Ordering::Relaxed);
+            shared.queue.lock().unwrap().units.clear();
+            if let SessionEnd::Disconnected(reason) = end {
+                on_status(&format!(
+                    "disconnected ({}), retrying in {}s",
+                    reason,
+                    backoff.as_secs()
+                ));
+            }
+            let deadline = Instant::now() + backoff;
+            while Instant::now() < deadline && !should_stop.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+            }
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        };
+
+        should_stop.store(true, Ordering::Relaxed);
+        end
+    })
+}
+
+/// One connected session: authenticate, then exchange work, progress and hits
+fn agent_session(
+    config: &AgentConfig,
+    shared: &AgentShared,
+    stream: TcpStream,
+    run_id: &mut Option<String>,
+    should_stop: &AtomicBool,
+    on_status: &dyn Fn(&str),
+) -> SessionEnd {
+    let disconnected = SessionEnd::Disconnected;
+    let mut conn = match Connection::new(stream) {
+        Ok(conn) => conn,
+        Err(e) => return disconnected(e),
+    };
+
+    let nonce = match conn.recv(Duration::from_secs(10)) {
+        Ok(Message::Challenge { nonce }) => nonce,
+        Ok(_) => return SessionEnd::Rejected("unexpected handshake".to_string()),
+        Err(e) => return disconnected(e),
+    };
+    let my_nonce = random_hex(16);
+    let hello = Message::Hello {
+        name: config.name.clone(),
+        nonce: my_nonce.clone(),
+        proof: auth_proof(&config.secret, "agent", &nonce),
+    };
+    if let Err(e) = conn.send(&hello) {
+        return disconnected(e);
+    }
+    match conn.recv(Duration::from_secs(10)) {
+        Ok(Message::Welcome {
+            proof,
+            run_id: new_run_id,
+            pattern,
+            ..
+        }) => {
+            if !proofs_match(
+                &proof,
+                &auth_proof(&config.secret, "coordinator", &my_nonce),
+            ) {
+                return SessionEnd::Rejected("coordinator failed authentication".to_string());
+            }
+            // Hits from before a reconnect are only meaningful for the same run
+            if run_id.as_deref() != Some(new_run_id.as_str()) {
+                shared.hits.lock().unwrap().clear();
+            }
+            *shared.run_key.lock().unwrap() = Some((run_key(&config.secret, &new_run_id), pattern));
+            *run_id = Some(new_run_id);
+        }
+        Ok(Message::Rejected { reason }) => return SessionEnd::Rejected(reason),
+        Ok(_) => return SessionEnd::Rejected("unexpected handshake".to_string()),
+        Err(e) => return disconnected(e),
+    }
+    shared.session_active.store(true, Ordering::Relaxed);
+
+    let mut last_report = Instant::now();
+    let mut reported = shared.attempts.load(Ordering::Relaxed);
+    loop {
+        if should_stop.load(Ordering::Relaxed) {
+            return SessionEnd::Stopped;
+        }
+
+        match conn.poll() {
+            Ok(Some(Message::Work { unit })) => {
+                shared.queue.lock().unwrap().units.push_back(ActiveUnit {
+                    unit,
+                    next: unit.start,
+                    outstanding: unit.len,
+                });
+            }
+            Ok(Some(Message::Stop)) => return SessionEnd::Stopped,
+            Ok(Some(Message::Rejected { reason })) => return SessionEnd::Rejected(reason),
+            Ok(_) => {}
+            Err(e) => return disconnected(e),
+        }
+
+        // Search threads push a unit's hits before marking it finished, so
+        // taking `finished` first sends every hit ahead of its unit's `Done`
+        let finished: Vec<u64> = std::mem::take(&mut shared.queue.lock().unwrap().finished);
+        let hits: Vec<u64> = std::mem::take(&mut *shared.hits.lock().unwrap());
+        let mut outgoing: Vec<Message> = hits
+            .iter()
+            .map(|&counter| Message::Hit { counter })
+            .collect();
+        outgoing.extend(
+            finished
+                .into_iter()
+                .map(|unit_id| Message::Done { unit_id }),
+        );
+
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            let total = shared.attempts.load(Ordering::Relaxed);
+            let delta = total - reported;
+            let rate = delta as f64 / last_report.elapsed().as_secs_f64();
+            outgoing.push(Message::Progress {
+                attempts: delta,
+                rate,
+            });
+            on_status(&format!(
+                "connected to {} | {} attempts | {:.0}/s",
+                config.coordinator, total, rate
+            ));
+            reported = total;
+            last_report = Instant::now();
+        }
+
+        for message in &outgoing {
+            if let Err(e) = conn.send(message) {
+                // Keep hits for the next session of the same run
+                shared.hits.lock().unwrap().extend(hits);
+                return disconnected(e);
+            }
+        }
+    }
+}
+
+/// Search thread: claim chunks of counters and check each derived key
+fn agent_search_loop(shared: &AgentShared, should_stop: &AtomicBool) {
+    while !should_stop.load(Ordering::Relaxed) {
+        let claimed = if shared.session_active.load(Ordering::Relaxed) {
+            shared.queue.lock().unwrap().claim()
+        } else {
+            None
+        };
+        let Some((unit_id, start, end)) = claimed else {
+            thread::sleep(POLL_INTERVAL / 4);
+            continue;
+        };
+        let Some((key, pattern)) = shared.run_key.lock().unwrap().clone() else {
+            continue;
+        };
+
+        for counter in start..end {
+            let candidate = derive_key(&key, counter);
+            if matches_pattern_bytes(&candidate.public_bytes, &pattern) {
+                shared.hits.lock().unwrap().push(counter);
+            }
+        }
+        shared.attempts.fetch_add(end - start, Ordering::Relaxed);
+        shared.queue.lock().unwrap().complete(unit_id, end - start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"correct horse battery staple";
+
+    fn start_coordinator(
+        pattern: PatternConfig,
+    ) -> (Coordinator, crossbeam_channel::Receiver<KeyInfo>) {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let coordinator = Coordinator::start(
+            "127.0.0.1:0",
+            SECRET,
+            pattern,
+            tx,
+            Arc::new(AtomicU64::new(0)),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .unwrap();
+        (coordinator, rx)
+    }
+
+    /// Authenticate a bare connection as `name` and collect its first leases
+    fn raw_session(addr: std::net::SocketAddr, name: &str) -> (Connection, Vec<WorkUnit>) {
+        let mut conn = Connection::new(TcpStream::connect(addr).unwrap()).unwrap();
+        let Message::Challenge { nonce } = conn.recv(Duration::from_secs(5)).unwrap() else {
+            panic!("expected challenge");
+        };
+        conn.send(&Message::Hello {
+            name: name.to_string(),
+            nonce: "00".to_string(),
+            proof: auth_proof(SECRET, "agent", &nonce),
+        })
+        .unwrap();
+        assert!(matches!(
+            conn.recv(Duration::from_secs(5)).unwrap(),
+            Message::Welcome { .. }
+        ));
+        let units: Vec<WorkUnit> = (0..UNITS_AHEAD)
+            .map(|_| match conn.recv(Duration::from_secs(5)).unwrap() {
+                Message::Work { unit } => unit,
+                other => panic!("expected work, got {:?}", other),
+            })
+            .collect();
+        (conn, units)
+    }
+
+    /// Wait until the coordinator sees the first node disconnect
+    fn wait_disconnected(coordinator: &Coordinator) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while coordinator.node_stats()[0].connected && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    fn agent_config(addr: std::net::SocketAddr, secret: &[u8]) -> AgentConfig {
+        AgentConfig {
+            coordinator: addr.to_string(),
+            secret: secret.to_vec(),
+            name: "test-agent".to_string(),
+            workers: 1,
+        }
+    }
+
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_agent_finds_key_and_is_stopped() {
+        let (coordinator, rx) = start_coordinator(PatternConfig::with_prefix("A"));
+        let config = agent_config(coordinator.local_addr(), SECRET);
+        let agent_stop = AtomicBool::new(false);
+
+        let end = thread::scope(|scope| {
+            let agent = scope.spawn(|| run_agent(&config, &agent_stop, &|_| {}));
+            let key = rx.recv_timeout(Duration::from_secs(30)).unwrap();
+            assert!(key.public_hex.starts_with('a'));
+            assert!(keygen::verify_key(&key));
+            assert_eq!(coordinator.node_stats()[0].name, "test-agent");
+            coordinator.shutdown();
+            agent.join().unwrap()
+        });
+        assert_eq!(end, SessionEnd::Stopped);
+    }
+
+    #[test]
+    fn test_wrong_secret_is_rejected() {
+        let (coordinator, _rx) = start_coordinator(PatternConfig::default());
+        let config = agent_config(coordinator.local_addr(), b"wrong secret");
+        let end = run_agent(&config, &AtomicBool::new(false), &|_| {});
+        assert!(matches!(end, SessionEnd::Rejected(_)));
+        assert!(coordinator.node_stats().is_empty());
+        coordinator.shutdown();
+    }
+
+    #[test]
+    fn test_units_of_dropped_agent_are_released() {
+        let (coordinator, _rx) = start_coordinator(PatternConfig::default());
+
+        let (first, first_units) = raw_session(coordinator.local_addr(), "raw");
+        drop(first);
+        wait_disconnected(&coordinator);
+
+        let (_second, second_units) = raw_session(coordinator.local_addr(), "raw");
+        assert_eq!(first_units, second_units);
+        // The reconnecting agent keeps its stats entry
+        let nodes = coordinator.node_stats();
+        assert_eq!(nodes.len(), 1);
+        assert!(nodes[0].connected);
+        coordinator.shutdown();
+    }
+
+    #[test]
+    fn test_hits_from_earlier_leases_are_accepted_after_reconnect() {
+        let (coordinator, rx) = start_coordinator(PatternConfig {
+            mode: crate::pattern::PatternMode::Any,
+            ..PatternConfig::default()
+        });
+
+        // The first session's units go to another agent, so the reconnected
+        // session is leased different ones
+        let (first, first_units) = raw_session(coordinator.local_addr(), "raw");
+        drop(first);
+        wait_disconnected(&coordinator);
+        let (_other, _) = raw_session(coordinator.local_addr(), "other");
+        let (mut second, second_units) = raw_session(coordinator.local_addr(), "raw");
+        assert!(second_units.iter().all(|u| !first_units.contains(u)));
+
+        let held_over = first_units[0].start + 3;
+        let never_leased = u64::MAX - 1;
+        for counter in [never_leased, held_over, held_over] {
+            second.send(&Message::Hit { counter }).unwrap();
+        }
+        let key = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let run_key = coordinator.context.run_key;
+        assert_eq!(key.public_hex, derive_key(&run_key, held_over).public_hex);
+        // Neither the duplicate nor the counter outside the run is forwarded
+        assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+        coordinator.shutdown();
+    }
+
+    #[test]
+    fn test_parse_secret_requires_enough_random_bytes() {
+        let secret = generate_secret();
+        assert_eq!(
+            parse_secret(&format!("{}\n", secret)).unwrap().len(),
+            MIN_SECRET_BYTES
+        );
+        assert!(parse_secret(&secret[..MIN_SECRET_BYTES * 2 - 2]).is_err());
+        assert!(parse_secret("correct horse battery staple").is_err());
+        assert_ne!(generate_secret(), secret);
+    }
+
+    #[test]
+    fn test_agent_queue_reports_finished_units() {
+        let mut queue = AgentQueue::default();
+        queue.units.push_back(ActiveUnit {
+            unit: WorkUnit {
+                id: 7,
+                start: 100,
+                len: CHUNK_SIZE + 5,
+            },
+            next: 100,
+            outstanding: CHUNK_SIZE + 5,
+        });
+        let (id, start, end) = queue.claim().unwrap();
+        assert_eq!((id, start, end), (7, 100, 100 + CHUNK_SIZE));
+        let (_, start2, end2) = queue.claim().unwrap();
+        assert_eq!(end2 - start2, 5);
+        assert!(queue.claim().is_none());
+
+        queue.complete(7, end2 - start2);
+        assert!(queue.finished.is_empty());
+        queue.complete(7, end - start);
+        assert_eq!(queue.finished, vec![7]);
+    }
+}
@@ -0,0 +1,558 @@
+//! Pattern Explanations
+//!
+//! The `explain` command is a dry run. It shows how the pattern flags were
+//! understood, the per-key odds and expected attempts, and time estimates
+//! from a short calibration run on this machine. It also builds a few
+//! synthetic keys that match, to show what a hit looks like. Patterns that
+//! can never produce a usable key are flagged before any time is spent on
+//! them, e.g. a prefix of `00` while MeshCore verification is on.
+
+use console::style;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::dictionary::Anchor;
+use crate::keygen;
+use crate::pattern::{key_nibble, matches_pattern_bytes, PatternConfig, PatternMode, KEY_NIBBLES};
+use crate::symmetry;
+
+/// Attempts at building each synthetic example before giving up
+const MAX_TRIES: usize = 1000;
+
+/// Chances of success the time estimates are given for
+const QUANTILES: [f64; 3] = [0.5, 0.9, 0.99];
+
+/// Expected times beyond this are flagged as impractical
+const IMPRACTICAL_SECONDS: f64 = 100.0 * 365.25 * 86_400.0;
+
+/// Attempts and time needed to find a key with some chance
+#[derive(Debug, Serialize)]
+pub struct Estimate {
+    /// Chance of having found a key, e.g. 0.9
+    pub chance: f64,
+    pub attempts: f64,
+    /// At the calibrated rate; absent without calibration
+    pub seconds: Option<f64>,
+}
+
+/// Everything `explain` found out about a pattern
+#[derive(Debug, Serialize)]
+pub struct Explanation {
+    pub description: String,
+    pub mode: PatternMode,
+    /// How the flags were combined and what the pattern requires
+    pub notes: Vec<String>,
+    /// Chance that one random key matches
+    pub probability: f64,
+    /// False where the odds assume independent parts (dictionaries, expressions)
+    pub probability_exact: bool,
+    pub expected_attempts: f64,
+    pub workers: usize,
+    /// Calibrated keys per second across all workers
+    pub rate: Option<f64>,
+    pub estimates: Vec<Estimate>,
+    /// Synthetic public keys (hex) that match; they have no private key
+    pub examples: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Problems that keep the search from ever finding a usable key
+    pub errors: Vec<String>,
+    pub feasible: bool,
+}
+
+/// Explain `config`, with `notes` about the flags prepended to the parse notes
+pub fn explain(
+    config: &PatternConfig,
+    mut notes: Vec<String>,
+    verify: bool,
+    workers: usize,
+    calibration: Duration,
+    examples: usize,
+) -> Explanation {
+    notes.extend(parse_notes(config));
+    let probability = config.estimated_probability();
+    let probability_exact = !matches!(
+        config.mode,
+        PatternMode::Dictionary | PatternMode::Expression
+    );
+    let (mut warnings, errors) = check(config, probability, verify);
+    if !probability_exact {
+        warnings.push(
+            "The odds assume the parts of the pattern are independent, so they are an estimate"
+                .to_string(),
+        );
+    }
+
+    let feasible = errors.is_empty();
+    let rate =
+        (feasible && !calibration.is_zero()).then(|| calibrate(config, workers, calibration));
+    let estimates: Vec<Estimate> = if feasible {
+        QUANTILES
+            .iter()
+            .map(|&chance| {
+                let attempts = attempts_for(probability, chance);
+                Estimate {
+                    chance,
+                    attempts,
+                    seconds: rate.filter(|&r| r > 0.0).map(|r| attempts / r),
+                }
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    if let Some(seconds) = estimates.first().and_then(|e| e.seconds) {
+        if seconds > IMPRACTICAL_SECONDS {
+            warnings.push(format!(
+                "Half of all searches would take longer than {} on this machine",
+                format_seconds(seconds)
+            ));
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let examples = if feasible {
+        (0..examples)
+            .filter_map(|_| synthesize(config, verify, &mut rng))
+            .map(hex::encode_upper)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Explanation {
+        description: config.description(),
+        mode: config.mode.clone(),
+        notes,
+        probability,
+        probability_exact,
+        expected_attempts: 1.0 / probability,
+        workers,
+        rate,
+        estimates,
+        examples,
+        warnings,
+        errors,
+        feasible,
+    }
+}
+
+/// Attempts after which a key has been found with probability `chance`
+pub fn attempts_for(probability: f64, chance: f64) -> f64 {
+    if probability >= 1.0 {
+        return 1.0;
+    }
+    ((1.0 - chance).ln() / (-probability).ln_1p()).max(1.0)
+}
+
+/// What the pattern mode requires, in words
+fn parse_notes(config: &PatternConfig) -> Vec<String> {
+    let n = config.vanity_length as usize;
+    let rules = config
+        .symmetries
+        .iter()
+        .map(|s| s.describe(n))
+        .collect::<Vec<_>>()
+        .join(" or ");
+    let prefix = config.prefix.as_deref().unwrap_or("");
+    match &config.mode {
+        PatternMode::Any => vec!["Every key matches the built-in pattern".to_string()],
+        PatternMode::Prefix => vec![format!(
+            "The key must start with the {} hex characters '{}'",
+            prefix.len(),
+            prefix
+        )],
+        PatternMode::Vanity | PatternMode::Pattern => {
+            vec![format!("Of the first {} characters: {}", n, rules)]
+        }
+        PatternMode::PrefixVanity => {
+            let overlap = if prefix.len() >= n {
+                format!(
+                    "the prefix fixes all {} vanity characters, so '{}' itself must repeat",
+                    n,
+                    prefix.chars().take(n).collect::<String>()
+                )
+            } else {
+                format!(
+                    "the prefix fixes {} of the {} vanity characters",
+                    prefix.len(),
+                    n
+                )
+            };
+            vec![
+                "--prefix combined with --pattern/--vanity: both must hold (PrefixVanity)"
+                    .to_string(),
+                format!("The key must start with '{}'", prefix),
+                format!("Of the first {} characters: {}", n, rules),
+                format!("The vanity rule includes the prefix: {}", overlap),
+            ]
+        }
+        PatternMode::Dictionary => match &config.dictionary {
+            Some(d) => {
+                let lengths = d.words().iter().map(|w| w.hex.len());
+                vec![format!(
+                    "{} words of {}-{} hex characters, at the {}",
+                    d.words().len(),
+                    lengths.clone().min().unwrap_or(0),
+                    lengths.max().unwrap_or(0),
+                    match d.anchor() {
+                        Anchor::Start => "start",
+                        Anchor::End => "end",
+                        Anchor::Both => "start or end",
+                    }
+                )]
+            }
+            None => Vec::new(),
+        },
+        PatternMode::Expression => config
+            .expression
+            .iter()
+            .map(|e| format!("Parsed as: {}", e))
+            .collect(),
+        PatternMode::Regex => config
+            .regex
+            .iter()
+            .map(|r| format!("Compiled to {} DFA states", r.state_count()))
+            .collect(),
+    }
+}
+
+/// Warnings and errors about the pattern
+fn check(config: &PatternConfig, probability: f64, verify: bool) -> (Vec<String>, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut errors = Vec::new();
+
+    let prefix_nibbles = config.prefix_nibbles();
+    if matches!(config.mode, PatternMode::Prefix | PatternMode::PrefixVanity)
+        && prefix_nibbles.is_none()
+    {
+        errors.push(format!(
+            "Prefix '{}' is not hex, so no key can ever match it",
+            config.prefix.as_deref().unwrap_or("")
+        ));
+    } else if probability <= 0.0 {
+        errors.push("No key can match this pattern".to_string());
+    }
+
+    // Node IDs 00 and FF are reserved by MeshCore
+    let starts: Vec<u8> = (0..=255u8).filter(|&b| can_start_with(config, b)).collect();
+    let reserved: Vec<String> = starts
+        .iter()
+        .filter(|&&b| b == 0x00 || b == 0xFF)
+        .map(|b| format!("{:02X}", b))
+        .collect();
+    if !starts.is_empty() && reserved.len() == starts.len() {
+        let message = format!(
+            "Every match starts with {}, a node ID MeshCore reserves",
+            reserved.join(" or ")
+        );
+        if verify {
+            errors.push(format!(
+                "{}; verification rejects all of them (--no-verify keeps them)",
+                message
+            ));
+        } else {
+            warnings.push(format!("{}; such keys will not work on a mesh", message));
+        }
+    } else if verify && !reserved.is_empty() && starts.len() < 256 {
+        warnings.push(format!(
+            "Matches starting with {} are rejected by verification ({} of {} possible first bytes)",
+            reserved.join(" or "),
+            reserved.len(),
+            starts.len()
+        ));
+    }
+
+    (warnings, errors)
+}
+
+/// Whether a matching key may start with `byte`
+pub fn can_start_with(config: &PatternConfig, byte: u8) -> bool {
+    let fits = |nibbles: &[u8]| {
+        nibbles.first().is_none_or(|&n| n == byte >> 4)
+            && nibbles.get(1).is_none_or(|&n| n == byte & 0x0F)
+    };
+    match &config.mode {
+        PatternMode::Prefix | PatternMode::PrefixVanity => {
+            config.prefix_nibbles().is_some_and(|p| fits(&p))
+        }
+        PatternMode::Dictionary => config.dictionary.as_ref().is_none_or(|d| {
+            d.anchor() != Anchor::Start
+                || d.words().iter().any(|w| {
+                    let nibbles: Vec<u8> = w
+                        .hex
+                        .chars()
+                        .filter_map(|c| c.to_digit(16).map(|d| d as u8))
+                        .collect();
+                    fits(&nibbles)
+                })
+        }),
+        PatternMode::Expression => config
+            .expression
+            .as_ref()
+            .is_none_or(|e| e.can_start_with(byte)),
+        PatternMode::Regex => config.regex.as_ref().is_none_or(|r| r.can_start_with(byte)),
+        PatternMode::Any | PatternMode::Vanity | PatternMode::Pattern => true,
+    }
+}
+
+/// A random public key that matches `config` (and passes the MeshCore
+/// prefix check if `verify`), built by forcing the pattern onto random
+/// digits; `None` if none turned up
+pub fn synthesize(
+    config: &PatternConfig,
+    verify: bool,
+    rng: &mut impl rand::Rng,
+) -> Option<[u8; 32]> {
+    let n = config.vanity_length as usize;
+    for _ in 0..MAX_TRIES {
+        let mut nibbles = [0u8; KEY_NIBBLES];
+        nibbles.iter_mut().for_each(|d| *d = rng.gen_range(0..16));
+        match &config.mode {
+            PatternMode::Any => {}
+            PatternMode::Prefix | PatternMode::PrefixVanity => {
+                let prefix = config.prefix_nibbles()?;
+                let len = prefix.len().min(KEY_NIBBLES);
+                nibbles[..len].copy_from_slice(&prefix[..len]);
+                if config.mode == PatternMode::PrefixVanity {
+                    symmetry::impose(&mut nibbles, n, &config.symmetries, rng);
+                }
+            }
+            PatternMode::Vanity | PatternMode::Pattern => {
+                symmetry::impose(&mut nibbles, n, &config.symmetries, rng)
+            }
+            PatternMode::Dictionary => {
+                let dictionary = config.dictionary.as_ref()?;
+                let words = dictionary.words();
+                let word: Vec<u8> = words[rng.gen_range(0..words.len())]
+                    .hex
+                    .chars()
+                    .filter_map(|c| c.to_digit(16).map(|d| d as u8))
+                    .collect();
+                let at_end = match dictionary.anchor() {
+                    Anchor::Start => false,
+                    Anchor::End => true,
+                    Anchor::Both => rng.gen(),
+                };
+                let offset = if at_end { KEY_NIBBLES - word.len() } else { 0 };
+                nibbles[offset..offset + word.len()].copy_from_slice(&word);
+            }
+            PatternMode::Expression => config.expression.as_ref()?.impose(&mut nibbles, rng),
+            PatternMode::Regex => {
+                let bytes = config.regex.as_ref()?.sample(rng);
+                for (i, d) in nibbles.iter_mut().enumerate() {
+                    *d = key_nibble(&bytes, i);
+                }
+            }
+        }
+
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = nibbles[2 * i] << 4 | nibbles[2 * i + 1];
+        }
+        if matches_pattern_bytes(&bytes, config)
+            && (!verify || keygen::is_valid_meshcore_prefix(&bytes))
+        {
+            return Some(bytes);
+        }
+    }
+    None
+}
+
+/// Keys per second generated and matched by `workers` threads over `duration`
+pub fn calibrate(config: &PatternConfig, workers: usize, duration: Duration) -> f64 {
+    let attempts = AtomicU64::new(0);
+    let start = Instant::now();
+    std::thread::scope(|scope| {
+        for _ in 0..workers.max(1) {
+            scope.spawn(|| {
+                let mut local = 0u64;
+                while start.elapsed() < duration {
+                    for _ in 0..64 {
+                        let key = keygen::generate_meshcore_keypair();
+                        std::hint::black_box(matches_pattern_bytes(&key.public_bytes, config));
+                    }
+                    local += 64;
+                }
+                attempts.fetch_add(local, Ordering::Relaxed);
+            });
+        }
+    });
+    attempts.load(Ordering::Relaxed) as f64 / start.elapsed().as_secs_f64()
+}
+
+/// Large counts: compact up to billions, then scientific
+pub fn format_count(n: f64) -> String {
+    if n < 1e12 {
+        crate::format_compact_f64(n)
+    } else {
+        format!("{:.2e}", n)
+    }
+}
+
+/// A duration that may run to years
+pub fn format_seconds(seconds: f64) -> String {
+    const YEAR: f64 = 365.25 * 86_400.0;
+    if !seconds.is_finite() {
+        return "∞".to_string();
+    }
+    if seconds >= YEAR {
+        let years = seconds / YEAR;
+        return if years < 1e6 {
+            format!("{:.0} years", years)
+        } else {
+            format!("{:.1e} years", years)
+        };
+    }
+    let s = seconds.round() as u64;
+    match s {
+        0 => "under 1s".to_string(),
+        1..=59 => format!("{}s", s),
+        60..=3599 => format!("{}m {:02}s", s / 60, s % 60),
+        3600..=86_399 => format!("{}h {:02}m", s / 3600, (s / 60) % 60),
+        _ => format!("{}d {:02}h", s / 86_400, (s / 3600) % 24),
+    }
+}
+
+/// Print an explanation for humans
+pub fn print_explanation(explanation: &Explanation) {
+    println!(
+        "{} Pattern: {}",
+        style("ℹ").cyan(),
+        style(&explanation.description).bold()
+    );
+    for note in &explanation.notes {
+        println!("  • {}", note);
+    }
+
+    if explanation.probability > 0.0 {
+        println!(
+            "{} Odds: 1 in {} keys{}",
+            style("ℹ").cyan(),
+            format_count(explanation.expected_attempts),
+            if explanation.probability_exact {
+                " (exact)"
+            } else {
+                " (estimate)"
+            }
+        );
+    }
+    if let Some(rate) = explanation.rate {
+        println!(
+            "{} Calibrated rate: {} keys/s with {} workers (CPU only)",
+            style("ℹ").cyan(),
+            crate::format_compact_f64(rate),
+            explanation.workers
+        );
+    }
+    if !explanation.estimates.is_empty() {
+        println!("{} Attempts needed to find one key:", style("ℹ").cyan());
+        for estimate in &explanation.estimates {
+            let time = estimate
+                .seconds
+                .map(|s| format!(", about {}", format_seconds(s)))
+                .unwrap_or_default();
+            println!(
+                "  {:>3.0}% chance: {} attempts{}",
+                estimate.chance * 100.0,
+                format_count(estimate.attempts),
+                time
+            );
+        }
+    }
+
+    if !explanation.examples.is_empty() {
+        println!(
+            "{} Example matches (synthetic, no private key):",
+            style("ℹ").cyan()
+        );
+        for example in &explanation.examples {
+            println!("  {}", example);
+        }
+    }
+
+    for warning in &explanation.warnings {
+        println!("{} {}", style("⚠").yellow(), warning);
+    }
+    for error in &explanation.errors {
+        println!("{} {}", style("✗").red(), error);
+    }
+    if explanation.feasible {
+        println!("{} Pattern is feasible", style("✓").green());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex_regex::HexRegex;
+
+    #[test]
+    fn test_estimates() {
+        let p = 1.0 / 256.0;
+        assert!((attempts_for(p, 0.5) - 177.1).abs() < 0.1);
+        assert!((attempts_for(p, 0.99) - 1176.6).abs() < 0.1);
+        assert_eq!(attempts_for(1.0, 0.99), 1.0);
+
+        let explanation = explain(
+            &PatternConfig::with_prefix("AB"),
+            Vec::new(),
+            true,
+            1,
+            Duration::from_millis(50),
+            0,
+        );
+        assert!(explanation.feasible && explanation.probability_exact);
+        assert_eq!(explanation.expected_attempts, 256.0);
+        assert!(explanation.rate.is_some_and(|r| r > 0.0));
+        assert!(explanation.estimates.iter().all(|e| e.seconds.is_some()));
+    }
+
+    #[test]
+    fn test_examples_and_feasibility() {
+        let mut configs = vec![
+            PatternConfig::with_prefix_vanity("AB", 4),
+            PatternConfig::with_regex(HexRegex::new("^(12|34)[0-9]{2}.*F{4}$").unwrap()),
+        ];
+        configs.push(PatternConfig::with_expression(
+            crate::expr::Expr::parse("node:10-3F & suffix:BEEF").unwrap(),
+        ));
+        for config in configs {
+            let explanation = explain(&config, Vec::new(), true, 1, Duration::ZERO, 3);
+            assert!(explanation.feasible, "{}", config.description());
+            assert_eq!(explanation.examples.len(), 3);
+            for example in &explanation.examples {
+                let bytes = crate::pattern::key_bytes(example);
+                assert!(matches_pattern_bytes(&bytes, &config));
+            }
+        }
+
+        let reserved = PatternConfig::with_prefix("00");
+        let explanation = explain(&reserved, Vec::new(), true, 1, Duration::ZERO, 3);
+        assert!(!explanation.feasible && explanation.examples.is_empty());
+        assert!(explanation.errors[0].contains("reserves"));
+        let explanation = explain(&reserved, Vec::new(), false, 1, Duration::ZERO, 1);
+        assert!(explanation.feasible && !explanation.warnings.is_empty());
+
+        let explanation = explain(
+            &PatternConfig::with_prefix("0"),
+            Vec::new(),
+            true,
+            1,
+            Duration::ZERO,
+            0,
+        );
+        assert!(explanation.feasible);
+        assert!(explanation.warnings[0].contains("1 of 16"));
+
+        let explanation = explain(
+            &PatternConfig::with_prefix("XY"),
+            Vec::new(),
+            true,
+            1,
+            Duration::ZERO,
+            0,
+        );
+        assert!(explanation.errors[0].contains("not hex"));
+    }
+}
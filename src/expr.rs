@@ -0,0 +1,607 @@
+//! Pattern Expressions
+//!
+//! `--match` (and the `--suffix`, `--infix` and `--node-range` shorthands)
+//! describe a key as a small expression tree evaluated on the raw public key
+//! bytes:
+//!
+//! ```text
+//! prefix:AB & !suffix:00
+//! (node:10-3F | node16:A000-A0FF) & vanity:4:equal
+//! at:8:CAFE | suffix:BEEF
+//! ```
+//!
+//! Atoms:
+//! - `prefix:HEX`, `suffix:HEX`: the key starts or ends with HEX
+//! - `at:OFFSET:HEX`: HEX at a fixed hex-character offset
+//! - `node:LO-HI`, `node16:LO-HI`: the first byte, or first two bytes, as a
+//!   number in an inclusive hex range (`node:3F` is a single value)
+//! - `vanity:N[:MODES]`: the `--symmetry` rules for N (2-8) characters
+//!
+//! Operators, loosest first: `|` (or `or`), `&` (or `and`), `!` (or `not`),
+//! with parentheses for grouping.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::pattern::{key_nibble, KEY_NIBBLES};
+use crate::symmetry::{self, default_symmetries, Symmetry};
+
+/// Hex digits, serialized as a string
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HexDigits(Vec<u8>);
+
+impl TryFrom<String> for HexDigits {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8))
+            .collect::<Option<Vec<u8>>>()
+            .filter(|d| !d.is_empty() && d.len() <= KEY_NIBBLES)
+            .map(HexDigits)
+            .ok_or_else(|| format!("'{}' is not 1-{} hex digits", value, KEY_NIBBLES))
+    }
+}
+
+impl From<HexDigits> for String {
+    fn from(digits: HexDigits) -> Self {
+        digits.to_string()
+    }
+}
+
+impl fmt::Display for HexDigits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &d in &self.0 {
+            write!(f, "{:X}", d)?;
+        }
+        Ok(())
+    }
+}
+
+impl HexDigits {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Pattern expression tree
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", try_from = "RawExpr")]
+pub enum Expr {
+    Prefix(HexDigits),
+    Suffix(HexDigits),
+    At {
+        offset: usize,
+        digits: HexDigits,
+    },
+    /// First `bytes` (1 or 2) bytes, big-endian, within `min..=max`
+    NodeRange {
+        bytes: u8,
+        min: u16,
+        max: u16,
+    },
+    Vanity {
+        length: usize,
+        symmetries: Vec<Symmetry>,
+    },
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+}
+
+/// `Expr` as deserialized, before the checks the parser would make
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RawExpr {
+    Prefix(HexDigits),
+    Suffix(HexDigits),
+    At {
+        offset: usize,
+        digits: HexDigits,
+    },
+    NodeRange {
+        bytes: u8,
+        min: u16,
+        max: u16,
+    },
+    Vanity {
+        length: usize,
+        symmetries: Vec<Symmetry>,
+    },
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+}
+
+impl TryFrom<RawExpr> for Expr {
+    type Error = String;
+
+    fn try_from(raw: RawExpr) -> Result<Self, Self::Error> {
+        Ok(match raw {
+            RawExpr::Prefix(digits) => Expr::Prefix(digits),
+            RawExpr::Suffix(digits) => Expr::Suffix(digits),
+            RawExpr::At { offset, digits } => {
+                if offset > KEY_NIBBLES - digits.len() {
+                    return Err(format!(
+                        "at:{}:{} runs past the end of the key",
+                        offset, digits
+                    ));
+                }
+                Expr::At { offset, digits }
+            }
+            RawExpr::NodeRange { bytes, min, max } => {
+                let limit = match bytes {
+                    1 => 0xFF,
+                    2 => 0xFFFF,
+                    _ => return Err(format!("Node ranges cover 1 or 2 bytes, not {}", bytes)),
+                };
+                if min > max || max > limit {
+                    return Err(format!("Invalid node range {:X}-{:X}", min, max));
+                }
+                Expr::NodeRange { bytes, min, max }
+            }
+            RawExpr::Vanity { length, symmetries } => {
+                if !symmetry::VANITY_LENGTHS.contains(&length) || symmetries.is_empty() {
+                    return Err(format!("Invalid vanity length {} or no symmetries", length));
+                }
+                Expr::Vanity { length, symmetries }
+            }
+            RawExpr::And(parts) | RawExpr::Or(parts) if parts.is_empty() => {
+                return Err("Empty 'and' or 'or' in expression".to_string())
+            }
+            RawExpr::And(parts) => Expr::And(parts),
+            RawExpr::Or(parts) => Expr::Or(parts),
+            RawExpr::Not(inner) => Expr::Not(inner),
+        })
+    }
+}
+
+impl Expr {
+    /// Parse an expression; usable as a clap value parser
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected '{}' in expression", token)),
+        }
+    }
+
+    /// All of `parts`, without a wrapper for a single part
+    pub fn all(mut parts: Vec<Expr>) -> Self {
+        if parts.len() == 1 {
+            parts.remove(0)
+        } else {
+            Expr::And(parts)
+        }
+    }
+
+    /// Evaluate on raw public key bytes (hot path)
+    #[inline]
+    pub fn matches_bytes(&self, public_bytes: &[u8; 32]) -> bool {
+        match self {
+            Expr::Prefix(digits) => digits_at(public_bytes, 0, digits),
+            Expr::Suffix(digits) => digits_at(public_bytes, KEY_NIBBLES - digits.len(), digits),
+            Expr::At { offset, digits } => digits_at(public_bytes, *offset, digits),
+            Expr::NodeRange { bytes, min, max } => {
+                let value = if *bytes == 1 {
+                    public_bytes[0] as u16
+                } else {
+                    u16::from_be_bytes([public_bytes[0], public_bytes[1]])
+                };
+                (*min..=*max).contains(&value)
+            }
+            Expr::Vanity { length, symmetries } => {
+                symmetry::matches_bytes(public_bytes, *length, symmetries)
+            }
+            Expr::And(parts) => parts.iter().all(|p| p.matches_bytes(public_bytes)),
+            Expr::Or(parts) => parts.iter().any(|p| p.matches_bytes(public_bytes)),
+            Expr::Not(inner) => !inner.matches_bytes(public_bytes),
+        }
+    }
+
+    /// Match probability, treating sub-expressions as independent
+    pub fn probability(&self) -> f64 {
+        match self {
+            Expr::Prefix(d) | Expr::Suffix(d) | Expr::At { digits: d, .. } => {
+                16f64.powi(-(d.len() as i32))
+            }
+            Expr::NodeRange { bytes, min, max } => {
+                (*max as f64 - *min as f64 + 1.0) / 256f64.powi(*bytes as i32)
+            }
+            Expr::Vanity { length, symmetries } => symmetry::probability(*length, symmetries),
+            Expr::And(parts) => parts.iter().map(Expr::probability).product(),
+            Expr::Or(parts) => 1.0 - parts.iter().map(|p| 1.0 - p.probability()).product::<f64>(),
+            Expr::Not(inner) => 1.0 - inner.probability(),
+        }
+    }
+
+    /// Set `nibbles` so that the positive atoms hold, picking one branch of
+    /// each `or` at random; negations are left to chance
+    pub fn impose(&self, nibbles: &mut [u8; KEY_NIBBLES], rng: &mut impl rand::Rng) {
+        let mut write = |offset: usize, digits: &HexDigits| {
+            nibbles[offset..offset + digits.len()].copy_from_slice(&digits.0);
+        };
+        match self {
+            Expr::Prefix(d) => write(0, d),
+            Expr::Suffix(d) => write(KEY_NIBBLES - d.len(), d),
+            Expr::At { offset, digits } => write(*offset, digits),
+            Expr::NodeRange { bytes, min, max } => {
+                let value = rng.gen_range(*min..=*max);
+                let width = *bytes as usize * 2;
+                for (i, nibble) in nibbles[..width].iter_mut().enumerate() {
+                    *nibble = (value >> (4 * (width - 1 - i)) & 0x0F) as u8;
+                }
+            }
+            Expr::Vanity { length, symmetries } => {
+                symmetry::impose(nibbles, *length, symmetries, rng)
+            }
+            Expr::And(parts) => parts.iter().for_each(|p| p.impose(nibbles, rng)),
+            Expr::Or(parts) => parts[rng.gen_range(0..parts.len())].impose(nibbles, rng),
+            Expr::Not(_) => {}
+        }
+    }
+
+    /// Whether a matching key may start with `byte`; negations are assumed
+    /// to allow anything
+    pub fn can_start_with(&self, byte: u8) -> bool {
+        let fits = |offset: usize, digits: &HexDigits| {
+            (0..2)
+                .filter(|&i| (offset..offset + digits.len()).contains(&i))
+                .all(|i| digits.0[i - offset] == byte >> (4 * (1 - i)) & 0x0F)
+        };
+        match self {
+            Expr::Prefix(d) => fits(0, d),
+            Expr::Suffix(d) => fits(KEY_NIBBLES - d.len(), d),
+            Expr::At { offset, digits } => fits(*offset, digits),
+            Expr::NodeRange { bytes: 1, min, max } => (*min..=*max).contains(&(byte as u16)),
+            Expr::NodeRange { min, max, .. } => (*min >> 8..=*max >> 8).contains(&(byte as u16)),
+            Expr::Vanity { .. } | Expr::Not(_) => true,
+            Expr::And(parts) => parts.iter().all(|p| p.can_start_with(byte)),
+            Expr::Or(parts) => parts.iter().any(|p| p.can_start_with(byte)),
+        }
+    }
+
+    /// Hex character ranges of the atoms that hold for this key, for
+    /// highlighting (negated atoms are never highlighted)
+    pub fn matched_ranges(&self, public_bytes: &[u8; 32]) -> Vec<std::ops::Range<usize>> {
+        let mut ranges = Vec::new();
+        self.collect_ranges(public_bytes, &mut ranges);
+        ranges
+    }
+
+    fn collect_ranges(&self, b: &[u8; 32], out: &mut Vec<std::ops::Range<usize>>) {
+        let range = match self {
+            Expr::Prefix(d) => 0..d.len(),
+            Expr::Suffix(d) => KEY_NIBBLES - d.len()..KEY_NIBBLES,
+            Expr::At { offset, digits } => *offset..offset + digits.len(),
+            Expr::NodeRange { bytes, .. } => 0..*bytes as usize * 2,
+            Expr::Vanity { length, symmetries } => {
+                for s in symmetries
+                    .iter()
+                    .filter(|s| symmetry::matches_bytes(b, *length, &[**s]))
+                {
+                    out.extend(s.ranges(*length));
+                }
+                return;
+            }
+            Expr::And(parts) | Expr::Or(parts) => {
+                for part in parts {
+                    part.collect_ranges(b, out);
+                }
+                return;
+            }
+            Expr::Not(_) => return,
+        };
+        if self.matches_bytes(b) {
+            out.push(range);
+        }
+    }
+}
+
+/// Whether `digits` appear at hex character `offset`
+#[inline(always)]
+fn digits_at(bytes: &[u8; 32], offset: usize, digits: &HexDigits) -> bool {
+    digits
+        .0
+        .iter()
+        .enumerate()
+        .all(|(i, &d)| key_nibble(bytes, offset + i) == d)
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Parenthesize an `or` inside an `and`, and anything compound under `!`
+        let grouped = |e: &Expr, inside_and: bool| match e {
+            Expr::Or(_) => format!("({})", e),
+            Expr::And(_) if !inside_and => format!("({})", e),
+            _ => e.to_string(),
+        };
+        match self {
+            Expr::Prefix(d) => write!(f, "prefix:{}", d),
+            Expr::Suffix(d) => write!(f, "suffix:{}", d),
+            Expr::At { offset, digits } => write!(f, "at:{}:{}", offset, digits),
+            Expr::NodeRange { bytes, min, max } => {
+                let name = if *bytes == 1 { "node" } else { "node16" };
+                let width = *bytes as usize * 2;
+                if min == max {
+                    write!(f, "{}:{:0w$X}", name, min, w = width)
+                } else {
+                    write!(f, "{}:{:0w$X}-{:0w$X}", name, min, max, w = width)
+                }
+            }
+            Expr::Vanity { length, symmetries } => {
+                write!(f, "vanity:{}", length)?;
+                if *symmetries != default_symmetries() {
+                    let modes: Vec<String> = symmetries.iter().map(|s| s.to_string()).collect();
+                    write!(f, ":{}", modes.join(","))?;
+                }
+                Ok(())
+            }
+            Expr::And(parts) => {
+                let parts: Vec<String> = parts.iter().map(|p| grouped(p, true)).collect();
+                f.write_str(&parts.join(" & "))
+            }
+            Expr::Or(parts) => {
+                let parts: Vec<String> = parts.iter().map(|p| p.to_string()).collect();
+                f.write_str(&parts.join(" | "))
+            }
+            Expr::Not(inner) => write!(f, "!{}", grouped(inner, false)),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if "()&|!".contains(c) {
+            tokens.push(c.to_string());
+            chars.next();
+        } else if c.is_ascii_alphanumeric() {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || ":-,_".contains(c)) {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(match word.to_lowercase().as_str() {
+                "and" => "&".to_string(),
+                "or" => "|".to_string(),
+                "not" => "!".to_string(),
+                _ => word,
+            });
+        } else {
+            return Err(format!("Unexpected character '{}' in expression", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.tokens.get(self.pos).is_some_and(|t| t == token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut parts = vec![self.and()?];
+        while self.eat("|") {
+            parts.push(self.and()?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.remove(0)
+        } else {
+            Expr::Or(parts)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut parts = vec![self.unary()?];
+        while self.eat("&") {
+            parts.push(self.unary()?);
+        }
+        Ok(Expr::all(parts))
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let inner = self.or()?;
+            if !self.eat(")") {
+                return Err("Missing ')' in expression".to_string());
+            }
+            return Ok(inner);
+        }
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or("Expression ends early")?;
+        if token.len() == 1 && "()&|!".contains(token.as_str()) {
+            return Err(format!("Unexpected '{}' in expression", token));
+        }
+        self.pos += 1;
+        parse_atom(&token)
+    }
+}
+
+fn parse_atom(atom: &str) -> Result<Expr, String> {
+    let (name, value) = atom
+        .split_once(':')
+        .ok_or_else(|| format!("'{}' needs a value, e.g. prefix:AB", atom))?;
+    let digits = |value: &str| HexDigits::try_from(value.to_string());
+    match name.to_lowercase().as_str() {
+        "prefix" => Ok(Expr::Prefix(digits(value)?)),
+        "suffix" => Ok(Expr::Suffix(digits(value)?)),
+        "at" | "infix" => {
+            let (offset, value) = value
+                .split_once(':')
+                .ok_or_else(|| format!("'{}' must be at:OFFSET:HEX", atom))?;
+            let offset: usize = offset
+                .parse()
+                .map_err(|_| format!("Invalid offset '{}' in '{}'", offset, atom))?;
+            let digits = digits(value)?;
+            if offset + digits.len() > KEY_NIBBLES {
+                return Err(format!("'{}' runs past the end of the key", atom));
+            }
+            Ok(Expr::At { offset, digits })
+        }
+        "node" => parse_range(value, 1),
+        "node16" => parse_range(value, 2),
+        "vanity" => {
+            let (length, modes) = value.split_once(':').unwrap_or((value, ""));
+            let length: usize = length
+                .parse()
+                .ok()
+                .filter(|n| symmetry::VANITY_LENGTHS.contains(n))
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid vanity length in '{}' (expected {}-{})",
+                        atom,
+                        symmetry::VANITY_LENGTHS.start(),
+                        symmetry::VANITY_LENGTHS.end()
+                    )
+                })?;
+            let symmetries = if modes.is_empty() {
+                default_symmetries()
+            } else {
+                modes
+                    .split(',')
+                    .map(|m| <Symmetry as clap::ValueEnum>::from_str(m, true))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| format!("Unknown symmetry in '{}'", atom))?
+            };
+            Ok(Expr::Vanity { length, symmetries })
+        }
+        _ => Err(format!(
+            "Unknown atom '{}' (expected prefix, suffix, at, node, node16 or vanity)",
+            name
+        )),
+    }
+}
+
+/// `LO-HI` or a single value, in hex, for the first `bytes` bytes
+fn parse_range(value: &str, bytes: u8) -> Result<Expr, String> {
+    let limit = if bytes == 1 { 0xFF } else { 0xFFFF };
+    let number = |s: &str| {
+        u16::from_str_radix(s, 16)
+            .ok()
+            .filter(|&v| v <= limit)
+            .ok_or_else(|| format!("Invalid node value '{}' (hex, at most {:X})", s, limit))
+    };
+    let (min, max) = match value.split_once('-') {
+        Some((lo, hi)) => (number(lo)?, number(hi)?),
+        None => (number(value)?, number(value)?),
+    };
+    if min > max {
+        return Err(format!("Empty node range '{}'", value));
+    }
+    Ok(Expr::NodeRange { bytes, min, max })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::key_bytes;
+
+    #[test]
+    fn test_parse_and_display() {
+        let expr = Expr::parse("(node:10-3f or node16:A000-A0FF) and not suffix:00").unwrap();
+        assert_eq!(
+            expr.to_string(),
+            "(node:10-3F | node16:A000-A0FF) & !suffix:00"
+        );
+        assert_eq!(Expr::parse(&expr.to_string()).unwrap(), expr);
+
+        // `&` binds tighter than `|`
+        let expr = Expr::parse("prefix:A | prefix:B & suffix:C").unwrap();
+        assert!(matches!(&expr, Expr::Or(parts) if matches!(parts[1], Expr::And(_))));
+        assert_eq!(
+            Expr::parse("!(vanity:4:equal,middle & at:8:cafe)")
+                .unwrap()
+                .to_string(),
+            "!(vanity:4:equal,middle & at:8:CAFE)"
+        );
+
+        for bad in [
+            "",
+            "prefix",
+            "prefix:XY",
+            "at:62:ABC",
+            "node:3F-10",
+            "node:100",
+            "vanity:4:sideways",
+            "vanity:1",
+            "vanity:32:rotation",
+            "prefix:A &",
+            "(prefix:A",
+            "prefix:A suffix:B",
+            "colour:red",
+        ] {
+            assert!(Expr::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_on_bytes() {
+        let hex = format!("2ACAFE{}BEEF", "1".repeat(54));
+        let bytes = key_bytes(&hex);
+
+        let holds = |e: &str| Expr::parse(e).unwrap().matches_bytes(&bytes);
+        assert!(holds("prefix:2A & suffix:BEEF"));
+        assert!(holds("at:2:CAFE"));
+        assert!(holds("at:3:AFE1"));
+        assert!(!holds("at:3:CAFE"));
+        assert!(holds("node:10-3F"));
+        assert!(holds("node:2A"));
+        assert!(!holds("node16:2ACB-FFFF"));
+        assert!(holds("!suffix:00 & (prefix:FF | node16:2AC0-2ACF)"));
+        assert!(!holds("not (prefix:2 or suffix:0)"));
+
+        let expr = Expr::parse("node:2A & !suffix:0 & (at:2:CAFE | prefix:FF)").unwrap();
+        assert_eq!(expr.matched_ranges(&bytes), vec![0..2, 2..6]);
+        assert!((Expr::parse("node:00-0F").unwrap().probability() - 1.0 / 16.0).abs() < 1e-12);
+        assert!(holds("node16:0000-FFFF"));
+        assert_eq!(Expr::parse("node16:0000-FFFF").unwrap().probability(), 1.0);
+    }
+
+    #[test]
+    fn test_deserialize_validates() {
+        let expr = Expr::parse("(node:10-3F | at:60:BEEF) & !vanity:4").unwrap();
+        let json = serde_json::to_string(&expr).unwrap();
+        assert_eq!(serde_json::from_str::<Expr>(&json).unwrap(), expr);
+
+        for bad in [
+            r#"{"at":{"offset":62,"digits":"ABC"}}"#,
+            r#"{"at":{"offset":18446744073709551615,"digits":"A"}}"#,
+            r#"{"or":[]}"#,
+            r#"{"and":[]}"#,
+            r#"{"not":{"or":[]}}"#,
+            r#"{"node_range":{"bytes":1,"min":63,"max":16}}"#,
+            r#"{"node_range":{"bytes":1,"min":0,"max":256}}"#,
+            r#"{"node_range":{"bytes":3,"min":0,"max":1}}"#,
+            r#"{"vanity":{"length":40,"symmetries":["equal"]}}"#,
+            r#"{"vanity":{"length":9,"symmetries":["rotation"]}}"#,
+        ] {
+            assert!(serde_json::from_str::<Expr>(bad).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,447 @@
+//! Power and Thermal Governor
+//!
+//! Samples CPU temperature, CPU load from other processes and AC/battery
+//! state, then throttles the worker pool to stay under the configured limits:
+//! one worker fewer per sample while a limit is exceeded, a full pause at one
+//! worker (or on battery), and one worker back per sample once readings drop
+//! below the limit minus a hysteresis margin.
+//!
+//! Temperatures and power state come from `<sysfs root>/class/thermal` and
+//! `<sysfs root>/class/power_supply`; the root is configurable so tests can
+//! point it at a fake tree. With the default root, `sysinfo` components are
+//! used when no thermal zone is readable. Load comes from `sysinfo`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::Sender;
+use sysinfo::{Components, Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::worker::PoolCommand;
+
+/// Default sysfs mount point
+pub const DEFAULT_SYSFS_ROOT: &str = "/sys";
+
+/// Time between samples (and therefore between pool changes)
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Degrees below `max_temp` before workers are added back
+const TEMP_HYSTERESIS: f32 = 5.0;
+
+/// Load percentage points below `max_load` before workers are added back
+const LOAD_HYSTERESIS: f32 = 10.0;
+
+/// Thermal zone types that measure the CPU package or cores
+const CPU_ZONE_TYPES: &[&str] = &["x86_pkg_temp", "cpu", "soc", "k10temp", "coretemp", "tctl"];
+
+/// Limits the governor enforces
+#[derive(Debug, Clone)]
+pub struct GovernorConfig {
+    /// Highest CPU temperature in °C
+    pub max_temp: Option<f32>,
+    /// Highest CPU usage by other processes, in percent of all cores
+    pub max_load: Option<f32>,
+    /// Pause while running on battery
+    pub pause_on_battery: bool,
+    /// Pause while on battery below this charge percentage
+    pub min_battery: Option<u8>,
+    pub sysfs_root: PathBuf,
+}
+
+impl GovernorConfig {
+    /// Whether any limit is set
+    pub fn is_enabled(&self) -> bool {
+        self.max_temp.is_some()
+            || self.max_load.is_some()
+            || self.pause_on_battery
+            || self.min_battery.is_some()
+    }
+}
+
+/// One sample of the machine's state
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Readings {
+    /// Hottest CPU sensor in °C
+    pub temperature: Option<f32>,
+    /// CPU used by other processes, in percent of all cores
+    pub other_load: Option<f32>,
+    pub on_battery: bool,
+    /// Lowest charge across batteries
+    pub battery_percent: Option<u8>,
+}
+
+/// Hottest CPU thermal zone under `root/class/thermal`, in °C
+///
+/// Zones whose type looks like a CPU sensor are preferred; otherwise the
+/// hottest zone of any type is used.
+pub fn read_thermal_zones(root: &Path) -> Option<f32> {
+    let entries = fs::read_dir(root.join("class/thermal")).ok()?;
+    let mut cpu_max: Option<f32> = None;
+    let mut any_max: Option<f32> = None;
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with("thermal_zone") {
+            continue;
+        }
+        let Some(millidegrees) =
+            read_trimmed(&entry.path().join("temp")).and_then(|t| t.parse::<i64>().ok())
+        else {
+            continue;
+        };
+        let celsius = millidegrees as f32 / 1000.0;
+        let zone_type = read_trimmed(&entry.path().join("type"))
+            .unwrap_or_default()
+            .to_lowercase();
+
+        any_max = Some(any_max.map_or(celsius, |m| m.max(celsius)));
+        if CPU_ZONE_TYPES.iter().any(|t| zone_type.starts_with(t)) {
+            cpu_max = Some(cpu_max.map_or(celsius, |m| m.max(celsius)));
+        }
+    }
+    cpu_max.or(any_max)
+}
+
+/// AC/battery state under `root/class/power_supply`: (on battery, lowest charge)
+///
+/// The machine counts as on battery when some battery is discharging and no
+/// mains or USB supply is online.
+pub fn read_power_supply(root: &Path) -> (bool, Option<u8>) {
+    let Ok(entries) = fs::read_dir(root.join("class/power_supply")) else {
+        return (false, None);
+    };
+    let mut ac_online = false;
+    let mut discharging = false;
+    let mut lowest: Option<u8> = None;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match read_trimmed(&path.join("type")).as_deref() {
+            Some("Mains") | Some("USB") => {
+                ac_online |= read_trimmed(&path.join("online")).as_deref() == Some("1");
+            }
+            Some("Battery") => {
+                discharging |= read_trimmed(&path.join("status")).as_deref() == Some("Discharging");
+                if let Some(capacity) =
+                    read_trimmed(&path.join("capacity")).and_then(|c| c.parse::<u8>().ok())
+                {
+                    lowest = Some(lowest.map_or(capacity, |l| l.min(capacity)));
+                }
+            }
+            _ => {}
+        }
+    }
+    (discharging && !ac_online, lowest)
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Why the governor paused the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseReason {
+    Battery,
+    Pressure,
+}
+
+/// Throttling decisions, separate from sampling so they can be tested directly
+#[derive(Debug)]
+pub struct Policy {
+    config: GovernorConfig,
+    paused: Option<PauseReason>,
+    /// Worker count before the first throttle step; scaling up stops here
+    ceiling: Option<usize>,
+}
+
+impl Policy {
+    pub fn new(config: GovernorConfig) -> Self {
+        Self {
+            config,
+            paused: None,
+            ceiling: None,
+        }
+    }
+
+    /// Pool command for one sample, plus a notice describing the change
+    pub fn decide(&mut self, readings: &Readings, workers: usize) -> Option<(PoolCommand, String)> {
+        let config = &self.config;
+        let low_battery = config
+            .min_battery
+            .zip(readings.battery_percent)
+            .is_some_and(|(min, percent)| percent < min);
+        if readings.on_battery && (config.pause_on_battery || low_battery) {
+            if self.paused == Some(PauseReason::Battery) {
+                return None;
+            }
+            self.paused = Some(PauseReason::Battery);
+            let notice = match readings.battery_percent {
+                Some(percent) => format!("On battery ({}%), pausing workers", percent),
+                None => "On battery, pausing workers".to_string(),
+            };
+            return Some((PoolCommand::Pause, notice));
+        }
+        if self.paused == Some(PauseReason::Battery) {
+            self.paused = None;
+            return Some((
+                PoolCommand::Resume,
+                "Battery limit cleared, resuming workers".to_string(),
+            ));
+        }
+
+        let hot = config
+            .max_temp
+            .zip(readings.temperature)
+            .is_some_and(|(max, temp)| temp >= max);
+        let busy = config
+            .max_load
+            .zip(readings.other_load)
+            .is_some_and(|(max, load)| load > max);
+        let cool = config
+            .max_temp
+            .zip(readings.temperature)
+            .is_none_or(|(max, temp)| temp <= max - TEMP_HYSTERESIS);
+        let idle = config
+            .max_load
+            .zip(readings.other_load)
+            .is_none_or(|(max, load)| load <= max - LOAD_HYSTERESIS);
+
+        let cause = if hot {
+            format!("CPU at {:.0}°C", readings.temperature.unwrap_or_default())
+        } else {
+            format!(
+                "other processes using {:.0}% CPU",
+                readings.other_load.unwrap_or_default()
+            )
+        };
+
+        if self.paused == Some(PauseReason::Pressure) {
+            if cool && idle {
+                self.paused = None;
+                return Some((
+                    PoolCommand::Resume,
+                    "Limits cleared, resuming workers".to_string(),
+                ));
+            }
+            return None;
+        }
+
+        if hot || busy {
+            self.ceiling.get_or_insert(workers);
+            if workers > 1 {
+                let target = workers - 1;
+                return Some((
+                    PoolCommand::SetWorkers(target),
+                    format!(
+                        "{}, throttling to {} worker{}",
+                        cause,
+                        target,
+                        if target == 1 { "" } else { "s" }
+                    ),
+                ));
+            }
+            self.paused = Some(PauseReason::Pressure);
+            return Some((PoolCommand::Pause, format!("{}, pausing workers", cause)));
+        }
+
+        if cool && idle {
+            if let Some(ceiling) = self.ceiling {
+                if workers < ceiling {
+                    let target = workers + 1;
+                    if target == ceiling {
+                        self.ceiling = None;
+                    }
+                    return Some((
+                        PoolCommand::SetWorkers(target),
+                        format!("Limits cleared, scaling back to {} workers", target),
+                    ));
+                }
+                self.ceiling = None;
+            }
+        }
+        None
+    }
+}
+
+/// Samples the machine and drives the pool through the control channel
+pub struct Governor {
+    policy: Policy,
+    sysfs_root: PathBuf,
+    system: System,
+    components: Option<Components>,
+    pid: Option<Pid>,
+    next_sample: Instant,
+    /// Skips the first load reading, which has no previous sample to diff against
+    load_primed: bool,
+}
+
+impl Governor {
+    pub fn new(config: GovernorConfig) -> Self {
+        let sysfs_root = config.sysfs_root.clone();
+        let components =
+            (sysfs_root == Path::new(DEFAULT_SYSFS_ROOT)).then(Components::new_with_refreshed_list);
+        Self {
+            policy: Policy::new(config),
+            sysfs_root,
+            system: System::new(),
+            components,
+            pid: sysinfo::get_current_pid().ok(),
+            next_sample: Instant::now(),
+            load_primed: false,
+        }
+    }
+
+    /// Take a sample of temperature, load and power state
+    pub fn sample(&mut self) -> Readings {
+        let temperature = read_thermal_zones(&self.sysfs_root).or_else(|| {
+            let components = self.components.as_mut()?;
+            components.refresh(false);
+            components
+                .iter()
+                .filter_map(|c| c.temperature())
+                .filter(|t| t.is_finite())
+                .reduce(f32::max)
+        });
+
+        self.system.refresh_cpu_usage();
+        let own = self.pid.and_then(|pid| {
+            self.system.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&[pid]),
+                false,
+                ProcessRefreshKind::nothing().with_cpu(),
+            );
+            self.system.process(pid).map(|p| p.cpu_usage())
+        });
+        let cores = self.system.cpus().len().max(1) as f32;
+        let other_load = self.load_primed.then(|| {
+            (self.system.global_cpu_usage() - own.unwrap_or(0.0) / cores).clamp(0.0, 100.0)
+        });
+        self.load_primed = true;
+
+        let (on_battery, battery_percent) = read_power_supply(&self.sysfs_root);
+        Readings {
+            temperature,
+            other_load,
+            on_battery,
+            battery_percent,
+        }
+    }
+
+    /// Sample when due and queue any resulting pool change; returns its notice
+    pub fn tick(&mut self, workers: usize, control: &Sender<PoolCommand>) -> Option<String> {
+        if Instant::now() < self.next_sample {
+            return None;
+        }
+        self.next_sample = Instant::now() + SAMPLE_INTERVAL;
+        let readings = self.sample();
+        let (command, notice) = self.policy.decide(&readings, workers)?;
+        control.send(command).ok()?;
+        Some(notice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn config(root: &Path) -> GovernorConfig {
+        GovernorConfig {
+            max_temp: Some(80.0),
+            max_load: None,
+            pause_on_battery: true,
+            min_battery: None,
+            sysfs_root: root.to_path_buf(),
+        }
+    }
+
+    #[test]
+    fn test_reads_fake_sysfs_tree() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(root, "class/thermal/thermal_zone0/type", "acpitz\n");
+        write(root, "class/thermal/thermal_zone0/temp", "95000\n");
+        write(root, "class/thermal/thermal_zone1/type", "x86_pkg_temp\n");
+        write(root, "class/thermal/thermal_zone1/temp", "71500\n");
+        write(root, "class/power_supply/AC/type", "Mains\n");
+        write(root, "class/power_supply/AC/online", "0\n");
+        write(root, "class/power_supply/BAT0/type", "Battery\n");
+        write(root, "class/power_supply/BAT0/status", "Discharging\n");
+        write(root, "class/power_supply/BAT0/capacity", "42\n");
+
+        // The CPU package sensor wins over the hotter ACPI zone
+        assert_eq!(read_thermal_zones(root), Some(71.5));
+        assert_eq!(read_power_supply(root), (true, Some(42)));
+
+        write(root, "class/power_supply/AC/online", "1\n");
+        assert_eq!(read_power_supply(root), (false, Some(42)));
+
+        let mut governor = Governor::new(config(root));
+        let readings = governor.sample();
+        assert_eq!(readings.temperature, Some(71.5));
+        assert_eq!(readings.other_load, None);
+        assert!(!readings.on_battery);
+    }
+
+    #[test]
+    fn test_thermal_throttle_pause_and_recover() {
+        let dir = tempdir().unwrap();
+        let mut policy = Policy::new(config(dir.path()));
+        let at = |temp: f32| Readings {
+            temperature: Some(temp),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            policy.decide(&at(85.0), 2).unwrap().0,
+            PoolCommand::SetWorkers(1)
+        );
+        assert_eq!(policy.decide(&at(85.0), 1).unwrap().0, PoolCommand::Pause);
+        // Still inside the hysteresis band: stay paused
+        assert!(policy.decide(&at(77.0), 1).is_none());
+        assert_eq!(policy.decide(&at(70.0), 1).unwrap().0, PoolCommand::Resume);
+        assert_eq!(
+            policy.decide(&at(70.0), 1).unwrap().0,
+            PoolCommand::SetWorkers(2)
+        );
+        // Back at the starting count: nothing more to restore
+        assert!(policy.decide(&at(70.0), 2).is_none());
+    }
+
+    #[test]
+    fn test_battery_pause_and_load_throttle() {
+        let dir = tempdir().unwrap();
+        let mut policy = Policy::new(GovernorConfig {
+            max_temp: None,
+            max_load: Some(50.0),
+            ..config(dir.path())
+        });
+        let battery = Readings {
+            on_battery: true,
+            battery_percent: Some(80),
+            ..Default::default()
+        };
+        assert_eq!(policy.decide(&battery, 4).unwrap().0, PoolCommand::Pause);
+        assert!(policy.decide(&battery, 4).is_none());
+        assert_eq!(
+            policy.decide(&Readings::default(), 4).unwrap().0,
+            PoolCommand::Resume
+        );
+
+        let busy = Readings {
+            other_load: Some(65.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.decide(&busy, 4).unwrap().0,
+            PoolCommand::SetWorkers(3)
+        );
+    }
+}
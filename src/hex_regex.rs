@@ -0,0 +1,633 @@
+//! Regular Expressions over the Public Key Hex
+//!
+//! `--regex` patterns such as `^(AB|CD)[0-9]{2}.*F{4}$` are compiled once into
+//! a DFA over the 16 hex digits, which the hot path steps a whole key byte
+//! (two nibbles) at a time without encoding the key as hex.
+//!
+//! Supported syntax: hex digits (either case), `.`, `\d`, classes such as
+//! `[0-9A-F]` or `[^0]`, groups `(...)` and `(?:...)`, `|`, and the
+//! quantifiers `*`, `+`, `?`, `{n}`, `{n,}` and `{n,m}`. `^` and `$` anchor
+//! the pattern to the start and end of the 64-character key; without them the
+//! pattern may match anywhere. Letters beyond F can never match.
+//!
+//! Every key has exactly 64 nibbles, so for each position the compiler records
+//! which DFA states can still reach a match in the remaining nibbles. A key is
+//! rejected as soon as it leaves those states, patterns that no key can match
+//! are refused, and the match probability is exact.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::pattern::{key_nibble, KEY_NIBBLES};
+
+/// Upper bound on DFA states, to keep the byte table small
+const MAX_STATES: usize = 4096;
+
+/// Upper bound on NFA states; nested repeats multiply, so this is checked
+/// on the parsed pattern before anything is expanded
+const MAX_NFA_STATES: usize = 4096;
+
+/// A set of hex digits, one bit per nibble value
+type Class = u16;
+
+const ANY: Class = 0xFFFF;
+
+/// Compiled regular expression over the key's 64 hex characters
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HexRegex {
+    source: String,
+    states: usize,
+    /// `next[state * 16 + nibble]`
+    nibble_next: Vec<u32>,
+    /// `next[state * 256 + byte]`, two nibble steps
+    byte_next: Vec<u32>,
+    /// `live[position * states + state]`: a match is still reachable from
+    /// `state` after `position` nibbles
+    live: Vec<bool>,
+    probability: f64,
+}
+
+impl HexRegex {
+    /// Compile `source`; usable as a clap value parser
+    pub fn new(source: &str) -> Result<Self, String> {
+        let mut body = source.trim();
+        let anchored_start = body.starts_with('^');
+        if anchored_start {
+            body = &body[1..];
+        }
+        let anchored_end = body.ends_with('$') && !body.ends_with("\\$");
+        if anchored_end {
+            body = &body[..body.len() - 1];
+        }
+
+        let mut parser = Parser {
+            chars: body.chars().collect(),
+            pos: 0,
+        };
+        let mut parts = Vec::new();
+        if !anchored_start {
+            parts.push(Node::any_run());
+        }
+        parts.push(parser.alternation()?);
+        if let Some(c) = parser.peek() {
+            return Err(format!("Unexpected '{}' in regex", c));
+        }
+        if !anchored_end {
+            parts.push(Node::any_run());
+        }
+
+        let root = Node::Concat(parts);
+        if root.nfa_states() > MAX_NFA_STATES {
+            return Err(format!(
+                "Regex is too complex (more than {} NFA states once repeats are expanded)",
+                MAX_NFA_STATES
+            ));
+        }
+        let mut nfa = Nfa::default();
+        let start = nfa.add();
+        let accept = root.build(&mut nfa, start);
+        let (nibble_next, accepting, states) = nfa.determinize(start, accept)?;
+
+        // Walk back from the end: live after 64 nibbles means accepting
+        let mut live = vec![false; (KEY_NIBBLES + 1) * states];
+        live[KEY_NIBBLES * states..].copy_from_slice(&accepting);
+        for position in (0..KEY_NIBBLES).rev() {
+            let (now, later) = live.split_at_mut((position + 1) * states);
+            let later = &later[..states];
+            for (state, alive) in now[position * states..].iter_mut().enumerate() {
+                *alive = (0..16).any(|n| later[nibble_next[state * 16 + n] as usize]);
+            }
+        }
+        if !live[0] {
+            return Err(format!("Regex '{}' can never match a public key", source));
+        }
+
+        // Forward: probability of each state after each nibble
+        let mut odds = vec![0.0; states];
+        odds[0] = 1.0;
+        for _ in 0..KEY_NIBBLES {
+            let mut next = vec![0.0; states];
+            for (state, p) in odds.iter().enumerate().filter(|(_, p)| **p > 0.0) {
+                for n in 0..16 {
+                    next[nibble_next[state * 16 + n] as usize] += p / 16.0;
+                }
+            }
+            odds = next;
+        }
+        let probability = odds
+            .iter()
+            .zip(&accepting)
+            .filter(|(_, accept)| **accept)
+            .map(|(p, _)| p)
+            .sum();
+
+        let mut byte_next = vec![0; states * 256];
+        for state in 0..states {
+            for byte in 0..256 {
+                let mid = nibble_next[state * 16 + (byte >> 4)] as usize;
+                byte_next[state * 256 + byte] = nibble_next[mid * 16 + (byte & 0x0F)];
+            }
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            states,
+            nibble_next,
+            byte_next,
+            live,
+            probability,
+        })
+    }
+
+    /// Number of DFA states
+    pub fn state_count(&self) -> usize {
+        self.states
+    }
+
+    /// Exact probability that a random key matches
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+
+    /// Evaluate on raw public key bytes (hot path)
+    #[inline]
+    pub fn matches_bytes(&self, public_bytes: &[u8; 32]) -> bool {
+        let mut state = 0;
+        for (i, &byte) in public_bytes.iter().enumerate() {
+            state = self.byte_next[state * 256 + byte as usize] as usize;
+            if !self.live[(2 * i + 2) * self.states + state] {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// A uniformly random nibble path through the DFA that matches
+    pub fn sample(&self, rng: &mut impl rand::Rng) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        let mut state = 0;
+        for position in 0..KEY_NIBBLES {
+            let later = (position + 1) * self.states;
+            let live: Vec<u8> = (0..16u8)
+                .filter(|&n| self.live[later + self.nibble_next[state * 16 + n as usize] as usize])
+                .collect();
+            let nibble = live[rng.gen_range(0..live.len())];
+            bytes[position / 2] |= if position.is_multiple_of(2) {
+                nibble << 4
+            } else {
+                nibble
+            };
+            state = self.nibble_next[state * 16 + nibble as usize] as usize;
+        }
+        bytes
+    }
+
+    /// Whether a matching key can start with `byte`
+    pub fn can_start_with(&self, byte: u8) -> bool {
+        let state = self.byte_next[byte as usize] as usize;
+        self.live[2 * self.states + state]
+    }
+
+    /// Hex character ranges of a matching key where the pattern left no
+    /// free choice, i.e. some other digit there could not have matched
+    pub fn forced_ranges(&self, public_bytes: &[u8; 32]) -> Vec<std::ops::Range<usize>> {
+        let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
+        let mut state = 0;
+        for position in 0..KEY_NIBBLES {
+            let nibble = key_nibble(public_bytes, position);
+            let later = (position + 1) * self.states;
+            let forced =
+                (0..16).any(|n| !self.live[later + self.nibble_next[state * 16 + n] as usize]);
+            if forced {
+                match ranges.last_mut() {
+                    Some(range) if range.end == position => range.end += 1,
+                    _ => ranges.push(position..position + 1),
+                }
+            }
+            state = self.nibble_next[state * 16 + nibble as usize] as usize;
+        }
+        ranges
+    }
+}
+
+impl PartialEq for HexRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl fmt::Debug for HexRegex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HexRegex")
+            .field("source", &self.source)
+            .field("states", &self.states)
+            .finish()
+    }
+}
+
+impl fmt::Display for HexRegex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for HexRegex {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Self::new(&source)
+    }
+}
+
+impl From<HexRegex> for String {
+    fn from(regex: HexRegex) -> Self {
+        regex.source
+    }
+}
+
+/// Parsed regex
+enum Node {
+    Class(Class),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+impl Node {
+    /// `.*`
+    fn any_run() -> Self {
+        Node::Repeat {
+            node: Box::new(Node::Class(ANY)),
+            min: 0,
+            max: None,
+        }
+    }
+
+    /// States `build` adds for this node, saturating
+    fn nfa_states(&self) -> usize {
+        match self {
+            Node::Class(_) => 2,
+            Node::Concat(parts) => parts
+                .iter()
+                .fold(0, |total, part| total.saturating_add(part.nfa_states())),
+            Node::Alternation(choices) => choices.iter().fold(1, |total, choice| {
+                total.saturating_add(choice.nfa_states()).saturating_add(1)
+            }),
+            Node::Repeat { node, min, max } => {
+                let each = node.nfa_states();
+                match max {
+                    None => each.saturating_mul(min + 1).saturating_add(1),
+                    Some(max) => each.saturating_mul(*max).saturating_add(max - min),
+                }
+            }
+        }
+    }
+
+    /// Add this node to `nfa` starting at `from`; returns its end state
+    fn build(&self, nfa: &mut Nfa, from: usize) -> usize {
+        match self {
+            Node::Class(class) => {
+                // `from` may already carry an edge, e.g. the hub of a `*`
+                let (at, to) = (nfa.add(), nfa.add());
+                nfa.epsilon[from].push(at);
+                nfa.edges[at] = Some((*class, to));
+                to
+            }
+            Node::Concat(parts) => parts.iter().fold(from, |at, part| part.build(nfa, at)),
+            Node::Alternation(choices) => {
+                let end = nfa.add();
+                for choice in choices {
+                    let start = nfa.add();
+                    nfa.epsilon[from].push(start);
+                    let last = choice.build(nfa, start);
+                    nfa.epsilon[last].push(end);
+                }
+                end
+            }
+            Node::Repeat { node, min, max } => {
+                let mut at = from;
+                for _ in 0..*min {
+                    at = node.build(nfa, at);
+                }
+                match max {
+                    None => {
+                        let hub = nfa.add();
+                        nfa.epsilon[at].push(hub);
+                        let last = node.build(nfa, hub);
+                        nfa.epsilon[last].push(hub);
+                        hub
+                    }
+                    Some(max) => {
+                        for _ in *min..*max {
+                            let skip = nfa.add();
+                            nfa.epsilon[at].push(skip);
+                            let last = node.build(nfa, at);
+                            nfa.epsilon[last].push(skip);
+                            at = skip;
+                        }
+                        at
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Thompson NFA: each state has epsilon moves and at most one class edge
+#[derive(Default)]
+struct Nfa {
+    epsilon: Vec<Vec<usize>>,
+    edges: Vec<Option<(Class, usize)>>,
+}
+
+impl Nfa {
+    fn add(&mut self) -> usize {
+        self.epsilon.push(Vec::new());
+        self.edges.push(None);
+        self.epsilon.len() - 1
+    }
+
+    fn closure(&self, mut stack: Vec<usize>) -> Vec<usize> {
+        let mut seen = vec![false; self.epsilon.len()];
+        let mut set = Vec::new();
+        while let Some(state) = stack.pop() {
+            if std::mem::replace(&mut seen[state], true) {
+                continue;
+            }
+            set.push(state);
+            stack.extend(&self.epsilon[state]);
+        }
+        set.sort_unstable();
+        set
+    }
+
+    /// Subset construction; returns the nibble table, accepting flags and
+    /// state count, with the start state at 0
+    fn determinize(
+        &self,
+        start: usize,
+        accept: usize,
+    ) -> Result<(Vec<u32>, Vec<bool>, usize), String> {
+        let mut ids: HashMap<Vec<usize>, u32> = HashMap::new();
+        let mut sets = vec![self.closure(vec![start])];
+        ids.insert(sets[0].clone(), 0);
+        let mut table = Vec::new();
+
+        let mut current = 0;
+        while current < sets.len() {
+            for nibble in 0..16 {
+                let moved: Vec<usize> = sets[current]
+                    .iter()
+                    .filter_map(|&s| self.edges[s])
+                    .filter(|(class, _)| class & (1 << nibble) != 0)
+                    .map(|(_, to)| to)
+                    .collect();
+                let target = self.closure(moved);
+                let id = match ids.get(&target) {
+                    Some(&id) => id,
+                    None => {
+                        if sets.len() >= MAX_STATES {
+                            return Err(format!(
+                                "Regex is too complex (more than {} DFA states)",
+                                MAX_STATES
+                            ));
+                        }
+                        ids.insert(target.clone(), sets.len() as u32);
+                        sets.push(target);
+                        sets.len() as u32 - 1
+                    }
+                };
+                table.push(id);
+            }
+            current += 1;
+        }
+
+        let accepting = sets.iter().map(|set| set.contains(&accept)).collect();
+        Ok((table, accepting, sets.len()))
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut choices = vec![self.concat()?];
+        while self.eat('|') {
+            choices.push(self.concat()?);
+        }
+        Ok(if choices.len() == 1 {
+            choices.remove(0)
+        } else {
+            Node::Alternation(choices)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut parts = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let mut node = self.atom()?;
+            while let Some((min, max)) = self.quantifier()? {
+                node = Node::Repeat {
+                    node: Box::new(node),
+                    min,
+                    max,
+                };
+            }
+            parts.push(node);
+        }
+        Ok(Node::Concat(parts))
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or("Regex ends early")?;
+        self.pos += 1;
+        match c {
+            '(' => {
+                if self.eat('?') && !self.eat(':') {
+                    return Err("Only (?:...) groups are supported".to_string());
+                }
+                let inner = self.alternation()?;
+                if !self.eat(')') {
+                    return Err("Missing ')' in regex".to_string());
+                }
+                Ok(inner)
+            }
+            '[' => self.class().map(Node::Class),
+            '.' => Ok(Node::Class(ANY)),
+            '\\' => self.escape().map(Node::Class),
+            '*' | '+' | '?' | '{' => Err(format!("Nothing to repeat before '{}'", c)),
+            '^' | '$' => Err(format!(
+                "'{}' is only supported at the edge of the regex",
+                c
+            )),
+            c if c.is_ascii_alphanumeric() => Ok(Node::Class(literal(c))),
+            c => Err(format!("Unexpected '{}' in regex", c)),
+        }
+    }
+
+    fn escape(&mut self) -> Result<Class, String> {
+        match self.peek() {
+            Some('d') => {
+                self.pos += 1;
+                Ok(0x03FF)
+            }
+            Some(c) => Err(format!("Unsupported escape '\\{}' in regex", c)),
+            None => Err("Regex ends with '\\'".to_string()),
+        }
+    }
+
+    fn class(&mut self) -> Result<Class, String> {
+        let negated = self.eat('^');
+        let mut class: Class = 0;
+        loop {
+            let c = self.peek().ok_or("Missing ']' in regex")?;
+            self.pos += 1;
+            match c {
+                ']' => break,
+                '\\' => class |= self.escape()?,
+                c if c.is_ascii_alphanumeric() => {
+                    if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                        self.pos += 1;
+                        let end = self.peek().ok_or("Missing ']' in regex")?;
+                        self.pos += 1;
+                        class |= range(c, end)?;
+                    } else {
+                        class |= literal(c);
+                    }
+                }
+                c => return Err(format!("Unexpected '{}' in regex class", c)),
+            }
+        }
+        Ok(if negated { !class } else { class })
+    }
+
+    /// `*`, `+`, `?` or `{n}`, `{n,}`, `{n,m}` as (min, max)
+    fn quantifier(&mut self) -> Result<Option<(usize, Option<usize>)>, String> {
+        let quantifier = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                let close = self.chars[self.pos..]
+                    .iter()
+                    .position(|&c| c == '}')
+                    .ok_or("Missing '}' in regex")?;
+                let body: String = self.chars[self.pos + 1..self.pos + close].iter().collect();
+                self.pos += close;
+                let count = |s: &str| {
+                    s.trim()
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&n| n <= KEY_NIBBLES)
+                        .ok_or_else(|| format!("Invalid repeat count '{{{}}}' (0-64)", body))
+                };
+                match body.split_once(',') {
+                    None => (count(&body)?, Some(count(&body)?)),
+                    Some((min, "")) => (count(min)?, None),
+                    Some((min, max)) => {
+                        let (min, max) = (count(min)?, count(max)?);
+                        if min > max {
+                            return Err(format!("Invalid repeat count '{{{}}}'", body));
+                        }
+                        (min, Some(max))
+                    }
+                }
+            }
+            _ => return Ok(None),
+        };
+        self.pos += 1;
+        Ok(Some(quantifier))
+    }
+}
+
+/// A character as a class; letters beyond F match nothing
+fn literal(c: char) -> Class {
+    c.to_digit(16).map_or(0, |d| 1 << d)
+}
+
+fn range(start: char, end: char) -> Result<Class, String> {
+    match (start.to_digit(16), end.to_digit(16)) {
+        (Some(lo), Some(hi)) if lo <= hi => Ok((lo..=hi).fold(0, |class, d| class | 1 << d)),
+        _ => Err(format!("Invalid hex range '{}-{}' in regex", start, end)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::key_bytes;
+
+    #[test]
+    fn test_matches_and_probability() {
+        let regex = HexRegex::new("^(AB|cd)[0-9]{2}.*F{4}$").unwrap();
+        assert!(regex.matches_bytes(&key_bytes(&format!("CD42{}FFFF", "0".repeat(56)))));
+        assert!(!regex.matches_bytes(&key_bytes(&format!("CD4A{}FFFF", "0".repeat(56)))));
+        assert!(!regex.matches_bytes(&key_bytes(&format!("AB42{}FFF0", "0".repeat(56)))));
+        let expected = 2.0 / 256.0 * (10.0f64 / 16.0).powi(2) / 65536.0;
+        assert!((regex.probability() - expected).abs() < expected * 1e-9);
+
+        // Unanchored patterns may match anywhere
+        let regex = HexRegex::new("(?:BEEF)+").unwrap();
+        assert!(regex.matches_bytes(&key_bytes(&format!(
+            "{}BEEF{}",
+            "1".repeat(30),
+            "2".repeat(30)
+        ))));
+        assert!(!regex.matches_bytes(&key_bytes(&"0".repeat(64))));
+
+        // Exact: ".{63}0" is 1/16, "[^0]{64}" is (15/16)^64
+        assert!((HexRegex::new("^.{63}0$").unwrap().probability() - 1.0 / 16.0).abs() < 1e-12);
+        let none_zero = HexRegex::new("^[^0]{64}$").unwrap().probability();
+        assert!((none_zero - (15.0f64 / 16.0).powi(64)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_rejects_impossible_and_invalid() {
+        for impossible in ["^.{40}.{25}", "^G", "^A{30}$", "^[0-3]{40}Q"] {
+            let error = HexRegex::new(impossible).unwrap_err();
+            assert!(error.contains("can never match"), "{}", impossible);
+        }
+        // An impossible branch is fine while another can match
+        assert!(HexRegex::new("^(XYZ|AB)").is_ok());
+
+        for invalid in [
+            "(AB", "AB)", "[A-", "[F-A]", "*A", "A{2", "A{99}", "A^B", "\\w",
+        ] {
+            assert!(HexRegex::new(invalid).is_err(), "{}", invalid);
+        }
+        // Nested repeats multiply; rejected before the NFA is built
+        let nested = HexRegex::new("((((0{64}){64}){64}){64})").unwrap_err();
+        assert!(nested.contains("too complex"), "{}", nested);
+
+        let regex = HexRegex::new("^AB.*9$").unwrap();
+        let hex = format!("AB{}9", "5".repeat(61));
+        assert_eq!(regex.forced_ranges(&key_bytes(&hex)), vec![0..2, 63..64]);
+        let json = serde_json::to_string(&regex).unwrap();
+        assert_eq!(json, "\"^AB.*9$\"");
+        assert_eq!(serde_json::from_str::<HexRegex>(&json).unwrap(), regex);
+    }
+}
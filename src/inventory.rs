@@ -0,0 +1,290 @@
+//! Node-ID Inventory
+//!
+//! `--fill-node-ids N` keeps searching until there are N keys for every
+//! usable node ID, or for the `--node-ids` subset. The node ID is the first
+//! public-key byte, and `00` and `FF` are reserved (see
+//! `keygen::is_valid_meshcore_prefix`). Keys are saved in one folder per
+//! node ID, so a free ID can be handed out right away.
+//!
+//! Main owns the per-ID counts. The CPU workers share a `NodeIdSet` of the
+//! IDs that still need keys and skip every other key before the pattern
+//! check. Keys already under the output directory count toward the target,
+//! so a later run tops up the same stock.
+
+use console::style;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Node IDs MeshCore reserves
+const RESERVED: [u8; 2] = [0x00, 0xFF];
+
+/// Every node ID a key may use
+pub fn usable_node_ids() -> Vec<u8> {
+    (0..=255u8).filter(|id| !RESERVED.contains(id)).collect()
+}
+
+/// Parse a node ID list like `10-3F,42`
+pub fn parse_node_ids(list: &str) -> Result<Vec<u8>, String> {
+    let byte = |s: &str| {
+        u8::from_str_radix(s.trim(), 16).map_err(|_| format!("Invalid node ID '{}'", s.trim()))
+    };
+    let mut ids = Vec::new();
+    for part in list.split(',').filter(|p| !p.trim().is_empty()) {
+        let (lo, hi) = match part.split_once('-') {
+            Some((lo, hi)) => (byte(lo)?, byte(hi)?),
+            None => (byte(part)?, byte(part)?),
+        };
+        if lo > hi {
+            return Err(format!("Empty node ID range '{}'", part.trim()));
+        }
+        for id in lo..=hi {
+            if RESERVED.contains(&id) {
+                return Err(format!("Node ID {:02X} is reserved by MeshCore", id));
+            }
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    if ids.is_empty() {
+        return Err("No node IDs given".to_string());
+    }
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+/// Format sorted node IDs as a list like `10-3F, 42`
+pub fn format_node_ids(ids: &[u8]) -> String {
+    let mut runs: Vec<(u8, u8)> = Vec::new();
+    for &id in ids {
+        match runs.last_mut() {
+            Some((_, hi)) if *hi as u16 + 1 == id as u16 => *hi = id,
+            _ => runs.push((id, id)),
+        }
+    }
+    runs.iter()
+        .map(|&(lo, hi)| {
+            if lo == hi {
+                format!("{:02X}", lo)
+            } else {
+                format!("{:02X}-{:02X}", lo, hi)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A set of node IDs that workers read while main updates it
+#[derive(Debug, Default)]
+pub struct NodeIdSet {
+    bits: [AtomicU64; 4],
+}
+
+impl NodeIdSet {
+    #[inline(always)]
+    pub fn contains(&self, id: u8) -> bool {
+        self.bits[id as usize / 64].load(Ordering::Relaxed) & (1 << (id % 64)) != 0
+    }
+
+    fn insert(&self, id: u8) {
+        self.bits[id as usize / 64].fetch_or(1 << (id % 64), Ordering::Relaxed);
+    }
+
+    fn remove(&self, id: u8) {
+        self.bits[id as usize / 64].fetch_and(!(1 << (id % 64)), Ordering::Relaxed);
+    }
+}
+
+/// Coverage for the run summary
+#[derive(Debug, Serialize)]
+pub struct Coverage {
+    pub keys_per_id: usize,
+    pub ids_wanted: usize,
+    pub ids_full: usize,
+    /// Node IDs still short of `keys_per_id`
+    pub ids_missing: Vec<String>,
+}
+
+/// Keys held per node ID against the target
+#[derive(Debug)]
+pub struct Inventory {
+    per_id: usize,
+    wanted: Vec<u8>,
+    counts: [usize; 256],
+    open: Arc<NodeIdSet>,
+}
+
+impl Inventory {
+    pub fn new(per_id: usize, ids: &[u8]) -> Self {
+        let open = Arc::new(NodeIdSet::default());
+        ids.iter().for_each(|&id| open.insert(id));
+        Self {
+            per_id,
+            wanted: ids.to_vec(),
+            counts: [0; 256],
+            open,
+        }
+    }
+
+    /// Count keys already in stock, given as public key hex
+    pub fn seed<'a>(&mut self, public_keys: impl IntoIterator<Item = &'a String>) {
+        for hex in public_keys {
+            if let Some(id) = hex.get(..2).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                self.record(id);
+            }
+        }
+    }
+
+    /// IDs that still need keys, for the workers
+    pub fn open_set(&self) -> Arc<NodeIdSet> {
+        self.open.clone()
+    }
+
+    /// Whether a key with node ID `id` is still wanted
+    pub fn needs(&self, id: u8) -> bool {
+        self.open.contains(id)
+    }
+
+    /// Count a key for `id`; true if that completed the ID
+    pub fn record(&mut self, id: u8) -> bool {
+        self.counts[id as usize] += 1;
+        let filled = self.open.contains(id) && self.counts[id as usize] >= self.per_id;
+        if filled {
+            self.open.remove(id);
+        }
+        filled
+    }
+
+    pub fn per_id(&self) -> usize {
+        self.per_id
+    }
+
+    pub fn wanted_count(&self) -> usize {
+        self.wanted.len()
+    }
+
+    pub fn full_count(&self) -> usize {
+        self.wanted.iter().filter(|&&id| !self.needs(id)).count()
+    }
+
+    /// Keys still needed to complete every wanted ID
+    pub fn remaining(&self) -> usize {
+        self.wanted
+            .iter()
+            .map(|&id| self.per_id.saturating_sub(self.counts[id as usize]))
+            .sum()
+    }
+
+    /// Expected attempts to finish, for keys matching the pattern with
+    /// `probability`: coupon collecting over the open IDs, plus the extra
+    /// keys the most-lacking ID needs
+    pub fn expected_attempts(&self, probability: f64) -> f64 {
+        let open: Vec<u8> = self
+            .wanted
+            .iter()
+            .copied()
+            .filter(|&id| self.needs(id))
+            .collect();
+        let Some(deficit) = open
+            .iter()
+            .map(|&id| self.per_id - self.counts[id as usize])
+            .max()
+        else {
+            return 0.0;
+        };
+        let harmonic: f64 = (1..=open.len()).map(|k| 1.0 / k as f64).sum();
+        256.0 / probability * (harmonic + (deficit - 1) as f64)
+    }
+
+    pub fn coverage(&self) -> Coverage {
+        Coverage {
+            keys_per_id: self.per_id,
+            ids_wanted: self.wanted_count(),
+            ids_full: self.full_count(),
+            ids_missing: self
+                .wanted
+                .iter()
+                .filter(|&&id| self.needs(id))
+                .map(|id| format!("{:02X}", id))
+                .collect(),
+        }
+    }
+
+    /// Print a 16×16 grid of node IDs: full, partly stocked, empty, or not wanted
+    pub fn print_grid(&self) {
+        println!(
+            "  Node IDs: {} full  {} partial  ░ empty  {} not wanted",
+            style("█").green(),
+            style("▒").yellow(),
+            style("·").dim()
+        );
+        println!(
+            "       {}",
+            (0..16)
+                .map(|c| format!("{:X}", c))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        for row in 0..16u8 {
+            let cells: Vec<String> = (0..16u8)
+                .map(|col| {
+                    let id = row << 4 | col;
+                    let count = self.counts[id as usize];
+                    if !self.wanted.contains(&id) {
+                        style("·").dim().to_string()
+                    } else if count >= self.per_id {
+                        style("█").green().to_string()
+                    } else if count > 0 {
+                        style("▒").yellow().to_string()
+                    } else {
+                        "░".to_string()
+                    }
+                })
+                .collect();
+            println!("    {:X}x {}", row, cells.join(" "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_node_ids() {
+        assert_eq!(
+            parse_node_ids("10-12, 42,11").unwrap(),
+            vec![0x10, 0x11, 0x12, 0x42]
+        );
+        assert_eq!(usable_node_ids().len(), 254);
+        for bad in ["", "GG", "3F-10", "00", "F0-FF"] {
+            assert!(parse_node_ids(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(
+            format_node_ids(&[0x01, 0x10, 0x11, 0x12, 0x42]),
+            "01, 10-12, 42"
+        );
+    }
+
+    #[test]
+    fn test_inventory_fill() {
+        let mut inventory = Inventory::new(2, &[0x10, 0x42]);
+        inventory.seed(&["10ab".to_string(), "99cd".to_string()]);
+        assert_eq!(inventory.remaining(), 3);
+        assert!(inventory.needs(0x10) && !inventory.needs(0x99));
+
+        let open = inventory.open_set();
+        assert!(inventory.record(0x10));
+        assert!(!open.contains(0x10) && open.contains(0x42));
+        assert_eq!(inventory.full_count(), 1);
+
+        // One open ID needing two keys: 256 + 256 attempts on average
+        assert_eq!(inventory.expected_attempts(1.0), 512.0);
+        assert!(!inventory.record(0x42));
+        assert!(inventory.record(0x42));
+        assert_eq!(inventory.remaining(), 0);
+        assert_eq!(inventory.expected_attempts(1.0), 0.0);
+        assert!(inventory.coverage().ids_missing.is_empty());
+    }
+}
@@ -10,6 +10,7 @@
 use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
 use curve25519_dalek::scalar::Scalar;
 use rand::RngCore;
+use serde::Serialize;
 use sha2::{Digest, Sha512};
 
 /// Contains the generated key information
@@ -244,6 +245,258 @@ pub fn verify_key_hex(private_hex: &str, expected_public_hex: &str) -> bool {
     derived_public == expected_public_hex.to_lowercase()
 }
 
+/// Rebuild a `KeyInfo` from a 64-byte MeshCore private key
+///
+/// The public key is re-derived from the clamped scalar, so the result always
+/// passes `verify_key`; use it when only the private half is trusted.
+pub fn keypair_from_private(private_bytes: &[u8; 64]) -> KeyInfo {
+    let mut clamped = [0u8; 32];
+    clamped.copy_from_slice(&private_bytes[..32]);
+
+    let scalar = Scalar::from_bytes_mod_order(clamped);
+    let public_point = &scalar * ED25519_BASEPOINT_TABLE;
+    let public_bytes: [u8; 32] = public_point.compress().to_bytes();
+
+    KeyInfo {
+        public_hex: hex::encode(public_bytes),
+        private_hex: hex::encode(private_bytes),
+        public_bytes,
+        private_bytes: *private_bytes,
+    }
+}
+
+/// Sign a message with a MeshCore private key (RFC 8032 Ed25519 on the expanded key)
+///
+/// MeshCore stores the already-expanded key (`clamped_scalar || sha512_suffix`),
+/// so the nonce is derived from the stored suffix instead of re-hashing a seed.
+pub fn sign_message(private_key: &[u8; 64], public_key: &[u8; 32], message: &[u8]) -> [u8; 64] {
+    let mut scalar_bytes = [0u8; 32];
+    scalar_bytes.copy_from_slice(&private_key[..32]);
+    let a = Scalar::from_bytes_mod_order(scalar_bytes);
+
+    // r = H(suffix || M)
+    let mut hasher = Sha512::new();
+    hasher.update(&private_key[32..]);
+    hasher.update(message);
+    let r = Scalar::from_hash(hasher);
+    let big_r = (&r * ED25519_BASEPOINT_TABLE).compress();
+
+    // k = H(R || A || M)
+    let mut hasher = Sha512::new();
+    hasher.update(big_r.as_bytes());
+    hasher.update(public_key);
+    hasher.update(message);
+    let k = Scalar::from_hash(hasher);
+
+    let s = r + k * a;
+
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(big_r.as_bytes());
+    signature[32..].copy_from_slice(s.as_bytes());
+    signature
+}
+
+/// Verify an Ed25519 signature produced by `sign_message`
+pub fn verify_signature(public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+
+    let a = match CompressedEdwardsY(*public_key).decompress() {
+        Some(point) => point,
+        None => return false,
+    };
+
+    let mut s_bytes = [0u8; 32];
+    s_bytes.copy_from_slice(&signature[32..]);
+    let s = match Option::<Scalar>::from(Scalar::from_canonical_bytes(s_bytes)) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let mut hasher = Sha512::new();
+    hasher.update(&signature[..32]);
+    hasher.update(public_key);
+    hasher.update(message);
+    let k = Scalar::from_hash(hasher);
+
+    // R' = [s]B - [k]A must re-encode to the R half of the signature
+    let r_check = EdwardsPoint::vartime_double_scalar_mul_basepoint(&k, &(-a), &s);
+    r_check.compress().as_bytes() == &signature[..32]
+}
+
+/// Number of random peers used for the ECDH part of the health report
+const HEALTH_ECDH_PEERS: usize = 4;
+
+/// A single named pass/fail check in a key health report
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Full diagnostic report for a keypair
+///
+/// Unlike `validate_for_meshcore`, every check runs even after a failure so the
+/// report explains every reason a key is rejected.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyHealthReport {
+    pub healthy: bool,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl KeyHealthReport {
+    /// Checks that did not pass
+    pub fn failures(&self) -> impl Iterator<Item = &HealthCheck> {
+        self.checks.iter().filter(|c| !c.passed)
+    }
+}
+
+/// Run every key health check and collect the results
+pub fn check_key_health(key: &KeyInfo) -> KeyHealthReport {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    let mut checks = Vec::new();
+    let mut push = |name: &str, passed: bool, detail: String| {
+        checks.push(HealthCheck {
+            name: name.to_string(),
+            passed,
+            detail,
+        });
+    };
+
+    // Clamping bits of the stored scalar
+    let low = key.private_bytes[0];
+    let high = key.private_bytes[31];
+    let clamped = low & 7 == 0 && high & 192 == 64;
+    push(
+        "clamping",
+        clamped,
+        if clamped {
+            "Scalar is clamped (low 3 bits clear, bit 254 set, bit 255 clear)".to_string()
+        } else {
+            format!(
+                "Scalar is not clamped (byte[0]=0x{:02x}, byte[31]=0x{:02x})",
+                low, high
+            )
+        },
+    );
+
+    // Canonical point encoding, small-order and torsion checks need the decoded point
+    let point = CompressedEdwardsY(key.public_bytes).decompress();
+    match point {
+        Some(point) => {
+            let canonical = point.compress().to_bytes() == key.public_bytes;
+            push(
+                "canonical_encoding",
+                canonical,
+                if canonical {
+                    "Public key is a canonical point encoding".to_string()
+                } else {
+                    "Public key decodes but is not the canonical encoding".to_string()
+                },
+            );
+
+            let small = point.is_small_order();
+            push(
+                "small_order",
+                !small,
+                if small {
+                    "Public key is a small-order point".to_string()
+                } else {
+                    "Public key is not a small-order point".to_string()
+                },
+            );
+
+            let torsion_free = point.is_torsion_free();
+            push(
+                "torsion_free",
+                torsion_free,
+                if torsion_free {
+                    "Public key lies in the prime-order subgroup".to_string()
+                } else {
+                    "Public key has a torsion component".to_string()
+                },
+            );
+        }
+        None => {
+            let detail = "Public key is not a valid curve point".to_string();
+            push("canonical_encoding", false, detail.clone());
+            push("small_order", false, detail.clone());
+            push("torsion_free", false, detail);
+        }
+    }
+
+    // Reserved node ID
+    let node_ok = is_valid_meshcore_prefix(&key.public_bytes);
+    push(
+        "node_id",
+        node_ok,
+        if node_ok {
+            format!("Node ID 0x{:02x} is usable", key.public_bytes[0])
+        } else {
+            format!(
+                "Node ID 0x{:02x} is reserved in MeshCore",
+                key.public_bytes[0]
+            )
+        },
+    );
+
+    // ECDH against several random peers
+    let mut ecdh_failures = 0;
+    for _ in 0..HEALTH_ECDH_PEERS {
+        let peer = generate_meshcore_keypair();
+        let ss1 = ecdh_key_exchange(&key.private_bytes, &peer.public_bytes);
+        let ss2 = ecdh_key_exchange(&peer.private_bytes, &key.public_bytes);
+        if ss1 != ss2 || ss1.iter().all(|&b| b == 0) {
+            ecdh_failures += 1;
+        }
+    }
+    push(
+        "ecdh",
+        ecdh_failures == 0,
+        if ecdh_failures == 0 {
+            format!(
+                "Shared secrets agree with {} random peers",
+                HEALTH_ECDH_PEERS
+            )
+        } else {
+            format!(
+                "Shared secrets mismatched or zero with {}/{} random peers",
+                ecdh_failures, HEALTH_ECDH_PEERS
+            )
+        },
+    );
+
+    // Sign/verify round trip
+    let message = b"meshcore-keygen health check";
+    let signature = sign_message(&key.private_bytes, &key.public_bytes, message);
+    let signs = verify_signature(&key.public_bytes, message, &signature);
+    push(
+        "sign_verify",
+        signs,
+        if signs {
+            "Signature round trip verifies".to_string()
+        } else {
+            "Signature made with the private key does not verify".to_string()
+        },
+    );
+
+    // Public/private consistency
+    let consistent = verify_key(key);
+    push(
+        "key_consistency",
+        consistent,
+        if consistent {
+            "Private key derives the stored public key".to_string()
+        } else {
+            "Private key derives a different public key".to_string()
+        },
+    );
+
+    let healthy = checks.iter().all(|c| c.passed);
+    KeyHealthReport { healthy, checks }
+}
+
 /// Batch generate multiple keypairs for efficiency
 #[allow(dead_code)]
 #[inline]
@@ -346,4 +599,64 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_keypair_from_private() {
+        let key = generate_from_seed(&[7u8; 32]);
+        let rebuilt = keypair_from_private(&key.private_bytes);
+        assert_eq!(rebuilt.public_hex, key.public_hex);
+        assert_eq!(rebuilt.private_hex, key.private_hex);
+    }
+
+    #[test]
+    fn test_sign_verify_roundtrip() {
+        let key = generate_from_seed(&[9u8; 32]);
+        let sig = sign_message(&key.private_bytes, &key.public_bytes, b"hello");
+        assert!(verify_signature(&key.public_bytes, b"hello", &sig));
+        assert!(!verify_signature(&key.public_bytes, b"hellp", &sig));
+
+        let other = generate_from_seed(&[10u8; 32]);
+        assert!(!verify_signature(&other.public_bytes, b"hello", &sig));
+    }
+
+    #[test]
+    fn test_health_report_healthy_key() {
+        // Seed chosen arbitrarily; skip reserved node IDs so every check passes
+        let key = (0u8..=255)
+            .map(|i| generate_from_seed(&[i; 32]))
+            .find(|k| is_valid_meshcore_prefix(&k.public_bytes))
+            .unwrap();
+        let report = check_key_health(&key);
+        assert!(report.healthy, "{:?}", report);
+        assert_eq!(report.failures().count(), 0);
+        assert_eq!(report.checks.len(), 8);
+    }
+
+    #[test]
+    fn test_health_report_lists_every_failure() {
+        let mut key = generate_from_seed(&[3u8; 32]);
+        // Break clamping and swap in the identity point (small order, node ID 0x01)
+        key.private_bytes[0] |= 1;
+        key.public_bytes = [0u8; 32];
+        key.public_bytes[0] = 1;
+
+        let report = check_key_health(&key);
+        assert!(!report.healthy);
+        let failed: Vec<&str> = report.failures().map(|c| c.name.as_str()).collect();
+        assert!(failed.contains(&"clamping"));
+        assert!(failed.contains(&"small_order"));
+        assert!(failed.contains(&"key_consistency"));
+        assert!(failed.contains(&"sign_verify"));
+        assert!(!failed.contains(&"node_id"));
+    }
+
+    #[test]
+    fn test_health_report_reserved_node_id() {
+        let mut key = generate_from_seed(&[5u8; 32]);
+        key.public_bytes[0] = 0xFF;
+        let report = check_key_health(&key);
+        let node = report.checks.iter().find(|c| c.name == "node_id").unwrap();
+        assert!(!node.passed);
+        assert!(node.detail.contains("0xff"));
+    }
 }
@@ -0,0 +1,333 @@
+//! Curated Key Library
+//!
+//! Copies or moves keys out of timestamped run directories into a library laid
+//! out by node ID, pattern or date, de-duplicated by public key, with an
+//! `index.json` manifest that can always be rebuilt from the files on disk.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::archive::{self, KeyFilePair};
+use crate::audit::{parse_private_hex, parse_public_hex};
+use crate::keygen;
+
+/// Name of the manifest index written at the library root
+pub const INDEX_FILE: &str = "index.json";
+
+/// Directory layout of the library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Layout {
+    /// `<library>/<NODE_ID>/`
+    NodeId,
+    /// `<library>/<PATTERN>/`
+    Pattern,
+    /// `<library>/<YYYY-MM-DD>/`
+    Date,
+}
+
+/// One key in the library index
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexEntry {
+    pub public_key: String,
+    pub node_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    pub public_file: String,
+    pub private_file: String,
+}
+
+/// Manifest index of every key in the library
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryIndex {
+    pub generated_at: String,
+    pub total_keys: usize,
+    pub keys: Vec<IndexEntry>,
+}
+
+/// Outcome of an organize run
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct OrganizeSummary {
+    /// Pairs copied or moved into the library
+    pub imported: usize,
+    /// Pairs already in the library (same public key)
+    pub duplicates: usize,
+    /// Orphaned, malformed or mismatched pairs left where they are
+    pub skipped: usize,
+    /// Pairs whose destination file name is taken by a different key
+    pub conflicts: usize,
+    /// Keys listed in the rebuilt index
+    pub indexed: usize,
+}
+
+/// Load a pair only if both halves exist and the private key derives the public key
+fn load_consistent_pair(pair: &KeyFilePair) -> Option<String> {
+    let public_hex = archive::read_public_key(pair.public_path.as_deref()?)?;
+    let private_hex = archive::read_private_key(pair.private_path.as_deref()?)?;
+    let public_bytes = parse_public_hex(&public_hex).ok()?;
+    let private_bytes = parse_private_hex(&private_hex).ok()?;
+    if keygen::keypair_from_private(&private_bytes).public_bytes == public_bytes {
+        Some(public_hex)
+    } else {
+        None
+    }
+}
+
+/// Sub-directory for a key under the given layout
+fn layout_dir(layout: Layout, pair: &KeyFilePair, public_hex: &str) -> String {
+    match layout {
+        Layout::NodeId => public_hex[..2].to_uppercase(),
+        Layout::Pattern => pair
+            .name
+            .as_ref()
+            .map(|n| n.pattern_id.clone())
+            .unwrap_or_else(|| public_hex[..8].to_uppercase()),
+        Layout::Date => pair
+            .name
+            .as_ref()
+            .map(|n| {
+                let d = &n.timestamp[..8];
+                format!("{}-{}-{}", &d[..4], &d[4..6], &d[6..8])
+            })
+            .unwrap_or_else(|| "undated".to_string()),
+    }
+}
+
+/// Move a file, falling back to copy + remove across filesystems
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+/// Import keys from `sources` into `library`, then rebuild the index
+///
+/// Keys already present in the library (outside the sources) are never
+/// imported twice. With `move_files` the originals are removed once placed.
+pub fn organize(
+    library: &Path,
+    sources: &[PathBuf],
+    layout: Layout,
+    move_files: bool,
+) -> Result<OrganizeSummary, String> {
+    fs::create_dir_all(library)
+        .map_err(|e| format!("Failed to create {}: {}", library.display(), e))?;
+
+    let source_pairs: Vec<KeyFilePair> = sources
+        .iter()
+        .flat_map(|s| archive::scan_key_files(s))
+        .collect();
+    let source_stems: HashSet<&PathBuf> = source_pairs.iter().map(|p| &p.stem).collect();
+
+    // Keys already in the library that are not about to be re-imported
+    let mut known: HashSet<String> = archive::scan_key_files(library)
+        .iter()
+        .filter(|p| !source_stems.contains(&p.stem))
+        .filter_map(load_consistent_pair)
+        .collect();
+
+    let mut summary = OrganizeSummary::default();
+    for pair in &source_pairs {
+        let Some(public_hex) = load_consistent_pair(pair) else {
+            summary.skipped += 1;
+            continue;
+        };
+        if !known.insert(public_hex.clone()) {
+            summary.duplicates += 1;
+            continue;
+        }
+
+        let dest_dir = library.join(layout_dir(layout, pair, &public_hex));
+        fs::create_dir_all(&dest_dir)
+            .map_err(|e| format!("Failed to create {}: {}", dest_dir.display(), e))?;
+
+        // Both halves exist, checked by load_consistent_pair
+        let files = [
+            pair.public_path.as_deref().unwrap(),
+            pair.private_path.as_deref().unwrap(),
+        ];
+        let targets: Vec<PathBuf> = files
+            .iter()
+            .map(|f| dest_dir.join(f.file_name().unwrap()))
+            .collect();
+
+        if files.iter().zip(&targets).all(|(f, t)| *f == t.as_path()) {
+            // Already where the layout wants it
+            summary.imported += 1;
+            continue;
+        }
+        if targets.iter().any(|t| t.exists()) {
+            summary.conflicts += 1;
+            continue;
+        }
+
+        for (from, to) in files.iter().zip(&targets) {
+            let result = if move_files {
+                move_file(from, to)
+            } else {
+                fs::copy(from, to).map(|_| ())
+            };
+            result.map_err(|e| format!("Failed to place {}: {}", to.display(), e))?;
+        }
+        summary.imported += 1;
+    }
+
+    if move_files {
+        remove_empty_dirs(sources);
+    }
+
+    summary.indexed = write_index(library)?.total_keys;
+    Ok(summary)
+}
+
+/// Remove run directories left empty after a move (the source roots are kept)
+fn remove_empty_dirs(sources: &[PathBuf]) {
+    fn prune(dir: &Path, is_root: bool) {
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                if entry.path().is_dir() {
+                    prune(&entry.path(), false);
+                }
+            }
+        }
+        if !is_root {
+            // Fails harmlessly when the directory still has files
+            let _ = fs::remove_dir(dir);
+        }
+    }
+    for source in sources {
+        prune(source, true);
+    }
+}
+
+/// Build the index from the key files currently in the library
+pub fn build_index(library: &Path) -> LibraryIndex {
+    let mut seen = HashSet::new();
+    let mut keys: Vec<IndexEntry> = archive::scan_key_files(library)
+        .iter()
+        .filter_map(|pair| {
+            let public_hex = load_consistent_pair(pair)?;
+            if !seen.insert(public_hex.clone()) {
+                return None;
+            }
+            let relative = |p: &Path| p.strip_prefix(library).unwrap_or(p).display().to_string();
+            Some(IndexEntry {
+                node_id: public_hex[..2].to_string(),
+                pattern_id: pair.name.as_ref().map(|n| n.pattern_id.clone()),
+                timestamp: pair.name.as_ref().map(|n| n.timestamp.clone()),
+                public_file: relative(pair.public_path.as_deref()?),
+                private_file: relative(pair.private_path.as_deref()?),
+                public_key: public_hex,
+            })
+        })
+        .collect();
+    keys.sort_by(|a, b| (&a.node_id, &a.public_key).cmp(&(&b.node_id, &b.public_key)));
+
+    LibraryIndex {
+        generated_at: chrono::Local::now().to_rfc3339(),
+        total_keys: keys.len(),
+        keys,
+    }
+}
+
+/// Rebuild `index.json` from scratch
+pub fn write_index(library: &Path) -> Result<LibraryIndex, String> {
+    let index = build_index(library);
+    let path = library.join(INDEX_FILE);
+    fs::write(&path, serde_json::to_string_pretty(&index).unwrap())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keygen::KeyInfo;
+    use tempfile::tempdir;
+
+    fn write_pair(dir: &Path, stem: &str, key: &KeyInfo) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join(format!("{}_public.txt", stem)), &key.public_hex).unwrap();
+        fs::write(dir.join(format!("{}_private.txt", stem)), &key.private_hex).unwrap();
+    }
+
+    #[test]
+    fn test_organize_by_node_id_dedupes() {
+        let dir = tempdir().unwrap();
+        let runs = dir.path().join("runs");
+        let lib = dir.path().join("lib");
+        let a = keygen::generate_from_seed(&[1u8; 32]);
+        let b = keygen::generate_from_seed(&[2u8; 32]);
+
+        write_pair(&runs.join("20260101_000000"), "AA_1_20260101_000000", &a);
+        write_pair(&runs.join("20260102_000000"), "AA_1_20260102_000000", &a);
+        write_pair(&runs.join("20260102_000000"), "BB_2_20260102_000000", &b);
+
+        let summary = organize(&lib, std::slice::from_ref(&runs), Layout::NodeId, false).unwrap();
+        assert_eq!(summary.imported, 2);
+        assert_eq!(summary.duplicates, 1);
+        assert_eq!(summary.indexed, 2);
+
+        let node_dir = lib.join(a.public_hex[..2].to_uppercase());
+        assert!(node_dir.join("AA_1_20260101_000000_public.txt").exists());
+        // Copy mode leaves the originals alone
+        assert!(runs
+            .join("20260101_000000/AA_1_20260101_000000_public.txt")
+            .exists());
+
+        // Running again imports nothing new
+        let again = organize(&lib, &[runs], Layout::NodeId, false).unwrap();
+        assert_eq!(again.imported, 0);
+        assert_eq!(again.duplicates, 3);
+    }
+
+    #[test]
+    fn test_organize_move_by_date_skips_broken_pairs() {
+        let dir = tempdir().unwrap();
+        let runs = dir.path().join("runs");
+        let lib = dir.path().join("lib");
+        let a = keygen::generate_from_seed(&[3u8; 32]);
+        let b = keygen::generate_from_seed(&[4u8; 32]);
+        let run = runs.join("20260305_101500");
+
+        write_pair(&run, "AA_1_20260305_101500", &a);
+        fs::write(run.join("BB_2_20260305_101500_public.txt"), &b.public_hex).unwrap();
+
+        let summary = organize(&lib, std::slice::from_ref(&runs), Layout::Date, true).unwrap();
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.skipped, 1);
+        assert!(lib
+            .join("2026-03-05/AA_1_20260305_101500_private.txt")
+            .exists());
+        assert!(!run.join("AA_1_20260305_101500_private.txt").exists());
+        // Orphan stays behind, so the run directory is kept
+        assert!(run.join("BB_2_20260305_101500_public.txt").exists());
+    }
+
+    #[test]
+    fn test_reorganize_in_place_and_rebuild_index() {
+        let dir = tempdir().unwrap();
+        let lib = dir.path().join("lib");
+        let a = keygen::generate_from_seed(&[5u8; 32]);
+        write_pair(&lib.join("20260101_000000"), "CAFE_1_20260101_000000", &a);
+
+        let summary = organize(&lib, std::slice::from_ref(&lib), Layout::Pattern, true).unwrap();
+        assert_eq!(summary.imported, 1);
+        assert!(lib.join("CAFE/CAFE_1_20260101_000000_public.txt").exists());
+        assert!(!lib.join("20260101_000000").exists());
+
+        let index = build_index(&lib);
+        assert_eq!(index.total_keys, 1);
+        assert_eq!(index.keys[0].public_key, a.public_hex);
+        assert_eq!(index.keys[0].pattern_id.as_deref(), Some("CAFE"));
+        assert_eq!(
+            Path::new(&index.keys[0].public_file),
+            Path::new("CAFE/CAFE_1_20260101_000000_public.txt")
+        );
+    }
+}
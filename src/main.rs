@@ -3,27 +3,56 @@
 //! High-performance key generator with CPU multi-threading and GPU support.
 //! Generates Ed25519 keys compatible with MeshCore's specific format.
 
+mod affinity;
+mod archive;
+mod audit;
+mod audit_log;
+mod blocklist;
+mod checkpoint;
+mod dictionary;
+mod distributed;
+mod explain;
+mod expr;
+mod governor;
 mod gpu_detect;
+mod hex_regex;
+mod inventory;
 mod keygen;
+mod library;
+mod manifest;
 #[cfg(target_os = "macos")]
 mod metal_gpu;
+mod metrics;
 mod pattern;
+mod plan;
+mod plugin;
+mod schedule;
+mod score;
+mod split_key;
+mod symmetry;
+#[cfg(not(target_os = "macos"))]
+mod topology;
+mod tui;
 mod worker;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
 use serde::Serialize;
 use std::collections::HashSet;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crate::keygen::KeyInfo;
+use crate::keygen::{HealthCheck, KeyInfo};
 use crate::pattern::{PatternConfig, PatternMode};
-use crate::worker::WorkerPool;
+use crate::worker::{PoolCommand, WorkerPool};
+
+/// How often `--checkpoint` is saved while searching (and after every key)
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
 
 /// JSON output structure for a found key
 #[derive(Serialize)]
@@ -38,11 +67,46 @@ struct KeyOutput {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub validation_error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub health_checks: Option<Vec<HealthCheck>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched_word: Option<dictionary::WordMatch>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<score::Score>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub public_file: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub private_file: Option<String>,
 }
 
+impl KeyOutput {
+    /// Record for a kept key, before diagnostics and file names are attached
+    fn new(
+        index: usize,
+        key: &KeyInfo,
+        validation: &keygen::ValidationResult,
+        pattern: &PatternConfig,
+    ) -> Self {
+        Self {
+            index,
+            public_key: key.public_hex.clone(),
+            private_key: key.private_hex.clone(),
+            node_id: key.public_hex[..2].to_string(),
+            first_8: key.public_hex[..8].to_string(),
+            last_8: key.public_hex[56..].to_string(),
+            meshcore_valid: validation.valid,
+            validation_error: validation.reason.clone(),
+            health_checks: None,
+            matched_word: pattern
+                .dictionary
+                .as_ref()
+                .and_then(|d| d.word_match(&key.public_hex)),
+            score: None,
+            public_file: None,
+            private_file: None,
+        }
+    }
+}
+
 /// JSON output structure for the summary
 #[derive(Serialize)]
 struct SummaryOutput {
@@ -51,7 +115,21 @@ struct SummaryOutput {
     pub average_rate: f64,
     pub keys_found: usize,
     pub keys_valid: usize,
+    /// Matches rejected by MeshCore validation
+    pub keys_rejected: u64,
+    /// Matches rejected by `--blocklist`
+    pub keys_blocked: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nodes: Option<Vec<distributed::NodeStats>>,
     pub keys: Vec<KeyOutput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub best_keys: Vec<KeyOutput>,
+    /// `--plugin` calls that trapped or ran out of fuel
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_failed_calls: Option<u64>,
+    /// `--fill-node-ids` coverage, including keys from earlier runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_ids: Option<inventory::Coverage>,
 }
 
 /// MeshCore Ed25519 Vanity Key Generator
@@ -73,17 +151,69 @@ struct Args {
     gpu: bool,
 
     /// Pattern mode: 2, 4, 6, or 8 character matching
-    #[arg(long, value_parser = clap::value_parser!(u8).range(2..=8))]
+    #[arg(long, global = true, value_parser = clap::value_parser!(u8).range(2..=8))]
     pattern: Option<u8>,
 
     /// Search for keys starting with this hex prefix
-    #[arg(long)]
+    #[arg(long, global = true)]
     prefix: Option<String>,
 
     /// Search for keys where first N chars match last N chars
-    #[arg(long, value_parser = clap::value_parser!(u8).range(2..=8))]
+    #[arg(long, global = true, value_parser = clap::value_parser!(u8).range(2..=8))]
     vanity: Option<u8>,
 
+    /// How --pattern/--vanity compare the first N chars; several modes match if any holds
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "either"
+    )]
+    symmetry: Vec<symmetry::Symmetry>,
+
+    /// Search for keys starting or ending with any word from this wordlist
+    #[arg(long, global = true, value_name = "FILE", conflicts_with_all = ["prefix", "vanity", "pattern", "suffix", "infix", "node_range", "match_expr", "regex"])]
+    dictionary: Option<PathBuf>,
+
+    /// Search for keys ending with this hex suffix
+    #[arg(long, global = true, value_name = "HEX")]
+    suffix: Option<String>,
+
+    /// Require hex at a fixed character offset, e.g. 8:CAFE
+    #[arg(long, global = true, value_name = "OFFSET:HEX")]
+    infix: Option<String>,
+
+    /// Node ID (first byte, or first two bytes with 4 digits) in a hex range, e.g. 10-3F
+    #[arg(long, global = true, value_name = "LO-HI")]
+    node_range: Option<String>,
+
+    /// Match an expression, e.g. "(node:10-3F | suffix:BEEF) & !prefix:00"
+    #[arg(long = "match", global = true, value_name = "EXPR", value_parser = expr::Expr::parse)]
+    match_expr: Option<expr::Expr>,
+
+    /// Match a regular expression over the key's 64 hex characters, e.g. "^(AB|CD)[0-9]{2}.*F{4}$"
+    #[arg(long, global = true, value_name = "REGEX", value_parser = hex_regex::HexRegex::new, conflicts_with_all = ["prefix", "vanity", "pattern", "suffix", "infix", "node_range", "match_expr"])]
+    regex: Option<hex_regex::HexRegex>,
+
+    /// Transliterate wordlist letters into hex digits (O→0, I/L→1, Z→2, S→5, G→6, T→7)
+    #[arg(long, global = true, default_value_t = false, requires = "dictionary")]
+    leet: bool,
+
+    /// Ignore dictionary words shorter than this many hex characters
+    #[arg(long, global = true, default_value_t = 4, requires = "dictionary")]
+    min_word_len: usize,
+
+    /// Where dictionary words may appear in the key
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value = "both",
+        requires = "dictionary"
+    )]
+    dict_anchor: dictionary::Anchor,
+
     /// Output directory for key files (default: current directory)
     #[arg(short, long, default_value = ".")]
     output: PathBuf,
@@ -92,8 +222,16 @@ struct Args {
     #[arg(long, default_value = "0")]
     max_time: u64,
 
+    /// Also keep the N highest-scoring keys and save them if the run ends short of its target
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..=1000))]
+    best: Option<u16>,
+
+    /// Score that ranks --best keys (default: the one that fits the pattern)
+    #[arg(long, value_enum, requires = "best")]
+    score: Option<score::ScoreKind>,
+
     /// Disable MeshCore verification (checks prefix and ECDH). Verification is enabled by default; pass `--no-verify` to disable.
-    #[arg(long = "no-verify", action = clap::ArgAction::SetTrue, default_value_t = false)]
+    #[arg(long = "no-verify", global = true, action = clap::ArgAction::SetTrue, default_value_t = false)]
     no_verify: bool,
 
     /// Skip keys that already exist in the output directory
@@ -101,7 +239,7 @@ struct Args {
     skip_existing: bool,
 
     /// Output results as JSON instead of human-readable format
-    #[arg(long)]
+    #[arg(long, global = true)]
     json: bool,
 
     /// Verbose output
@@ -116,6 +254,54 @@ struct Args {
     #[arg(long, default_value_t = false)]
     powersave: bool,
 
+    /// Pin CPU workers to these CPUs, one each in turn, e.g. "0-7" or "0,2,4-6" (Linux)
+    #[arg(long, value_name = "LIST")]
+    cpus: Option<String>,
+
+    /// Run CPU workers only on this core type of a hybrid CPU (Linux)
+    #[arg(long, value_enum, conflicts_with = "cpus")]
+    core_type: Option<affinity::CoreType>,
+
+    /// Run CPU workers in the SCHED_IDLE class so any other work preempts them (Linux)
+    #[arg(long, default_value_t = false)]
+    sched_idle: bool,
+
+    /// Nice level for CPU workers (Linux)
+    #[arg(long, value_name = "LEVEL", value_parser = clap::value_parser!(i32).range(0..=19))]
+    nice: Option<i32>,
+
+    /// Cap total CPU use at this percentage of the machine by duty-cycling the workers
+    #[arg(long, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(1..=100))]
+    cpu_percent: Option<u8>,
+
+    /// Only search inside these windows, e.g. "mon-fri 22:00-07:00; sat,sun"
+    #[arg(long, value_name = "SPEC", value_parser = schedule::Schedule::parse)]
+    schedule: Option<schedule::Schedule>,
+
+    /// Save progress to this file and resume from it when it exists
+    #[arg(long, value_name = "FILE")]
+    checkpoint: Option<PathBuf>,
+
+    /// Throttle workers to keep the CPU below this temperature in °C
+    #[arg(long, value_name = "CELSIUS")]
+    max_temp: Option<f32>,
+
+    /// Throttle workers while other processes use more than this share of all cores
+    #[arg(long, value_name = "PERCENT")]
+    max_load: Option<f32>,
+
+    /// Pause workers while the machine runs on battery
+    #[arg(long, default_value_t = false)]
+    pause_on_battery: bool,
+
+    /// Pause workers while on battery below this charge
+    #[arg(long, value_name = "PERCENT")]
+    min_battery: Option<u8>,
+
+    /// Root of the sysfs tree read for temperatures and power state
+    #[arg(long, default_value = governor::DEFAULT_SYSFS_ROOT)]
+    sysfs_root: PathBuf,
+
     /// Benchmark mode: measure speed without saving keys to disk
     #[arg(long, default_value_t = false)]
     benchmark: bool,
@@ -128,9 +314,203 @@ struct Args {
     #[arg(long, default_value = "500")]
     refresh_ms: u64,
 
+    /// WebAssembly matcher (.wasm or .wat) every match must also pass
+    #[arg(long, value_name = "FILE")]
+    plugin: Option<PathBuf>,
+
+    /// Fuel (about one per instruction) for each plugin call
+    #[arg(long, value_name = "N", default_value_t = plugin::DEFAULT_FUEL, requires = "plugin")]
+    plugin_fuel: u64,
+
+    /// Reject keys containing any substring, prefix or node ID listed in this file
+    #[arg(long, value_name = "FILE")]
+    blocklist: Option<PathBuf>,
+
+    /// Keep searching until every usable node ID has N keys, saved in one folder per ID
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..), conflicts_with_all = ["target_keys", "checkpoint", "coordinator", "gpu"])]
+    fill_node_ids: Option<u16>,
+
+    /// Node IDs to fill instead of all 254 usable ones, e.g. "10-3F,42"
+    #[arg(long, value_name = "LIST", requires = "fill_node_ids")]
+    node_ids: Option<String>,
+
+    /// Append every saved key to this hash-chained audit log (JSON lines)
+    #[arg(long)]
+    audit_log: Option<PathBuf>,
+
+    /// Operator key that signs audit log entries: 128 hex chars or a `_private.txt` path
+    #[arg(long, requires = "audit_log")]
+    audit_signing_key: Option<String>,
+
+    /// Serve Prometheus metrics at http://<ADDR>/metrics (e.g. 127.0.0.1:9090)
+    #[arg(long)]
+    metrics_addr: Option<String>,
+
+    /// Also accept POST requests on the metrics server that pause, resume and resize the worker pool
+    #[arg(long, requires = "metrics_addr")]
+    metrics_control: bool,
+
+    /// Accept remote agents on this address (e.g. 0.0.0.0:7700) and farm out work to them
+    #[arg(long, requires = "cluster_secret")]
+    coordinator: Option<String>,
+
+    /// File holding the shared secret that authenticates coordinator and agents (see `cluster-secret`)
+    #[arg(long, global = true)]
+    cluster_secret: Option<String>,
+
     /// Run tests
     #[arg(long)]
     test: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Maintenance commands that run instead of a key search
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the key-health diagnostics on a stored key or a whole key archive
+    Audit {
+        /// Private key as 128 hex chars, a path to a `_private.txt` file, or a directory to scan
+        target: String,
+
+        /// Public key as 64 hex chars or a file path (defaults to the sibling `_public.txt`)
+        #[arg(long)]
+        public: Option<String>,
+
+        /// Write the archive report as JSON to this file
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+
+    /// Copy or move keys into a curated library and rebuild its index
+    Organize {
+        /// Library root (its `index.json` is always rebuilt from the files on disk)
+        library: PathBuf,
+
+        /// Output roots or run directories to import from (omit to only rebuild the index)
+        #[arg(long = "from")]
+        sources: Vec<PathBuf>,
+
+        /// Directory layout inside the library
+        #[arg(long, value_enum, default_value = "node-id")]
+        layout: library::Layout,
+
+        /// Move files instead of copying them
+        #[arg(long = "move", default_value_t = false)]
+        move_files: bool,
+    },
+
+    /// Check the files of a run against its manifest
+    VerifyManifest {
+        /// A manifest file, or a run directory containing one or more manifests
+        path: PathBuf,
+    },
+
+    /// Create a split-key request: keeps the secret scalar, publishes only `A = a·G` and the pattern
+    SplitRequest {
+        /// Request file to hand to helpers
+        request: PathBuf,
+
+        /// Where to keep the secret (never share this file)
+        #[arg(long, default_value = "split_secret.json")]
+        secret: PathBuf,
+
+        /// Overwrite an existing secret file (its key can then never be combined)
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Search a split-key request for a matching offset (no private key is involved)
+    SplitSearch {
+        /// Request file created by `split-request`
+        request: PathBuf,
+
+        /// Number of worker threads (defaults to detected CPU cores)
+        #[arg(short, long)]
+        workers: Option<usize>,
+
+        /// Maximum time to search in seconds (0 = unlimited)
+        #[arg(long, default_value = "0")]
+        max_time: u64,
+    },
+
+    /// Combine the split-key secret with a helper's offset into the final key
+    Combine {
+        /// Secret file written by `split-request`
+        secret: PathBuf,
+
+        /// Offset returned by `split-search` (hex)
+        offset: String,
+
+        /// Output directory for the key files
+        #[arg(short, long, default_value = ".")]
+        output: PathBuf,
+    },
+
+    /// Write a new random secret file for --cluster-secret
+    ClusterSecret {
+        /// Secret file to create (copy it to every agent over a trusted channel)
+        path: PathBuf,
+
+        /// Overwrite the file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Join a coordinator and search the ranges it assigns (requires --cluster-secret)
+    Agent {
+        /// Coordinator address, e.g. 192.168.1.10:7700
+        coordinator: String,
+
+        /// Number of worker threads (defaults to detected CPU cores)
+        #[arg(short, long)]
+        workers: Option<usize>,
+
+        /// Name shown in the coordinator's per-node stats (defaults to the hostname)
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Check the hash chain and signatures of an audit log
+    VerifyLog {
+        /// Audit log written with `--audit-log`
+        path: PathBuf,
+
+        /// Require every entry to be signed by this operator public key (64 hex chars)
+        #[arg(long)]
+        signer: Option<String>,
+    },
+
+    /// Dry run: show how the pattern is parsed, its odds, time estimates and example matches
+    Explain {
+        /// Seconds of key generation used to measure this machine's rate (0 = skip)
+        #[arg(long, default_value_t = 2.0)]
+        calibrate: f64,
+
+        /// Number of synthetic example keys to show
+        #[arg(long, default_value_t = 3)]
+        examples: usize,
+
+        /// Number of worker threads to calibrate with (defaults to detected CPU cores)
+        #[arg(short, long)]
+        workers: Option<usize>,
+    },
+
+    /// Benchmark this machine and list the patterns likely to finish within a time budget
+    Plan {
+        /// Time budget, e.g. 4h, 90m, 1h30m, 2d or seconds
+        #[arg(value_parser = plan::parse_budget)]
+        budget: Duration,
+
+        /// Seconds to benchmark the worker pool for
+        #[arg(long, default_value_t = 5)]
+        calibrate: u64,
+
+        /// Number of worker threads (defaults to detected CPU cores)
+        #[arg(short, long)]
+        workers: Option<usize>,
+    },
 }
 
 fn main() {
@@ -141,13 +521,17 @@ fn main() {
         return;
     }
 
+    if let Some(command) = &args.command {
+        std::process::exit(run_command(command, &args));
+    }
+
     // Prepare output directories
     let base_output = args.output.clone(); // root where timestamped runs will live
 
     // If user did not provide an explicit output (default '.'), create a timestamped subdirectory
+    let run_ts = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
     let output_dir: PathBuf = if base_output == Path::new(".") {
-        let ts = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
-        let newdir = base_output.join(ts);
+        let newdir = base_output.join(&run_ts);
         fs::create_dir_all(&newdir).expect("Failed to create timestamped output directory");
         newdir
     } else {
@@ -168,7 +552,147 @@ fn main() {
     let verify = !args.no_verify;
 
     // Configure pattern matching
-    let pattern_config = build_pattern_config(&args);
+    let pattern_config = match build_pattern_config(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{} {}", style("✗").red(), e);
+            std::process::exit(2);
+        }
+    };
+
+    // CPU placement for workers; an unusable CPU list fails before anything starts
+    let thread_policy = match thread_policy(&args) {
+        Ok(policy) => policy,
+        Err(e) => {
+            eprintln!("{} {}", style("✗").red(), e);
+            std::process::exit(2);
+        }
+    };
+
+    // Open the audit log up front so a broken chain or bad signing key fails fast
+    let mut audit_log = match open_audit_log(&args) {
+        Ok(log) => log,
+        Err(e) => {
+            eprintln!("{} {}", style("✗").red(), e);
+            std::process::exit(2);
+        }
+    };
+
+    let plugin = match args
+        .plugin
+        .as_deref()
+        .map(|p| plugin::Plugin::load(p, args.plugin_fuel))
+    {
+        Some(Ok(plugin)) => Some(plugin),
+        Some(Err(e)) => {
+            eprintln!("{} {}", style("✗").red(), e);
+            std::process::exit(2);
+        }
+        None => None,
+    };
+    if args.score == Some(score::ScoreKind::Plugin)
+        && !plugin.as_ref().is_some_and(|p| p.has_score())
+    {
+        eprintln!(
+            "{} --score plugin needs a --plugin that exports score",
+            style("✗").red()
+        );
+        std::process::exit(2);
+    }
+    // Main's own instance re-checks keys from the GPU and remote agents
+    let mut plugin_check = plugin
+        .as_ref()
+        .map(|p| p.instantiate().expect("checked on load"));
+
+    let blocklist = match args.blocklist.as_deref().map(blocklist::Blocklist::load) {
+        Some(Ok(blocklist)) => Some(blocklist),
+        Some(Err(e)) => {
+            eprintln!("{} {}", style("✗").red(), e);
+            std::process::exit(2);
+        }
+        None => None,
+    };
+
+    // Progress carried over from an earlier run of the same search
+    let mut checkpoint = match &args.checkpoint {
+        Some(path) => match checkpoint::Checkpoint::load_or_new(
+            path,
+            &pattern_config.description(),
+            &checkpoint::inputs_digest(&pattern_config, blocklist.as_ref(), plugin.as_deref()),
+            args.target_keys,
+        ) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                eprintln!("{} {}", style("✗").red(), e);
+                std::process::exit(2);
+            }
+        },
+        None => None,
+    };
+    if checkpoint.as_ref().is_some_and(|c| c.remaining() == 0) {
+        if !args.json {
+            println!(
+                "{} Checkpoint already complete: {} key(s) found",
+                style("✓").green(),
+                args.target_keys
+            );
+        }
+        return;
+    }
+    let prior_found = checkpoint.as_ref().map_or(0, |c| c.keys.len());
+
+    // Keys per node ID; keys already under the output root count as stock
+    let mut inventory = match args.fill_node_ids {
+        Some(per_id) => {
+            let ids = match args.node_ids.as_deref().map(inventory::parse_node_ids) {
+                Some(Ok(ids)) => ids,
+                Some(Err(e)) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    std::process::exit(2);
+                }
+                None => inventory::usable_node_ids(),
+            };
+            // Leave out IDs no key could fill: the pattern fixes the first
+            // byte, or the blocklist forbids it
+            let (ids, dropped): (Vec<u8>, Vec<u8>) = ids.into_iter().partition(|&id| {
+                explain::can_start_with(&pattern_config, id)
+                    && !blocklist.as_ref().is_some_and(|b| b.blocks_node_id(id))
+            });
+            if ids.is_empty() {
+                eprintln!(
+                    "{} No wanted node ID can be filled: the pattern or blocklist rules them all out",
+                    style("✗").red()
+                );
+                std::process::exit(2);
+            }
+            if !dropped.is_empty() {
+                eprintln!(
+                    "{} Skipping {} node ID(s) the pattern or blocklist rules out: {}",
+                    style("⚠").yellow(),
+                    dropped.len(),
+                    inventory::format_node_ids(&dropped)
+                );
+            }
+            let mut inventory = inventory::Inventory::new(per_id as usize, &ids);
+            inventory.seed(&existing_keys);
+            Some(inventory)
+        }
+        None => None,
+    };
+    if let Some(inv) = inventory.as_ref().filter(|i| i.remaining() == 0) {
+        if !args.json {
+            println!(
+                "{} Inventory already complete: {} key(s) for each of {} node IDs",
+                style("✓").green(),
+                inv.per_id(),
+                inv.wanted_count()
+            );
+        }
+        return;
+    }
+    let target_keys = inventory
+        .as_ref()
+        .map_or(args.target_keys, |i| i.remaining());
 
     if !args.json {
         println!(
@@ -205,7 +729,9 @@ fn main() {
                 style("⚡").yellow()
             );
         }
-        let worker_count = args.workers.unwrap_or(cpu_cores);
+        let worker_count = args
+            .workers
+            .unwrap_or_else(|| default_worker_count(cpu_cores, &thread_policy));
 
         println!(
             "{} Detected {} CPU cores, using {} workers",
@@ -213,12 +739,57 @@ fn main() {
             cpu_cores,
             worker_count
         );
+        #[cfg(not(target_os = "macos"))]
+        if let Some(summary) = topology::CpuTopology::detect().describe() {
+            println!("{} CPU topology: {}", style("ℹ").blue(), summary);
+        }
+        if !thread_policy.is_default() {
+            println!("{} Worker placement: {}", style("ℹ").blue(), thread_policy);
+        }
         println!(
             "{} Pattern: {}",
             style("ℹ").blue(),
             pattern_config.description()
         );
-        println!("{} Target: {} key(s)", style("ℹ").blue(), args.target_keys);
+        if let Some(regex) = &pattern_config.regex {
+            println!(
+                "{} Regex compiled to {} DFA states, 1 in {:.0} keys match",
+                style("ℹ").blue(),
+                regex.state_count(),
+                1.0 / regex.probability()
+            );
+        }
+        if let Some(p) = &plugin {
+            println!(
+                "{} Plugin: {} ({} fuel per call{})",
+                style("ℹ").blue(),
+                p.path().display(),
+                format_number(p.fuel()),
+                if p.has_score() { ", scores keys" } else { "" }
+            );
+        }
+        if let Some(b) = &blocklist {
+            println!(
+                "{} Blocklist: {} entr{}",
+                style("ℹ").blue(),
+                b.entry_count(),
+                if b.entry_count() == 1 { "y" } else { "ies" }
+            );
+        }
+        match &inventory {
+            Some(inv) => {
+                println!(
+                    "{} Target: {} key(s) for each of {} node IDs, {} full, {} key(s) to go",
+                    style("ℹ").blue(),
+                    inv.per_id(),
+                    inv.wanted_count(),
+                    inv.full_count(),
+                    target_keys
+                );
+                inv.print_grid();
+            }
+            None => println!("{} Target: {} key(s)", style("ℹ").blue(), target_keys),
+        }
 
         if verify {
             println!(
@@ -228,6 +799,29 @@ fn main() {
             );
         }
 
+        if let Some(c) = checkpoint.as_ref().filter(|c| c.total_attempts > 0) {
+            println!(
+                "{} Resuming from checkpoint: {}/{} key(s), {} attempts, {} searched",
+                style("ℹ").blue(),
+                c.keys.len(),
+                c.target_keys,
+                format_number(c.total_attempts),
+                format_hms(c.active_seconds as u64)
+            );
+        }
+        if let Some(s) = &args.schedule {
+            println!("{} Schedule: {}", style("ℹ").blue(), s);
+        }
+        if let Some(n) = args.best {
+            let kind = best_score_kind(&args, &pattern_config, plugin.as_deref());
+            println!(
+                "{} Keeping the best {} key(s) by {} score",
+                style("ℹ").blue(),
+                n,
+                kind.name()
+            );
+        }
+
         if !existing_keys.is_empty() {
             println!(
                 "{} Loaded {} existing keys (will skip duplicates)",
@@ -249,29 +843,31 @@ fn main() {
     }
 
     let cpu_cores = detect_cpu_cores(args.brutal, args.powersave);
-    let worker_count = args.workers.unwrap_or(cpu_cores);
+    let worker_count = args
+        .workers
+        .unwrap_or_else(|| default_worker_count(cpu_cores, &thread_policy));
 
     // Shared state
-    let found_count = Arc::new(AtomicU64::new(0));
-    let total_attempts = Arc::new(AtomicU64::new(0));
+    let found_count = Arc::new(AtomicU64::new(prior_found as u64));
+    let total_attempts = Arc::new(AtomicU64::new(
+        checkpoint.as_ref().map_or(0, |c| c.total_attempts),
+    ));
     let should_stop = Arc::new(AtomicBool::new(false));
 
-    // Progress display (only if not JSON mode)
-    let progress_bar = if !args.json {
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.green} [{elapsed_precise}] {msg}")
-                .unwrap(),
-        );
-        pb.enable_steady_tick(Duration::from_millis(100));
-        Some(pb)
-    } else {
+    // `--beautiful` on a real terminal gets the full-screen dashboard instead of the spinner
+    let use_dashboard = args.beautiful && !args.json && console::Term::stdout().is_term();
+
+    // Elapsed time excludes pauses and continues from the checkpoint
+    let mut clock = schedule::ActiveClock::start(Duration::from_secs_f64(
+        checkpoint.as_ref().map_or(0.0, |c| c.active_seconds),
+    ));
+    let start_wall = chrono::Local::now();
+    let host_info = if args.benchmark {
         None
+    } else {
+        Some(manifest::HostInfo::collect())
     };
 
-    let start_time = Instant::now();
-
     // Channel for found keys
     let (tx, rx) = crossbeam_channel::unbounded::<KeyInfo>();
 
@@ -283,6 +879,26 @@ fn main() {
         total_attempts.clone(),
         should_stop.clone(),
     );
+    if let Some(percent) = args.cpu_percent {
+        worker_pool.set_cpu_percent(percent);
+    }
+    worker_pool.set_thread_policy(thread_policy);
+    let best_keys = args.best.map(|n| {
+        let kind = best_score_kind(&args, &pattern_config, plugin.as_deref());
+        Arc::new(score::BestKeys::new(
+            n as usize,
+            score::Scorer::new(kind, &pattern_config),
+        ))
+    });
+    if let Some(best) = &best_keys {
+        worker_pool.set_best_keys(best.clone());
+    }
+    if let Some(p) = &plugin {
+        worker_pool.set_plugin(p.clone());
+    }
+    if let Some(inv) = &inventory {
+        worker_pool.set_node_ids(inv.open_set());
+    }
 
     #[cfg(target_os = "macos")]
     let gpu_counter = {
@@ -297,10 +913,68 @@ fn main() {
         counter
     };
 
-    worker_pool.start();
+    if let Err(e) = worker_pool.start() {
+        eprintln!("{} {}", style("✗").red(), e);
+        std::process::exit(2);
+    }
+
+    // Runtime pool changes from the dashboard and the HTTP API, applied by the main loop
+    let (control_tx, control_rx) = crossbeam_channel::unbounded::<PoolCommand>();
+
+    // Optional Prometheus endpoint, fed from the same samples as the progress display
+    let metrics = Arc::new(metrics::Metrics::new(target_keys));
+    if let Some(addr) = &args.metrics_addr {
+        let control = args.metrics_control.then(|| control_tx.clone());
+        match metrics::serve(addr, metrics.clone(), control, should_stop.clone()) {
+            Ok(bound) => {
+                if !args.json {
+                    println!("{} Metrics at http://{}/metrics", style("ℹ").blue(), bound);
+                }
+            }
+            Err(e) => {
+                eprintln!("{} {}", style("✗").red(), e);
+                worker_pool.stop();
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // Remote agents feed the same channel and attempt counter as local workers
+    let coordinator = match &args.coordinator {
+        Some(addr) => {
+            let started = cluster_secret(&args).and_then(|secret| {
+                distributed::Coordinator::start(
+                    addr,
+                    &secret,
+                    pattern_config.clone(),
+                    tx.clone(),
+                    total_attempts.clone(),
+                    should_stop.clone(),
+                )
+            });
+            match started {
+                Ok(coordinator) => {
+                    if !args.json {
+                        println!(
+                            "{} Coordinator listening on {}",
+                            style("ℹ").blue(),
+                            coordinator.local_addr()
+                        );
+                    }
+                    Some(coordinator)
+                }
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    worker_pool.stop();
+                    std::process::exit(2);
+                }
+            }
+        }
+        None => None,
+    };
 
     // Snapshot per-worker counters for live stats
-    let worker_counters = worker_pool.attempts_per_worker_snapshot();
+    let mut worker_counters = worker_pool.attempts_per_worker_snapshot();
     let mut prev_worker_totals: Vec<u64> = worker_counters
         .iter()
         .map(|c| c.load(Ordering::Relaxed))
@@ -313,31 +987,175 @@ fn main() {
         vec![vec![0.0f64; window_size]; worker_counters.len()];
     let mut window_idx: usize = 0;
 
+    let mut dashboard = if use_dashboard {
+        match tui::Dashboard::start() {
+            Ok(dashboard) => Some(dashboard),
+            Err(e) => {
+                eprintln!("{} Dashboard unavailable: {}", style("⚠").yellow(), e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Progress display (only if not JSON mode), also when the dashboard failed to start
+    let progress_bar = if !args.json && dashboard.is_none() {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} [{prefix}] {msg}")
+                .unwrap(),
+        );
+        pb.enable_steady_tick(Duration::from_millis(100));
+        Some(pb)
+    } else {
+        None
+    };
+    let mut attempts_at_last_find: u64 = 0;
+
     // Collect found keys with their output info
     let mut found_keys: Vec<KeyOutput> = Vec::new();
     let mut known_keys: HashSet<String> = existing_keys;
-    let target = args.target_keys;
+    if let Some(c) = &checkpoint {
+        known_keys.extend(c.keys.iter().cloned());
+    }
+    let target = target_keys;
     let max_time = if args.max_time > 0 {
         Some(Duration::from_secs(args.max_time))
     } else {
         None
     };
 
+    // Power and thermal limits, enforced through the same control channel
+    let mut governor = {
+        let config = governor::GovernorConfig {
+            max_temp: args.max_temp,
+            max_load: args.max_load,
+            pause_on_battery: args.pause_on_battery,
+            min_battery: args.min_battery,
+            sysfs_root: args.sysfs_root.clone(),
+        };
+        config.is_enabled().then(|| governor::Governor::new(config))
+    };
+    // Latest governor, schedule or warning message for the dashboard's notice line
+    let mut dashboard_notice: Option<String> = None;
+    // While the dashboard owns the terminal, warnings go to its notice line
+    let on_dashboard = dashboard.is_some();
+    // Whether the current time is inside a `--schedule` window (None before the first check)
+    let mut schedule_open: Option<bool> = None;
+    let mut last_checkpoint = Instant::now();
+    let mut checkpoint_dirty = false;
+    // Cores below this index are efficiency cores; the topology does not
+    // change during a run, so it is detected once rather than on every tick
+    let efficiency_count = num_cpus::get().saturating_sub(detect_perf_cores_count());
+
     loop {
+        let mut notice = None;
+        if let Some(g) = governor.as_mut() {
+            notice = g.tick(worker_pool.worker_count(), &control_tx);
+        }
+
+        if let Some(s) = &args.schedule {
+            let now = chrono::Local::now().naive_local();
+            let open = s.is_active(&now);
+            if schedule_open != Some(open) {
+                let _ = control_tx.send(if open {
+                    PoolCommand::Resume
+                } else {
+                    PoolCommand::Pause
+                });
+                if !open {
+                    notice = Some(match s.next_change(&now) {
+                        Some(at) => format!(
+                            "Outside the run window, paused until {}",
+                            at.format("%a %H:%M")
+                        ),
+                        None => "Outside the run window, paused".to_string(),
+                    });
+                } else if schedule_open.is_some() {
+                    notice = Some("Run window open, resuming workers".to_string());
+                }
+                schedule_open = Some(open);
+            }
+        }
+
+        if let Some(notice) = notice {
+            if let Some(ref pb) = progress_bar {
+                pb.suspend(|| println!("{} {}", style("⚠").yellow(), notice));
+            }
+            dashboard_notice = Some(notice);
+        }
+
+        // Apply pending pool changes; surviving workers keep their sampling state
+        let mut pool_changed = false;
+        for command in control_rx.try_iter() {
+            if let Err(e) = worker_pool.apply(command) {
+                warn(&mut dashboard_notice, on_dashboard, e);
+            }
+            pool_changed = true;
+        }
+        if pool_changed {
+            let counters = worker_pool.attempts_per_worker_snapshot();
+            let kept = worker_counters
+                .iter()
+                .zip(&counters)
+                .take_while(|(old, new)| Arc::ptr_eq(old, new))
+                .count();
+            prev_worker_totals.truncate(kept);
+            prev_worker_totals.resize(counters.len(), 0);
+            per_core_windows.truncate(kept);
+            per_core_windows.resize(counters.len(), vec![0.0f64; window_size]);
+            worker_counters = counters;
+        }
+        // Nothing (dashboard, HTTP, governor) resumes outside the run window
+        if schedule_open == Some(false) {
+            worker_pool.set_paused(true);
+        }
+        clock.set_running(!worker_pool.is_paused());
+
         // Check for found keys
         while let Ok(key) = rx.try_recv() {
+            // Workers and agents may deliver several hits at once; keep only the target count
+            if prior_found + found_keys.len() >= target {
+                break;
+            }
+
             // Check if this key already exists
             if known_keys.contains(&key.public_hex) {
                 if args.verbose && !args.json {
-                    eprintln!(
-                        "{} Skipping duplicate key: {}",
-                        style("⚠").yellow(),
-                        &key.public_hex[..16]
+                    warn(
+                        &mut dashboard_notice,
+                        on_dashboard,
+                        format!("Skipping duplicate key: {}", &key.public_hex[..16]),
                     );
                 }
+                metrics.duplicates_skipped.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+
+            // Node IDs fill up while keys are still in flight
+            if inventory
+                .as_ref()
+                .is_some_and(|i| !i.needs(key.public_bytes[0]))
+            {
                 continue;
             }
 
+            // GPU and agent hits only passed the built-in pattern
+            if let Some(p) = plugin_check.as_mut() {
+                if !p.matches(&key.public_bytes) {
+                    if args.verbose && !args.json {
+                        warn(
+                            &mut dashboard_notice,
+                            on_dashboard,
+                            format!("Skipping key the plugin rejects: {}", &key.public_hex[..16]),
+                        );
+                    }
+                    continue;
+                }
+            }
+
             // Verify key for MeshCore compatibility if requested
             let validation = if verify {
                 keygen::validate_for_meshcore(&key)
@@ -351,43 +1169,99 @@ fn main() {
             // Skip invalid keys if verification is enabled
             if verify && !validation.valid {
                 if args.verbose && !args.json {
-                    eprintln!(
-                        "{} Skipping invalid key: {} - {}",
-                        style("⚠").yellow(),
-                        &key.public_hex[..16],
-                        validation.reason.as_deref().unwrap_or("unknown")
+                    warn(
+                        &mut dashboard_notice,
+                        on_dashboard,
+                        format!(
+                            "Skipping invalid key: {} - {}",
+                            &key.public_hex[..16],
+                            validation.reason.as_deref().unwrap_or("unknown")
+                        ),
+                    );
+                    // The notice line has room for the summary only
+                    if !on_dashboard {
+                        for check in keygen::check_key_health(&key).failures() {
+                            eprintln!("    {} {}: {}", style("✗").red(), check.name, check.detail);
+                        }
+                    }
+                }
+                metrics.keys_rejected.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+
+            // Skip keys the blocklist forbids
+            if let Some(reason) = blocklist.as_ref().and_then(|b| b.check(&key)) {
+                if args.verbose && !args.json {
+                    warn(
+                        &mut dashboard_notice,
+                        on_dashboard,
+                        format!(
+                            "Skipping blocked key: {} - {}",
+                            &key.public_hex[..16],
+                            reason
+                        ),
                     );
                 }
+                metrics.keys_blocked.fetch_add(1, Ordering::Relaxed);
                 continue;
             }
 
+            // Full diagnostics are only attached to JSON output
+            let health = if verify && args.json {
+                Some(keygen::check_key_health(&key).checks)
+            } else {
+                None
+            };
+
             found_count.fetch_add(1, Ordering::Relaxed);
+            metrics.keys_found.fetch_add(1, Ordering::Relaxed);
             let count = found_count.load(Ordering::Relaxed) as usize;
 
             // Mark this key as known
             known_keys.insert(key.public_hex.clone());
 
-            // Save the key (skip in benchmark mode)
+            // Save the key (skip in benchmark mode), in its node ID's folder when filling
+            let node_dir = inventory.as_mut().map(|i| {
+                i.record(key.public_bytes[0]);
+                format!("{:02X}", key.public_bytes[0])
+            });
             let saved = if args.benchmark {
                 None
             } else {
-                save_key(&key, &output_dir, count, args.prefix.as_deref())
+                let saved = match &node_dir {
+                    Some(dir) => fs::create_dir_all(output_dir.join(dir))
+                        .map_err(|e| format!("Failed to create {}: {}", dir, e))
+                        .and_then(|_| save_key(&key, &output_dir.join(dir), count, None))
+                        .map(|(public, private)| {
+                            (
+                                format!("{}/{}", dir, public),
+                                format!("{}/{}", dir, private),
+                            )
+                        }),
+                    None => save_key(&key, &output_dir, count, args.prefix.as_deref()),
+                };
+                saved
+                    .map_err(|e| warn(&mut dashboard_notice, on_dashboard, e))
+                    .ok()
             };
 
             // Create output record
             let key_output = KeyOutput {
-                index: count,
-                public_key: key.public_hex.clone(),
-                private_key: key.private_hex.clone(),
-                node_id: key.public_hex[..2].to_string(),
-                first_8: key.public_hex[..8].to_string(),
-                last_8: key.public_hex[56..].to_string(),
-                meshcore_valid: validation.valid,
-                validation_error: validation.reason.clone(),
+                health_checks: health,
                 public_file: saved.as_ref().map(|(p, _)| p.clone()),
                 private_file: saved.as_ref().map(|(_, p)| p.clone()),
+                ..KeyOutput::new(count, &key, &validation, &pattern_config)
             };
 
+            if let Some(d) = dashboard.as_mut() {
+                d.record_key(
+                    count,
+                    &key.public_hex,
+                    pattern_config.highlight_ranges(&key.public_hex),
+                );
+            }
+            attempts_at_last_find = total_attempts.load(Ordering::Relaxed);
+
             if !args.json {
                 if let Some(ref pb) = progress_bar {
                     pb.suspend(|| {
@@ -411,6 +1285,9 @@ fn main() {
                         println!("  First 8:     {}", style(&key.public_hex[..8]).cyan());
                         println!("  Last 8:      {}", style(&key.public_hex[56..]).cyan());
                         println!("  Node ID:     {}", style(&key.public_hex[..2]).magenta());
+                        if let Some(word) = &key_output.matched_word {
+                            println!("  Word:        {}", style(word).green());
+                        }
                         if verify {
                             if validation.valid {
                                 println!("  MeshCore:    {}", style("✓ Valid").green());
@@ -432,16 +1309,39 @@ fn main() {
                 }
             }
 
+            if let (Some(log), Some(_)) = (audit_log.as_mut(), &saved) {
+                if let Err(e) = log.append(&key.public_hex, &pattern_config.description()) {
+                    warn(&mut dashboard_notice, on_dashboard, e);
+                }
+            }
+
+            if let Some(c) = checkpoint.as_mut() {
+                c.keys.push(key_output.public_key.clone());
+            }
             found_keys.push(key_output);
+            checkpoint_dirty = true;
 
-            if found_keys.len() >= target {
+            if prior_found + found_keys.len() >= target {
                 should_stop.store(true, Ordering::Relaxed);
             }
         }
 
         // Update progress
         let attempts = total_attempts.load(Ordering::Relaxed);
-        let elapsed = start_time.elapsed();
+        let elapsed = clock.elapsed();
+
+        if let (Some(c), Some(path)) = (checkpoint.as_mut(), &args.checkpoint) {
+            if checkpoint_dirty || last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL {
+                c.total_attempts = attempts;
+                c.active_seconds = elapsed.as_secs_f64();
+                if let Err(e) = c.save(path) {
+                    warn(&mut dashboard_notice, on_dashboard, e);
+                }
+                last_checkpoint = Instant::now();
+                checkpoint_dirty = false;
+            }
+        }
+
         let _rate = if elapsed.as_secs_f64() > 0.0 {
             attempts as f64 / elapsed.as_secs_f64()
         } else {
@@ -485,28 +1385,51 @@ fn main() {
             }
         };
 
-        // Total instantaneous rate approximate (sum per-core + gpu)
-        let total_inst_rate: f64 = per_core_rates.iter().sum::<f64>() + gpu_rate;
+        // Rates reported by connected agents
+        let nodes = coordinator
+            .as_ref()
+            .map(|c| c.node_stats())
+            .unwrap_or_default();
+        let remote_rate: f64 = nodes.iter().map(|n| n.rate).sum();
+
+        // Total instantaneous rate approximate (sum per-core + gpu + agents)
+        let total_inst_rate: f64 = per_core_rates.iter().sum::<f64>() + gpu_rate + remote_rate;
 
         // Estimate probability/time to finish
         let prob_per_attempt = pattern_config.estimated_probability();
-        let remaining = if target > found_keys.len() {
-            target - found_keys.len()
+        let remaining = if target > prior_found + found_keys.len() {
+            target - prior_found - found_keys.len()
         } else {
             0
         };
         let eta_seconds = if prob_per_attempt > 0.0 && total_inst_rate > 0.0 {
-            let attempts_per_key = 1.0 / prob_per_attempt;
-            let expected_attempts = attempts_per_key * (remaining as f64);
+            let expected_attempts = match &inventory {
+                Some(inv) => inv.expected_attempts(prob_per_attempt),
+                None => remaining as f64 / prob_per_attempt,
+            };
             expected_attempts / total_inst_rate
         } else {
             f64::INFINITY
         };
 
+        metrics.update(metrics::LiveStats {
+            total_attempts: attempts,
+            worker_attempts: prev_worker_totals.clone(),
+            worker_rates: per_core_rates.clone(),
+            gpu_rate,
+            total_rate: total_inst_rate,
+            eta_seconds,
+            agent_rates: nodes
+                .iter()
+                .filter(|n| n.connected)
+                .map(|n| (n.name.clone(), n.rate))
+                .collect(),
+            workers: worker_pool.worker_count(),
+            paused: worker_pool.is_paused(),
+            cpu_percent: worker_pool.cpu_percent(),
+        });
+
         // Format per-core rates into short fixed-width colored string using compact notation
-        let total_physical = num_cpus::get();
-        let perf_count = detect_perf_cores_count();
-        let efficiency_count = total_physical.saturating_sub(perf_count);
 
         let per_core_str = per_core_rates
             .iter()
@@ -529,10 +1452,18 @@ fn main() {
                 };
                 format!("{}{}", label_s, count_s)
             })
+            .chain(nodes.iter().filter(|n| n.connected).map(|n| {
+                format!(
+                    "{}{}",
+                    style(format!("{}:", n.name)).magenta(),
+                    style(format!("{:>6}", format_compact_f64(n.rate))).green()
+                )
+            }))
             .collect::<Vec<_>>()
             .join(" ");
 
         if let Some(ref pb) = progress_bar {
+            pb.set_prefix(format_hms(elapsed.as_secs()));
             let eta_display = if eta_seconds.is_finite() {
                 let et =
                     chrono::Local::now() + chrono::Duration::seconds(eta_seconds.round() as i64);
@@ -545,6 +1476,7 @@ fn main() {
             let rate_s = format_compact_f64(total_inst_rate);
             let found_s = format_compact_u64(found_count.load(Ordering::Relaxed));
             let target_s = format_compact_u64(target as u64);
+            let cpu_cap = worker_pool.cpu_percent();
 
             if args.beautiful {
                 // Beautiful mode: cleaner multi-line style statistics
@@ -568,8 +1500,13 @@ fn main() {
                 };
 
                 pb.set_message(format!(
-                    "{mode} {attempts:>10} attempts │ {rate:>8}/s │ Progress: {found}/{target} ({pct:>5.1}%) │ CPU:{cpu:>8}/s GPU:{gpu:>8}/s │ {eta}",
+                    "{mode} {attempts:>10} attempts │ {rate:>8}/s │ Progress: {found}/{target} ({pct:>5.1}%) │ CPU:{cpu:>8}/s{cap} GPU:{gpu:>8}/s │ {eta}",
                     mode = mode_str,
+                    cap = if cpu_cap < 100 {
+                        format!(" (cap {}%)", cpu_cap)
+                    } else {
+                        String::new()
+                    },
                     attempts = attempts_s,
                     rate = rate_s,
                     found = found_s,
@@ -581,9 +1518,14 @@ fn main() {
                 ));
             } else {
                 pb.set_message(format!(
-                    "{attempts:>10} | Rate: {rate:>8}/s | Found: {found:>6}/{target:<6} | {eta} | GPU:{gpu:>8}/s | {cores}",
+                    "{attempts:>10} | Rate: {rate:>8}/s{cap} | Found: {found:>6}/{target:<6} | {eta} | GPU:{gpu:>8}/s | {cores}",
                     attempts = attempts_s,
                     rate = rate_s,
+                    cap = if cpu_cap < 100 {
+                        format!(" | CPU cap: {:>3}%", cpu_cap)
+                    } else {
+                        String::new()
+                    },
                     found = found_s,
                     target = target_s,
                     eta = eta_display,
@@ -593,6 +1535,46 @@ fn main() {
             }
         }
 
+        if let Some(d) = dashboard.as_mut() {
+            let mode = if args.benchmark {
+                "BENCHMARK"
+            } else if args.powersave {
+                "POWERSAVE"
+            } else if args.brutal {
+                "BRUTAL"
+            } else {
+                ""
+            };
+            let stats = tui::DashboardStats {
+                pattern: &pattern_config.description(),
+                mode,
+                elapsed,
+                attempts,
+                total_rate: total_inst_rate,
+                gpu_rate,
+                core_rates: &per_core_rates,
+                perf_core_start: args.brutal.then_some(efficiency_count),
+                found: prior_found + found_keys.len(),
+                target,
+                probability: prob_per_attempt,
+                attempts_since_last: attempts.saturating_sub(attempts_at_last_find),
+                eta_seconds,
+                paused: worker_pool.is_paused(),
+                cpu_percent: worker_pool.cpu_percent(),
+                notice: dashboard_notice.as_deref(),
+            };
+            let refresh = Duration::from_millis(args.refresh_ms.max(50));
+            let actions = d.draw(&stats).and_then(|_| d.poll_actions(refresh));
+            for action in actions.unwrap_or_else(|_| vec![tui::DashboardAction::Quit]) {
+                match action {
+                    tui::DashboardAction::Pool(command) => {
+                        let _ = control_tx.send(command);
+                    }
+                    tui::DashboardAction::Quit => should_stop.store(true, Ordering::Relaxed),
+                }
+            }
+        }
+
         // Check stop conditions
         if should_stop.load(Ordering::Relaxed) {
             break;
@@ -600,7 +1582,7 @@ fn main() {
 
         if let Some(max_dur) = max_time {
             if elapsed >= max_dur {
-                if !args.json {
+                if !args.json && dashboard.is_none() {
                     println!("\n{} Time limit reached", style("⏱").yellow());
                 }
                 should_stop.store(true, Ordering::Relaxed);
@@ -608,36 +1590,146 @@ fn main() {
             }
         }
 
-        // Use configurable refresh interval for smoother display
-        std::thread::sleep(Duration::from_millis(args.refresh_ms.max(50)));
+        // Use configurable refresh interval for smoother display (the dashboard
+        // already waited for keyboard input for that long)
+        if dashboard.is_none() {
+            std::thread::sleep(Duration::from_millis(args.refresh_ms.max(50)));
+        }
     }
 
+    // Restore the terminal before printing the summary
+    drop(dashboard);
+
     // Cleanup
     worker_pool.stop();
+    let nodes = coordinator.map(|c| {
+        let nodes = c.node_stats();
+        c.shutdown();
+        nodes
+    });
     if let Some(pb) = progress_bar {
         pb.finish_and_clear();
     }
 
-    // Summary
-    let elapsed = start_time.elapsed();
-    let attempts = total_attempts.load(Ordering::Relaxed);
-    let rate = if elapsed.as_secs_f64() > 0.0 {
-        attempts as f64 / elapsed.as_secs_f64()
-    } else {
-        0.0
-    };
-
-    let valid_count = found_keys.iter().filter(|k| k.meshcore_valid).count();
-
-    if args.json {
-        // Output JSON
-        let summary = SummaryOutput {
-            total_time_seconds: elapsed.as_secs_f64(),
-            total_attempts: attempts,
-            average_rate: rate,
-            keys_found: found_keys.len(),
+    // A run that ends short of its target still leaves the best-scoring keys
+    let mut best_output: Vec<KeyOutput> = Vec::new();
+    let best_kind = best_keys.as_ref().map(|b| b.scorer().kind());
+    if let Some(best) = best_keys.filter(|_| prior_found + found_keys.len() < target) {
+        for candidate in best.snapshot() {
+            let key = candidate.key;
+            if known_keys.contains(&key.public_hex) {
+                continue;
+            }
+            let validation = if verify {
+                keygen::validate_for_meshcore(&key)
+            } else {
+                keygen::ValidationResult {
+                    valid: true,
+                    reason: None,
+                }
+            };
+            if !validation.valid {
+                continue;
+            }
+            if blocklist.as_ref().is_some_and(|b| b.check(&key).is_some()) {
+                metrics.keys_blocked.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+
+            let index = best_output.len() + 1;
+            let saved = if args.benchmark {
+                None
+            } else {
+                save_key(&key, &output_dir, index, Some("best"))
+                    .map_err(|e| eprintln!("{} {}", style("⚠").yellow(), e))
+                    .ok()
+            };
+            if let (Some(log), Some(_)) = (audit_log.as_mut(), &saved) {
+                let pattern = format!("Best of: {}", pattern_config.description());
+                if let Err(e) = log.append(&key.public_hex, &pattern) {
+                    eprintln!("{} {}", style("⚠").yellow(), e);
+                }
+            }
+            best_output.push(KeyOutput {
+                score: Some(candidate.score),
+                public_file: saved.as_ref().map(|(p, _)| p.clone()),
+                private_file: saved.as_ref().map(|(_, p)| p.clone()),
+                ..KeyOutput::new(index, &key, &validation, &pattern_config)
+            });
+        }
+    }
+
+    // Summary
+    let elapsed = clock.elapsed();
+    let attempts = total_attempts.load(Ordering::Relaxed);
+
+    if let (Some(c), Some(path)) = (checkpoint.as_mut(), &args.checkpoint) {
+        c.total_attempts = attempts;
+        c.active_seconds = elapsed.as_secs_f64();
+        if let Err(e) = c.save(path) {
+            eprintln!("{} {}", style("⚠").yellow(), e);
+        }
+    }
+    let rate = if elapsed.as_secs_f64() > 0.0 {
+        attempts as f64 / elapsed.as_secs_f64()
+    } else {
+        0.0
+    };
+
+    let valid_count = found_keys.iter().filter(|k| k.meshcore_valid).count();
+
+    // Record what produced this run next to the saved keys
+    if let Some(host) = host_info {
+        let saved_files = found_keys
+            .iter()
+            .chain(&best_output)
+            .flat_map(|k| [k.public_file.as_deref(), k.private_file.as_deref()])
+            .flatten();
+        let run_id = (output_dir != base_output.join(&run_ts)).then_some(run_ts.as_str());
+        let result = manifest::checksum_files(&output_dir, saved_files)
+            .map_err(|e| format!("Failed to checksum saved keys: {}", e))
+            .and_then(|files| {
+                let run_manifest = manifest::RunManifest {
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    args: std::env::args().collect(),
+                    pattern: serde_json::to_value(&pattern_config).unwrap(),
+                    pattern_description: pattern_config.description(),
+                    workers: worker_count,
+                    verify,
+                    host,
+                    start_time: start_wall.to_rfc3339(),
+                    end_time: chrono::Local::now().to_rfc3339(),
+                    total_time_seconds: elapsed.as_secs_f64(),
+                    total_attempts: attempts,
+                    average_rate: rate,
+                    keys_found: found_keys.len(),
+                    files,
+                };
+                manifest::write_manifest(
+                    &manifest::manifest_path(&output_dir, run_id),
+                    &run_manifest,
+                )
+            });
+        if let Err(e) = result {
+            eprintln!("{} {}", style("⚠").yellow(), e);
+        }
+    }
+
+    if args.json {
+        // Output JSON
+        let summary = SummaryOutput {
+            total_time_seconds: elapsed.as_secs_f64(),
+            total_attempts: attempts,
+            average_rate: rate,
+            keys_found: found_keys.len(),
             keys_valid: valid_count,
+            keys_rejected: metrics.keys_rejected.load(Ordering::Relaxed),
+            keys_blocked: metrics.keys_blocked.load(Ordering::Relaxed),
+            nodes,
             keys: found_keys,
+            best_keys: best_output,
+            plugin_failed_calls: plugin.as_ref().map(|p| p.failed_calls()),
+            node_ids: inventory.as_ref().map(|i| i.coverage()),
         };
         println!("{}", serde_json::to_string_pretty(&summary).unwrap());
     } else {
@@ -663,12 +1755,701 @@ fn main() {
         if verify {
             println!("  Keys Valid:      {} (MeshCore compatible)", valid_count);
         }
+        if let Some(failed) = plugin.as_ref().map(|p| p.failed_calls()).filter(|&n| n > 0) {
+            println!(
+                "  {} Plugin calls that trapped or ran out of fuel: {}",
+                style("⚠").yellow(),
+                format_number(failed)
+            );
+        }
+        let rejected = metrics.keys_rejected.load(Ordering::Relaxed);
+        if rejected > 0 {
+            println!(
+                "  Keys Rejected:   {} (failed MeshCore validation)",
+                rejected
+            );
+        }
+        if blocklist.is_some() {
+            println!(
+                "  Keys Blocked:    {} (blocklist)",
+                metrics.keys_blocked.load(Ordering::Relaxed)
+            );
+        }
+        if let Some(inv) = &inventory {
+            println!(
+                "  Node IDs Full:   {}/{} ({} key(s) each)",
+                inv.full_count(),
+                inv.wanted_count(),
+                inv.per_id()
+            );
+            inv.print_grid();
+        }
+        for node in nodes.iter().flatten() {
+            println!(
+                "  Agent {:<10} {} attempts",
+                node.name,
+                format_number(node.attempts)
+            );
+        }
+        if let Some(kind) = best_kind.filter(|_| !best_output.is_empty()) {
+            println!(
+                "  Best Keys:       {} by {} score (no full match)",
+                best_output.len(),
+                kind.name()
+            );
+            for k in &best_output {
+                println!(
+                    "    #{:<3} {}  {}",
+                    k.index,
+                    style(&k.public_key).yellow(),
+                    k.score.map(|s| s.to_string()).unwrap_or_default()
+                );
+            }
+        }
         println!();
     }
 }
 
-fn build_pattern_config(args: &Args) -> PatternConfig {
-    let mut config = PatternConfig::default();
+/// Run a maintenance subcommand and return the process exit code
+fn run_command(command: &Command, args: &Args) -> i32 {
+    let json = args.json;
+    match command {
+        Command::Audit {
+            target,
+            public,
+            report,
+        } if Path::new(target).is_dir() => {
+            let archive_report = audit::audit_archive(Path::new(target));
+            let report_json = serde_json::to_string_pretty(&archive_report).unwrap();
+            if let Some(path) = report {
+                if let Err(e) = fs::write(path, &report_json) {
+                    eprintln!("{} Failed to write report: {}", style("✗").red(), e);
+                    return 2;
+                }
+            }
+            if json {
+                println!("{}", report_json);
+            } else {
+                audit::print_archive_report(&archive_report);
+            }
+            if public.is_some() {
+                eprintln!(
+                    "{} --public is ignored when auditing a directory",
+                    style("⚠").yellow()
+                );
+            }
+            if archive_report.is_clean() {
+                0
+            } else {
+                1
+            }
+        }
+        Command::Audit { target, public, .. } => {
+            let key = match audit::load_key(target, public.as_deref()) {
+                Ok(key) => key,
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    return 2;
+                }
+            };
+            let report = keygen::check_key_health(&key);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            } else {
+                audit::print_report(&key, &report);
+            }
+            if report.healthy {
+                0
+            } else {
+                1
+            }
+        }
+        Command::Organize {
+            library,
+            sources,
+            layout,
+            move_files,
+        } => match library::organize(library, sources, *layout, *move_files) {
+            Ok(summary) => {
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+                } else {
+                    println!("  Imported:    {}", style(summary.imported).green());
+                    println!("  Duplicates:  {}", summary.duplicates);
+                    println!(
+                        "  Skipped:     {} (run `audit` for details)",
+                        summary.skipped
+                    );
+                    println!("  Conflicts:   {}", summary.conflicts);
+                    println!(
+                        "  Indexed:     {} key(s) in {}",
+                        summary.indexed,
+                        library.join(library::INDEX_FILE).display()
+                    );
+                }
+                0
+            }
+            Err(e) => {
+                eprintln!("{} {}", style("✗").red(), e);
+                2
+            }
+        },
+        Command::VerifyManifest { path } => {
+            let manifests = manifest::find_manifests(path);
+            if manifests.is_empty() {
+                eprintln!(
+                    "{} No manifest found at {}",
+                    style("✗").red(),
+                    path.display()
+                );
+                return 2;
+            }
+
+            let mut results = Vec::new();
+            for manifest_file in &manifests {
+                match manifest::verify_manifest(manifest_file) {
+                    Ok(result) => results.push(result),
+                    Err(e) => {
+                        eprintln!("{} {}", style("✗").red(), e);
+                        return 2;
+                    }
+                }
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results).unwrap());
+            } else {
+                for result in &results {
+                    println!("  Manifest: {}", style(&result.manifest).dim());
+                    for file in &result.files {
+                        let mark = match file.status {
+                            manifest::FileStatus::Ok => style("✓").green(),
+                            _ => style("✗").red(),
+                        };
+                        println!(
+                            "    {} {:<9} {}",
+                            mark,
+                            format!("{:?}", file.status),
+                            file.file
+                        );
+                    }
+                }
+            }
+            if results.iter().all(|r| r.is_intact()) {
+                0
+            } else {
+                1
+            }
+        }
+        Command::SplitRequest {
+            request,
+            secret,
+            force,
+        } => {
+            let pattern = match build_pattern_config(args) {
+                Ok(pattern) => pattern,
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    return 2;
+                }
+            };
+            let split = split_key::new_request(pattern);
+            // Never clobber an earlier secret: a helper may still be searching its request
+            let written = write_private(
+                secret,
+                &serde_json::to_string_pretty(&split).unwrap(),
+                *force,
+            )
+            .and_then(|_| write_json(request, &split.request));
+            if let Err(e) = written {
+                eprintln!("{} {}", style("✗").red(), e);
+                return 2;
+            }
+            if json {
+                println!("{}", serde_json::to_string_pretty(&split.request).unwrap());
+            } else {
+                println!(
+                    "{} Pattern: {}",
+                    style("ℹ").blue(),
+                    split.request.pattern.description()
+                );
+                println!("  Request: {} (share with helpers)", request.display());
+                println!(
+                    "  Secret:  {} ({})",
+                    secret.display(),
+                    style("keep private").red()
+                );
+            }
+            0
+        }
+        Command::SplitSearch {
+            request,
+            workers,
+            max_time,
+        } => {
+            let split: split_key::SplitRequest = match read_json(request) {
+                Ok(split) => split,
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    return 2;
+                }
+            };
+            let workers = workers.unwrap_or_else(|| detect_cpu_cores(false, false));
+            let attempts = AtomicU64::new(0);
+            let should_stop = AtomicBool::new(false);
+            let start = Instant::now();
+
+            if !json {
+                println!(
+                    "{} Searching for {} with {} workers",
+                    style("ℹ").blue(),
+                    split.pattern.description(),
+                    workers
+                );
+            }
+            let result = std::thread::scope(|scope| {
+                let search =
+                    scope.spawn(|| split_key::search(&split, workers, &attempts, &should_stop));
+                while !search.is_finished() {
+                    std::thread::sleep(Duration::from_millis(200));
+                    if *max_time > 0 && start.elapsed().as_secs() >= *max_time {
+                        should_stop.store(true, Ordering::Relaxed);
+                    }
+                }
+                search.join().unwrap()
+            });
+
+            match result {
+                Ok(Some(solution)) => {
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&solution).unwrap());
+                    } else {
+                        println!(
+                            "{} Found after {} attempts in {:.1}s",
+                            style("✓").green(),
+                            format_number(attempts.load(Ordering::Relaxed)),
+                            start.elapsed().as_secs_f64()
+                        );
+                        println!("  Offset:      {}", style(&solution.offset).yellow());
+                        println!("  Public Key:  {}", solution.public_key);
+                    }
+                    0
+                }
+                Ok(None) => {
+                    eprintln!(
+                        "{} No offset found before the time limit",
+                        style("⚠").yellow()
+                    );
+                    1
+                }
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    2
+                }
+            }
+        }
+        Command::Combine {
+            secret,
+            offset,
+            output,
+        } => {
+            let result = read_json::<split_key::SplitSecret>(secret)
+                .and_then(|secret| split_key::combine(&secret, split_key::parse_offset(offset)?));
+            let key = match result {
+                Ok(key) => key,
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    return 1;
+                }
+            };
+            if let Err(e) = fs::create_dir_all(output) {
+                eprintln!(
+                    "{} Failed to create {}: {}",
+                    style("✗").red(),
+                    output.display(),
+                    e
+                );
+                return 2;
+            }
+            let (public_file, private_file) = match save_key(&key, output, 1, None) {
+                Ok(files) => files,
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    return 2;
+                }
+            };
+            if json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "public_key": key.public_hex,
+                        "public_file": public_file,
+                        "private_file": private_file,
+                    })
+                );
+            } else {
+                println!("{} Combined key verified", style("✓").green());
+                println!("  Public Key:  {}", style(&key.public_hex).yellow());
+                println!("  MeshCore:    {}", style("✓ Valid").green());
+                println!("  Saved to:");
+                println!("    Public:  {}", style(&public_file).dim());
+                println!("    Private: {}", style(&private_file).dim());
+            }
+            0
+        }
+        Command::ClusterSecret { path, force } => {
+            if let Err(e) = write_private(path, &distributed::generate_secret(), *force) {
+                eprintln!("{} {}", style("✗").red(), e);
+                return 2;
+            }
+            if json {
+                println!("{}", serde_json::json!({ "secret_file": path }));
+            } else {
+                println!(
+                    "{} Cluster secret written to {} ({})",
+                    style("✓").green(),
+                    path.display(),
+                    style("keep private").red()
+                );
+            }
+            0
+        }
+        Command::Agent {
+            coordinator,
+            workers,
+            name,
+        } => {
+            let secret = match cluster_secret(args) {
+                Ok(secret) => secret,
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    return 2;
+                }
+            };
+            let config = distributed::AgentConfig {
+                coordinator: coordinator.clone(),
+                secret,
+                name: name
+                    .clone()
+                    .or_else(sysinfo::System::host_name)
+                    .unwrap_or_else(|| "agent".to_string()),
+                workers: workers.unwrap_or_else(|| detect_cpu_cores(false, false)),
+            };
+
+            let progress_bar = (!json).then(|| {
+                let pb = ProgressBar::new_spinner();
+                pb.set_style(
+                    ProgressStyle::default_spinner()
+                        .template("{spinner:.green} [{elapsed_precise}] {msg}")
+                        .unwrap(),
+                );
+                pb.enable_steady_tick(Duration::from_millis(100));
+                pb
+            });
+            let should_stop = AtomicBool::new(false);
+            let end = distributed::run_agent(&config, &should_stop, &|status| {
+                if let Some(pb) = &progress_bar {
+                    pb.set_message(status.to_string());
+                }
+            });
+            if let Some(pb) = progress_bar {
+                pb.finish_and_clear();
+            }
+
+            match end {
+                distributed::SessionEnd::Stopped => {
+                    if !json {
+                        println!("{} Coordinator finished the search", style("✓").green());
+                    }
+                    0
+                }
+                distributed::SessionEnd::Rejected(reason)
+                | distributed::SessionEnd::Disconnected(reason) => {
+                    eprintln!("{} {}", style("✗").red(), reason);
+                    2
+                }
+            }
+        }
+        Command::VerifyLog { path, signer } => {
+            let result = match audit_log::verify_log(path, signer.as_deref()) {
+                Ok(result) => result,
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    return 2;
+                }
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&result).unwrap());
+            } else {
+                println!("  Entries:  {}", result.entries);
+                println!("  Signed:   {}", result.signed_entries);
+                println!("  Head:     {}", style(&result.head_hash).dim());
+                if result.errors.is_empty() {
+                    println!("  {} Chain intact", style("✓").green());
+                } else {
+                    for error in &result.errors {
+                        println!("  {} {}", style("✗").red(), error);
+                    }
+                }
+            }
+            if result.errors.is_empty() {
+                0
+            } else {
+                1
+            }
+        }
+        Command::Explain {
+            calibrate,
+            examples,
+            workers,
+        } => {
+            let pattern_config = match build_pattern_config(args) {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    return 2;
+                }
+            };
+            let workers = workers.unwrap_or_else(|| detect_cpu_cores(false, false));
+            if !json && *calibrate > 0.0 {
+                println!(
+                    "{} Calibrating for {}s with {} workers...",
+                    style("ℹ").cyan(),
+                    calibrate,
+                    workers
+                );
+            }
+            let explanation = explain::explain(
+                &pattern_config,
+                pattern_flag_notes(args),
+                !args.no_verify,
+                workers,
+                Duration::from_secs_f64(calibrate.max(0.0)),
+                *examples,
+            );
+            if json {
+                println!("{}", serde_json::to_string_pretty(&explanation).unwrap());
+            } else {
+                explain::print_explanation(&explanation);
+            }
+            if explanation.feasible {
+                0
+            } else {
+                1
+            }
+        }
+        Command::Plan {
+            budget,
+            calibrate,
+            workers,
+        } => {
+            // The given pattern flags, if any, get a row of their own
+            let pattern_given = args.prefix.is_some()
+                || args.vanity.is_some()
+                || args.pattern.is_some()
+                || args.dictionary.is_some()
+                || args.suffix.is_some()
+                || args.infix.is_some()
+                || args.node_range.is_some()
+                || args.match_expr.is_some()
+                || args.regex.is_some();
+            let pattern_config = match pattern_given.then(|| build_pattern_config(args)) {
+                Some(Err(e)) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    return 2;
+                }
+                Some(Ok(config)) => Some(config),
+                None => None,
+            };
+            let thread_policy = match thread_policy(args) {
+                Ok(policy) => policy,
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    return 2;
+                }
+            };
+            let workers = workers.unwrap_or_else(|| {
+                default_worker_count(
+                    detect_cpu_cores(args.brutal, args.powersave),
+                    &thread_policy,
+                )
+            });
+            if !json {
+                println!(
+                    "{} Benchmarking {} workers for {}s...",
+                    style("ℹ").cyan(),
+                    workers,
+                    calibrate
+                );
+            }
+            let rate = match plan::benchmark(
+                workers,
+                thread_policy,
+                Duration::from_secs((*calibrate).max(1)),
+            ) {
+                Ok(rate) => rate,
+                Err(e) => {
+                    eprintln!("{} {}", style("✗").red(), e);
+                    return 2;
+                }
+            };
+            let plan = plan::plan(
+                *budget,
+                workers,
+                rate,
+                &args.symmetry,
+                pattern_config.as_ref(),
+            );
+            if json {
+                println!("{}", serde_json::to_string_pretty(&plan).unwrap());
+            } else {
+                plan::print_plan(&plan);
+            }
+            0
+        }
+    }
+}
+
+/// Read a JSON file written by one of the maintenance commands
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
+
+/// Write a value as pretty JSON
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    fs::write(path, serde_json::to_string_pretty(value).unwrap())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Write a file only its owner can read, refusing to replace one unless `force`
+fn write_private(path: &Path, contents: &str, force: bool) -> Result<(), String> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => {
+            format!(
+                "{} already exists (use --force to overwrite it)",
+                path.display()
+            )
+        }
+        _ => format!("Failed to write {}: {}", path.display(), e),
+    })?;
+    // The mode only applies to new files; tighten one being overwritten too
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))
+        .map_err(|e| format!("Failed to restrict {}: {}", path.display(), e))?;
+    file.write_all(contents.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The secret in the `--cluster-secret` file
+fn cluster_secret(args: &Args) -> Result<Vec<u8>, String> {
+    let path = args
+        .cluster_secret
+        .as_deref()
+        .ok_or("--cluster-secret is required for coordinator and agent mode")?;
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    distributed::parse_secret(&content).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Open the `--audit-log` file, loading the operator signing key if one was given
+fn open_audit_log(args: &Args) -> Result<Option<audit_log::AuditLog>, String> {
+    let Some(path) = &args.audit_log else {
+        return Ok(None);
+    };
+    let signer = args
+        .audit_signing_key
+        .as_deref()
+        .map(audit::load_signing_key)
+        .transpose()?;
+    audit_log::AuditLog::open(path, signer).map(Some)
+}
+
+/// Score that ranks `--best` keys: `--score`, else the plugin's, else the pattern's
+fn best_score_kind(
+    args: &Args,
+    pattern: &PatternConfig,
+    plugin: Option<&plugin::Plugin>,
+) -> score::ScoreKind {
+    args.score.unwrap_or_else(|| {
+        if plugin.is_some_and(|p| p.has_score()) {
+            score::ScoreKind::Plugin
+        } else {
+            score::ScoreKind::for_pattern(pattern)
+        }
+    })
+}
+
+fn build_pattern_config(args: &Args) -> Result<PatternConfig, String> {
+    if let Some(path) = &args.dictionary {
+        let dictionary =
+            dictionary::Dictionary::load(path, args.leet, args.min_word_len, args.dict_anchor)?;
+        return Ok(PatternConfig::with_dictionary(dictionary));
+    }
+    if let Some(regex) = &args.regex {
+        return Ok(PatternConfig::with_regex(regex.clone()));
+    }
+
+    // Anything beyond prefix and vanity is composed as an expression,
+    // with every given constraint required
+    let mut parts = Vec::new();
+    if let Some(suffix) = &args.suffix {
+        parts.push(expr::Expr::parse(&format!("suffix:{}", suffix))?);
+    }
+    if let Some(infix) = &args.infix {
+        parts.push(expr::Expr::parse(&format!("at:{}", infix))?);
+    }
+    if let Some(range) = &args.node_range {
+        let atom = if range.split('-').any(|v| v.len() > 2) {
+            "node16"
+        } else {
+            "node"
+        };
+        parts.push(expr::Expr::parse(&format!("{}:{}", atom, range))?);
+    }
+    if let Some(expression) = &args.match_expr {
+        parts.push(expression.clone());
+    }
+    if !parts.is_empty() {
+        if let Some(prefix) = &args.prefix {
+            parts.insert(0, expr::Expr::parse(&format!("prefix:{}", prefix))?);
+        }
+        if let Some(length) = args.vanity.or(args.pattern) {
+            parts.push(expr::Expr::Vanity {
+                length: length as usize,
+                symmetries: args.symmetry.clone(),
+            });
+        }
+        return Ok(PatternConfig::with_expression(expr::Expr::all(parts)));
+    }
+
+    // A plugin or a node-ID fill alone sees every key
+    if (args.plugin.is_some() || args.fill_node_ids.is_some())
+        && args.prefix.is_none()
+        && args.vanity.is_none()
+        && args.pattern.is_none()
+    {
+        return Ok(PatternConfig {
+            mode: PatternMode::Any,
+            ..PatternConfig::default()
+        });
+    }
+
+    let mut config = PatternConfig {
+        symmetries: args.symmetry.clone(),
+        ..PatternConfig::default()
+    };
 
     if let Some(prefix) = &args.prefix {
         config.mode = PatternMode::Prefix;
@@ -690,7 +2471,58 @@ fn build_pattern_config(args: &Args) -> PatternConfig {
         config.mode = PatternMode::PrefixVanity;
     }
 
-    config
+    Ok(config)
+}
+
+/// How `build_pattern_config` combines the given flags, for `explain`
+fn pattern_flag_notes(args: &Args) -> Vec<String> {
+    let mut notes = Vec::new();
+    let shorthands: Vec<&str> = [
+        (args.suffix.is_some(), "--suffix"),
+        (args.infix.is_some(), "--infix"),
+        (args.node_range.is_some(), "--node-range"),
+        (args.match_expr.is_some(), "--match"),
+    ]
+    .into_iter()
+    .filter_map(|(given, flag)| given.then_some(flag))
+    .collect();
+    let vanity_flags = args.vanity.is_some() || args.pattern.is_some();
+
+    if args.dictionary.is_some() || args.regex.is_some() {
+        // These stand alone
+    } else if !shorthands.is_empty() {
+        let mut flags = shorthands;
+        if args.prefix.is_some() {
+            flags.insert(0, "--prefix");
+        }
+        if vanity_flags {
+            flags.push("the vanity length");
+        }
+        if let Some((last, rest)) = flags.split_last().filter(|(_, rest)| !rest.is_empty()) {
+            notes.push(format!(
+                "{} and {} are combined with AND",
+                rest.join(", "),
+                last
+            ));
+        }
+    } else if args.prefix.is_none() && !vanity_flags {
+        if args.plugin.is_some() {
+            notes.push("Only --plugin given: every key goes to the plugin".to_string());
+        } else {
+            notes.push("No pattern flags given: defaults to --pattern 8".to_string());
+        }
+    }
+    if let (Some(vanity), Some(pattern)) = (args.vanity, args.pattern) {
+        notes.push(format!(
+            "--pattern {} overrides --vanity {}",
+            pattern, vanity
+        ));
+    }
+    if args.plugin.is_some() {
+        notes
+            .push("--plugin is not run here; the odds cover the built-in pattern only".to_string());
+    }
+    notes
 }
 
 fn detect_cpu_cores(brutal: bool, powersave: bool) -> usize {
@@ -735,26 +2567,123 @@ fn detect_cpu_cores(brutal: bool, powersave: bool) -> usize {
                 return cores;
             }
         }
+
+        // Fallback to num_cpus - 75% of cores like the Python version
+        let cores = num_cpus::get();
+        std::cmp::max(2, (cores as f64 * 0.75).round() as usize)
     }
 
     #[cfg(not(target_os = "macos"))]
     {
-        // brutal takes precedence over powersave
-        if brutal {
-            let cores = num_cpus::get();
-            return std::cmp::max(1, cores.saturating_sub(1));
+        workers_for_topology(&topology::CpuTopology::detect(), brutal, powersave)
+    }
+}
+
+/// Worker count for the CPUs this process may use (cpuset, quota, hybrid cores)
+#[cfg(not(target_os = "macos"))]
+fn workers_for_topology(topology: &topology::CpuTopology, brutal: bool, powersave: bool) -> usize {
+    let available = topology.available();
+    // 75% of usable cores like the Python version
+    let normal = std::cmp::max(1, (available as f64 * 0.75).round() as usize);
+
+    // brutal takes precedence over powersave
+    if brutal {
+        // Leave one core free for responsiveness, but never use fewer than normal
+        return available.saturating_sub(1).max(normal);
+    }
+
+    if powersave {
+        // Efficiency cores only on hybrid parts, otherwise half of usable cores
+        if topology.is_hybrid() {
+            return topology.efficiency_cpus.len().min(available).max(1);
         }
+        return std::cmp::max(1, available / 2);
+    }
 
-        if powersave {
-            // Use half of total cores on non-macOS
-            let cores = num_cpus::get();
-            return std::cmp::max(1, cores / 2);
+    normal
+}
+
+/// Placement and scheduling class for CPU workers from `--cpus`,
+/// `--core-type`, `--powersave`, `--sched-idle` and `--nice`
+fn thread_policy(args: &Args) -> Result<affinity::ThreadPolicy, String> {
+    #[cfg(target_os = "linux")]
+    {
+        let topology = topology::CpuTopology::detect();
+        Ok(affinity::ThreadPolicy {
+            cpus: placement_cpus(
+                &topology,
+                args.cpus.as_deref(),
+                args.core_type,
+                args.powersave && !args.brutal,
+            )?,
+            nice: args.nice,
+            sched_idle: args.sched_idle,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        if args.cpus.is_some() || args.core_type.is_some() || args.sched_idle || args.nice.is_some()
+        {
+            return Err(
+                "--cpus, --core-type, --sched-idle and --nice are only supported on Linux"
+                    .to_string(),
+            );
+        }
+        Ok(affinity::ThreadPolicy::default())
+    }
+}
+
+/// CPUs to pin workers to: an explicit list (which must be usable by this
+/// process), one core type of a hybrid part, or the efficiency cores for
+/// `--powersave` on hybrid parts; empty leaves placement to the kernel
+#[cfg(target_os = "linux")]
+fn placement_cpus(
+    topology: &topology::CpuTopology,
+    cpus: Option<&str>,
+    core_type: Option<affinity::CoreType>,
+    powersave: bool,
+) -> Result<Vec<usize>, String> {
+    if let Some(list) = cpus {
+        let cpus = topology::parse_cpu_list(list)
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| format!("Invalid CPU list '{}' (expected e.g. 0-3,8)", list))?;
+        if let Some(cpu) = cpus.iter().find(|c| !topology.cpus.contains(c)) {
+            return Err(format!(
+                "CPU {} is not available to this process (usable: {})",
+                cpu,
+                affinity::format_cpu_list(&topology.cpus)
+            ));
+        }
+        return Ok(cpus);
+    }
+
+    if let Some(core_type) = core_type {
+        if !topology.is_hybrid() {
+            return Err(
+                "--core-type needs a hybrid CPU with both performance and efficiency cores"
+                    .to_string(),
+            );
         }
+        return Ok(match core_type {
+            affinity::CoreType::Performance => topology.perf_cpus.clone(),
+            affinity::CoreType::Efficiency => topology.efficiency_cpus.clone(),
+        });
     }
 
-    // Fallback to num_cpus - 75% of cores like the Python version
-    let cores = num_cpus::get();
-    std::cmp::max(2, (cores as f64 * 0.75).round() as usize)
+    if powersave && topology.is_hybrid() {
+        return Ok(topology.efficiency_cpus.clone());
+    }
+    Ok(Vec::new())
+}
+
+/// Without `--workers`, one worker per pinned CPU, else the core heuristics
+fn default_worker_count(cpu_cores: usize, policy: &affinity::ThreadPolicy) -> usize {
+    if policy.cpus.is_empty() {
+        cpu_cores
+    } else {
+        policy.cpus.len()
+    }
 }
 
 fn detect_perf_cores_count() -> usize {
@@ -775,40 +2704,33 @@ fn detect_perf_cores_count() -> usize {
     }
     #[cfg(not(target_os = "macos"))]
     {
-        0
+        let topology = topology::CpuTopology::detect();
+        if topology.is_hybrid() {
+            topology.perf_cpus.len()
+        } else {
+            0
+        }
     }
 }
 
 /// Load existing public keys from the output directory to avoid duplicates
-fn load_existing_keys(output_dir: &PathBuf) -> HashSet<String> {
-    let mut keys = HashSet::new();
-
+fn load_existing_keys(output_dir: &Path) -> HashSet<String> {
     // Recursively scan the provided directory for any files ending with `_public.txt`.
-    fn scan_dir(dir: &PathBuf, keys: &mut HashSet<String>) {
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    scan_dir(&path, keys);
-                    continue;
-                }
+    archive::scan_key_files(output_dir)
+        .iter()
+        .filter_map(|pair| pair.public_path.as_deref())
+        .filter_map(archive::read_public_key)
+        .collect()
+}
 
-                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    if name.ends_with("_public.txt") {
-                        if let Ok(content) = fs::read_to_string(&path) {
-                            let key = content.trim().to_lowercase();
-                            if key.len() == 64 && key.chars().all(|c| c.is_ascii_hexdigit()) {
-                                keys.insert(key);
-                            }
-                        }
-                    }
-                }
-            }
-        }
+/// Print a warning, or put it on the dashboard's notice line while the
+/// dashboard owns the terminal
+fn warn(notice: &mut Option<String>, on_dashboard: bool, message: String) {
+    if on_dashboard {
+        *notice = Some(message);
+    } else {
+        eprintln!("{} {}", style("⚠").yellow(), message);
     }
-
-    scan_dir(output_dir, &mut keys);
-    keys
 }
 
 fn save_key(
@@ -816,7 +2738,7 @@ fn save_key(
     output_dir: &Path,
     index: usize,
     filename_prefix: Option<&str>,
-) -> Option<(String, String)> {
+) -> Result<(String, String), String> {
     // If a user-supplied prefix is provided, prefer it as the filename prefix (uppercased).
     // Otherwise fall back to the first 8 hex chars of the public key.
     let pattern_id = if let Some(p) = filename_prefix {
@@ -833,17 +2755,12 @@ fn save_key(
     let pub_path = output_dir.join(&pub_filename);
     let priv_path = output_dir.join(&priv_filename);
 
-    if let Err(e) = fs::write(&pub_path, &key.public_hex) {
-        eprintln!("Failed to write public key: {}", e);
-        return None;
-    }
-
-    if let Err(e) = fs::write(&priv_path, &key.private_hex) {
-        eprintln!("Failed to write private key: {}", e);
-        return None;
-    }
+    fs::write(&pub_path, &key.public_hex)
+        .map_err(|e| format!("Failed to write public key: {}", e))?;
+    fs::write(&priv_path, &key.private_hex)
+        .map_err(|e| format!("Failed to write private key: {}", e))?;
 
-    Some((pub_filename, priv_filename))
+    Ok((pub_filename, priv_filename))
 }
 
 #[cfg(test)]
@@ -895,6 +2812,32 @@ mod main_filename_tests {
             "load_existing_keys did not find the key in subdir"
         );
     }
+
+    #[test]
+    fn test_write_private_refuses_to_overwrite() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("split_secret.json");
+
+        write_private(&path, "first", false).unwrap();
+        let err = write_private(&path, "second", false).unwrap_err();
+        assert!(err.contains("--force"), "unexpected error: {}", err);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        }
+        write_private(&path, "second", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -999,6 +2942,85 @@ mod cpu_detection_tests {
             "When both flags set, brutal should take precedence"
         );
     }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_workers_for_topology() {
+        let plain = |cpus: usize, quota: Option<f64>| topology::CpuTopology {
+            cpus: (0..cpus).collect(),
+            physical_cores: cpus,
+            perf_cpus: Vec::new(),
+            efficiency_cpus: Vec::new(),
+            quota,
+        };
+
+        // A single CPU (or a quota of one) gets one worker in every mode
+        for t in [plain(1, None), plain(16, Some(0.5))] {
+            assert_eq!(workers_for_topology(&t, false, false), 1);
+            assert_eq!(workers_for_topology(&t, true, false), 1);
+            assert_eq!(workers_for_topology(&t, false, true), 1);
+        }
+
+        assert_eq!(workers_for_topology(&plain(8, None), false, false), 6);
+        assert_eq!(workers_for_topology(&plain(8, None), true, false), 7);
+        assert_eq!(workers_for_topology(&plain(8, Some(2.5)), true, false), 2);
+
+        let hybrid = topology::CpuTopology {
+            perf_cpus: (0..8).collect(),
+            efficiency_cpus: (8..24).collect(),
+            ..plain(24, None)
+        };
+        assert_eq!(workers_for_topology(&hybrid, false, true), 16);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_placement_cpus() {
+        let hybrid = topology::CpuTopology {
+            cpus: (0..12).collect(),
+            physical_cores: 12,
+            perf_cpus: (0..4).collect(),
+            efficiency_cpus: (4..12).collect(),
+            quota: None,
+        };
+
+        assert_eq!(
+            placement_cpus(&hybrid, Some("0-1,5"), None, true),
+            Ok(vec![0, 1, 5])
+        );
+        assert!(placement_cpus(&hybrid, Some("10-13"), None, false)
+            .unwrap_err()
+            .contains("CPU 12"));
+        assert!(placement_cpus(&hybrid, Some("x"), None, false).is_err());
+        assert_eq!(
+            placement_cpus(&hybrid, None, Some(affinity::CoreType::Performance), false),
+            Ok(vec![0, 1, 2, 3])
+        );
+        // --powersave on a hybrid part keeps workers on the efficiency cores
+        assert_eq!(
+            placement_cpus(&hybrid, None, None, true),
+            Ok((4..12).collect())
+        );
+        assert_eq!(placement_cpus(&hybrid, None, None, false), Ok(Vec::new()));
+
+        let plain = topology::CpuTopology {
+            perf_cpus: Vec::new(),
+            efficiency_cpus: Vec::new(),
+            ..hybrid
+        };
+        assert_eq!(placement_cpus(&plain, None, None, true), Ok(Vec::new()));
+        assert!(placement_cpus(&plain, None, Some(affinity::CoreType::Efficiency), false).is_err());
+    }
+}
+
+/// Format seconds as H:MM:SS
+fn format_hms(seconds: u64) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
 }
 
 fn format_number(n: u64) -> String {
@@ -1091,6 +3113,10 @@ fn run_tests() {
         mode: PatternMode::Prefix,
         prefix: Some("AB".to_string()),
         vanity_length: 8,
+        symmetries: crate::symmetry::default_symmetries(),
+        dictionary: None,
+        expression: None,
+        regex: None,
     };
     let test_hex = "AB1234567890ABCDEF1234567890ABCDEF1234567890ABCDEF12345678";
     assert!(pattern::matches_pattern(test_hex, &config));
@@ -1106,6 +3132,10 @@ fn run_tests() {
         mode: PatternMode::Vanity,
         prefix: None,
         vanity_length: 4,
+        symmetries: crate::symmetry::default_symmetries(),
+        dictionary: None,
+        expression: None,
+        regex: None,
     };
     // First 4 == Last 4
     let test_hex = "ABCD1234567890ABCDEF1234567890ABCDEF1234567890ABCDEF12ABCD";
@@ -0,0 +1,277 @@
+//! Per-Run Manifest
+//!
+//! Every run that saves keys writes a manifest recording what produced them:
+//! pattern, CLI arguments, crate version, host and CPU/GPU details, timing,
+//! throughput and a SHA-256 checksum of every written file. `verify-manifest`
+//! re-hashes the files to detect tampering or missing keys.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::gpu_detect;
+
+/// Manifest name inside a timestamped run directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Host details captured at the start of a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub hostname: Option<String>,
+    pub os: Option<String>,
+    pub arch: String,
+    pub cpu_brand: Option<String>,
+    pub logical_cores: usize,
+    pub gpu_backend: String,
+    pub gpu_backends_available: Vec<String>,
+}
+
+impl HostInfo {
+    /// Collect host details via `sysinfo` and `gpu_detect`
+    pub fn collect() -> Self {
+        use sysinfo::{CpuRefreshKind, RefreshKind, System};
+
+        let sys =
+            System::new_with_specifics(RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing()));
+        let cpu_brand = sys
+            .cpus()
+            .first()
+            .map(|c| c.brand().trim().to_string())
+            .filter(|b| !b.is_empty());
+
+        Self {
+            hostname: System::host_name(),
+            os: System::long_os_version(),
+            arch: System::cpu_arch(),
+            cpu_brand,
+            logical_cores: num_cpus::get(),
+            gpu_backend: gpu_detect::get_best_backend().to_string(),
+            gpu_backends_available: gpu_detect::get_available_backends()
+                .iter()
+                .map(|b| b.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// SHA-256 checksum of one written file, relative to the run directory
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileChecksum {
+    pub file: String,
+    pub sha256: String,
+}
+
+/// Everything needed to reproduce and verify a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub version: String,
+    pub args: Vec<String>,
+    pub pattern: serde_json::Value,
+    pub pattern_description: String,
+    pub workers: usize,
+    pub verify: bool,
+    pub host: HostInfo,
+    pub start_time: String,
+    pub end_time: String,
+    pub total_time_seconds: f64,
+    pub total_attempts: u64,
+    pub average_rate: f64,
+    pub keys_found: usize,
+    pub files: Vec<FileChecksum>,
+}
+
+/// SHA-256 of a file as lowercase hex
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let data = fs::read(path)?;
+    Ok(hex::encode(Sha256::digest(&data)))
+}
+
+/// Hash the given files (names relative to `dir`)
+pub fn checksum_files<'a>(
+    dir: &Path,
+    files: impl IntoIterator<Item = &'a str>,
+) -> std::io::Result<Vec<FileChecksum>> {
+    files
+        .into_iter()
+        .map(|file| {
+            Ok(FileChecksum {
+                file: file.to_string(),
+                sha256: sha256_file(&dir.join(file))?,
+            })
+        })
+        .collect()
+}
+
+/// Manifest path for a run: `manifest.json` in a fresh timestamped directory,
+/// `manifest_<run_id>.json` in a shared output directory so earlier runs keep theirs
+pub fn manifest_path(dir: &Path, run_id: Option<&str>) -> PathBuf {
+    match run_id {
+        Some(id) => dir.join(format!("manifest_{}.json", id)),
+        None => dir.join(MANIFEST_FILE),
+    }
+}
+
+/// Write the manifest as pretty JSON
+pub fn write_manifest(path: &Path, manifest: &RunManifest) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// State of one file listed in a manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    Ok,
+    Modified,
+    Missing,
+}
+
+/// Verification result for one manifest file entry
+#[derive(Debug, Clone, Serialize)]
+pub struct FileVerification {
+    pub file: String,
+    pub status: FileStatus,
+}
+
+/// Verification result for a whole manifest
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestVerification {
+    pub manifest: String,
+    pub files: Vec<FileVerification>,
+}
+
+impl ManifestVerification {
+    pub fn is_intact(&self) -> bool {
+        self.files.iter().all(|f| f.status == FileStatus::Ok)
+    }
+}
+
+/// Re-hash every file listed in a manifest
+pub fn verify_manifest(path: &Path) -> Result<ManifestVerification, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let manifest: RunManifest = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid manifest {}: {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    let files = manifest
+        .files
+        .iter()
+        .map(|entry| {
+            let status = match sha256_file(&dir.join(&entry.file)) {
+                Ok(hash) if hash == entry.sha256 => FileStatus::Ok,
+                Ok(_) => FileStatus::Modified,
+                Err(_) => FileStatus::Missing,
+            };
+            FileVerification {
+                file: entry.file.clone(),
+                status,
+            }
+        })
+        .collect();
+
+    Ok(ManifestVerification {
+        manifest: path.display().to_string(),
+        files,
+    })
+}
+
+/// Manifests to verify for a path: the file itself, or every manifest in a directory
+pub fn find_manifests(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+    let mut found: Vec<PathBuf> = fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with("manifest") && n.ends_with(".json"))
+                .unwrap_or(false)
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample_manifest(files: Vec<FileChecksum>) -> RunManifest {
+        RunManifest {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            args: vec!["meshcore-keygen".to_string()],
+            pattern: serde_json::json!({"mode": "Pattern"}),
+            pattern_description: "test".to_string(),
+            workers: 1,
+            verify: true,
+            host: HostInfo::collect(),
+            start_time: "2026-01-01T00:00:00+00:00".to_string(),
+            end_time: "2026-01-01T00:00:01+00:00".to_string(),
+            total_time_seconds: 1.0,
+            total_attempts: 10,
+            average_rate: 10.0,
+            keys_found: 1,
+            files,
+        }
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("f.txt");
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_manifest_roundtrip_and_tamper_detection() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("A_1_x_public.txt"), "pub").unwrap();
+        fs::write(dir.path().join("A_1_x_private.txt"), "priv").unwrap();
+        fs::write(dir.path().join("B_2_x_public.txt"), "pub2").unwrap();
+
+        let files = checksum_files(
+            dir.path(),
+            ["A_1_x_public.txt", "A_1_x_private.txt", "B_2_x_public.txt"],
+        )
+        .unwrap();
+        let path = manifest_path(dir.path(), None);
+        write_manifest(&path, &sample_manifest(files)).unwrap();
+
+        let result = verify_manifest(&path).unwrap();
+        assert!(result.is_intact());
+
+        fs::write(dir.path().join("A_1_x_private.txt"), "swapped").unwrap();
+        fs::remove_file(dir.path().join("B_2_x_public.txt")).unwrap();
+        let result = verify_manifest(&path).unwrap();
+        assert!(!result.is_intact());
+        let status = |name: &str| result.files.iter().find(|f| f.file == name).unwrap().status;
+        assert_eq!(status("A_1_x_public.txt"), FileStatus::Ok);
+        assert_eq!(status("A_1_x_private.txt"), FileStatus::Modified);
+        assert_eq!(status("B_2_x_public.txt"), FileStatus::Missing);
+    }
+
+    #[test]
+    fn test_find_manifests_in_shared_dir() {
+        let dir = tempdir().unwrap();
+        let first = manifest_path(dir.path(), Some("20260101_000000"));
+        let second = manifest_path(dir.path(), Some("20260102_000000"));
+        write_manifest(&first, &sample_manifest(vec![])).unwrap();
+        write_manifest(&second, &sample_manifest(vec![])).unwrap();
+        fs::write(dir.path().join("other.json"), "{}").unwrap();
+
+        assert_eq!(find_manifests(dir.path()), vec![first.clone(), second]);
+        assert_eq!(find_manifests(&first), vec![first]);
+    }
+}
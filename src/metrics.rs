@@ -0,0 +1,474 @@
+//! Prometheus/OpenMetrics Exporter
+//!
+//! Optional local HTTP endpoint (`--metrics-addr`) serving the statistics the
+//! main loop already computes for the progress display: attempts per worker,
+//! keys found, rejected and skipped, live rates and the estimated time left.
+//!
+//! With `--metrics-control` the same server also accepts POST requests that
+//! pause, resume or resize the worker pool; they are queued as `PoolCommand`s
+//! and applied by the main loop on its next refresh.
+
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::Sender;
+
+use crate::worker::{max_workers, PoolCommand};
+
+/// Metric name prefix
+const PREFIX: &str = "meshcore_keygen";
+
+/// Rates and per-worker totals sampled by the main loop on every refresh
+#[derive(Debug, Clone, Default)]
+pub struct LiveStats {
+    pub total_attempts: u64,
+    pub worker_attempts: Vec<u64>,
+    pub worker_rates: Vec<f64>,
+    pub gpu_rate: f64,
+    pub total_rate: f64,
+    /// `f64::INFINITY` when no estimate is possible yet
+    pub eta_seconds: f64,
+    /// (agent name, rate) for connected distributed agents
+    pub agent_rates: Vec<(String, f64)>,
+    /// Running CPU workers
+    pub workers: usize,
+    pub paused: bool,
+    /// CPU cap in percent (100 = uncapped)
+    pub cpu_percent: u8,
+}
+
+/// Counters updated by the main loop and read by the HTTP server
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub keys_found: AtomicU64,
+    pub keys_rejected: AtomicU64,
+    pub keys_blocked: AtomicU64,
+    pub duplicates_skipped: AtomicU64,
+    target_keys: u64,
+    live: Mutex<LiveStats>,
+}
+
+impl Metrics {
+    pub fn new(target_keys: usize) -> Self {
+        Self {
+            target_keys: target_keys as u64,
+            ..Default::default()
+        }
+    }
+
+    /// Replace the sampled statistics
+    pub fn update(&self, stats: LiveStats) {
+        *self.live.lock().unwrap() = stats;
+    }
+
+    /// Render in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let live = self.live.lock().unwrap().clone();
+        let mut out = String::new();
+
+        let mut family = |name: &str, kind: &str, help: &str, samples: &[(String, f64)]| {
+            let _ = writeln!(out, "# HELP {}_{} {}", PREFIX, name, help);
+            let _ = writeln!(out, "# TYPE {}_{} {}", PREFIX, name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(
+                    out,
+                    "{}_{}{} {}",
+                    PREFIX,
+                    name,
+                    labels,
+                    format_value(*value)
+                );
+            }
+        };
+        let single = |value: f64| vec![(String::new(), value)];
+        let per_worker = |values: &[f64]| {
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (format!("{{worker=\"{}\"}}", i), *v))
+                .collect::<Vec<_>>()
+        };
+
+        family(
+            "attempts_total",
+            "counter",
+            "Keys generated and checked",
+            &single(live.total_attempts as f64),
+        );
+        family(
+            "worker_attempts_total",
+            "counter",
+            "Keys generated and checked per CPU worker",
+            &per_worker(
+                &live
+                    .worker_attempts
+                    .iter()
+                    .map(|&a| a as f64)
+                    .collect::<Vec<_>>(),
+            ),
+        );
+        family(
+            "keys_found_total",
+            "counter",
+            "Matching keys accepted",
+            &single(self.keys_found.load(Ordering::Relaxed) as f64),
+        );
+        family(
+            "keys_rejected_total",
+            "counter",
+            "Matching keys rejected by MeshCore validation",
+            &single(self.keys_rejected.load(Ordering::Relaxed) as f64),
+        );
+        family(
+            "keys_blocked_total",
+            "counter",
+            "Matching keys rejected by the blocklist",
+            &single(self.keys_blocked.load(Ordering::Relaxed) as f64),
+        );
+        family(
+            "duplicates_skipped_total",
+            "counter",
+            "Matching keys skipped because they already exist",
+            &single(self.duplicates_skipped.load(Ordering::Relaxed) as f64),
+        );
+        family(
+            "target_keys",
+            "gauge",
+            "Number of keys requested",
+            &single(self.target_keys as f64),
+        );
+        family(
+            "rate",
+            "gauge",
+            "Current total rate in keys per second",
+            &single(live.total_rate),
+        );
+        family(
+            "worker_rate",
+            "gauge",
+            "Current rate per CPU worker in keys per second",
+            &per_worker(&live.worker_rates),
+        );
+        family(
+            "gpu_rate",
+            "gauge",
+            "Current GPU rate in keys per second",
+            &single(live.gpu_rate),
+        );
+        family(
+            "agent_rate",
+            "gauge",
+            "Current rate per distributed agent in keys per second",
+            &live
+                .agent_rates
+                .iter()
+                .map(|(name, rate)| (format!("{{agent=\"{}\"}}", escape_label(name)), *rate))
+                .collect::<Vec<_>>(),
+        );
+        family(
+            "workers",
+            "gauge",
+            "Running CPU workers",
+            &single(live.workers as f64),
+        );
+        family(
+            "paused",
+            "gauge",
+            "1 while CPU workers are paused",
+            &single(if live.paused { 1.0 } else { 0.0 }),
+        );
+        family(
+            "cpu_percent_limit",
+            "gauge",
+            "CPU cap in percent of the whole machine (100 = uncapped)",
+            &single(live.cpu_percent as f64),
+        );
+        family(
+            "eta_seconds",
+            "gauge",
+            "Estimated seconds until the target is reached",
+            &single(live.eta_seconds),
+        );
+        out
+    }
+
+    /// Short JSON summary for `GET /status`
+    fn status_json(&self) -> String {
+        let live = self.live.lock().unwrap();
+        let mut status = serde_json::json!({
+            "workers": live.workers,
+            "paused": live.paused,
+            "cpu_percent": live.cpu_percent,
+            "attempts": live.total_attempts,
+            "rate": live.total_rate,
+            "keys_found": self.keys_found.load(Ordering::Relaxed),
+            "target_keys": self.target_keys,
+        });
+        if live.eta_seconds.is_finite() {
+            status["eta_seconds"] = live.eta_seconds.into();
+        }
+        let mut body = status.to_string();
+        body.push('\n');
+        body
+    }
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else if value.is_nan() {
+        "NaN".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Translate a control request path into a pool command
+///
+/// `/pause`, `/resume`, `/workers/add`, `/workers/remove`,
+/// `/workers?count=N` (at most `max_workers`) and `/cpu-percent?value=N`.
+fn parse_control(path: &str) -> Option<PoolCommand> {
+    let (route, query) = path.split_once('?').unwrap_or((path, ""));
+    match route {
+        "/pause" => Some(PoolCommand::Pause),
+        "/resume" => Some(PoolCommand::Resume),
+        "/workers/add" => Some(PoolCommand::AddWorker),
+        "/workers/remove" => Some(PoolCommand::RemoveWorker),
+        "/workers" => query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("count="))
+            .and_then(|count| count.parse().ok())
+            .filter(|&count| count > 0)
+            .map(|count: usize| PoolCommand::SetWorkers(count.min(max_workers()))),
+        "/cpu-percent" => query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("value="))
+            .and_then(|value| value.parse().ok())
+            .filter(|value| (1..=100).contains(value))
+            .map(PoolCommand::SetCpuPercent),
+        _ => None,
+    }
+}
+
+/// Serve `/metrics` (and `/status`) on `addr` until `should_stop` is set
+///
+/// Control requests are only accepted when `control` is set. Returns the
+/// bound address (useful with port 0).
+pub fn serve(
+    addr: &str,
+    metrics: Arc<Metrics>,
+    control: Option<Sender<PoolCommand>>,
+    should_stop: Arc<AtomicBool>,
+) -> Result<SocketAddr, String> {
+    let listener = TcpListener::bind(addr)
+        .map_err(|e| format!("Failed to bind metrics server on {}: {}", addr, e))?;
+    let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure metrics server: {}", e))?;
+
+    thread::Builder::new()
+        .name("metrics-server".to_string())
+        .spawn(move || {
+            while !should_stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => handle_request(stream, &metrics, control.as_ref()),
+                    Err(_) => thread::sleep(Duration::from_millis(100)),
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn metrics server: {}", e))?;
+
+    Ok(local_addr)
+}
+
+/// Read the request head (up to the blank line) and answer it
+fn handle_request(mut stream: TcpStream, metrics: &Metrics, control: Option<&Sender<PoolCommand>>) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < 16 * 1024 {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => head.extend_from_slice(&buf[..n]),
+        }
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("");
+    let path = request_line.next().unwrap_or("");
+
+    let (status, content_type, body) = match (method, path.split('?').next().unwrap_or("")) {
+        ("GET", "/metrics") => (
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            metrics.render(),
+        ),
+        ("GET", "/status") => ("200 OK", "application/json", metrics.status_json()),
+        ("GET", _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        ("POST", _) => match (control, parse_control(path)) {
+            (None, _) => (
+                "403 Forbidden",
+                "text/plain",
+                "control disabled (start with --metrics-control)\n".to_string(),
+            ),
+            (Some(_), None) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+            (Some(sender), Some(command)) => match sender.send(command) {
+                Ok(()) => ("202 Accepted", "text/plain", "accepted\n".to_string()),
+                Err(_) => (
+                    "503 Service Unavailable",
+                    "text/plain",
+                    "search is shutting down\n".to_string(),
+                ),
+            },
+        },
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_metrics() -> Metrics {
+        let metrics = Metrics::new(3);
+        metrics.keys_found.store(1, Ordering::Relaxed);
+        metrics.keys_rejected.store(2, Ordering::Relaxed);
+        metrics.duplicates_skipped.store(4, Ordering::Relaxed);
+        metrics.keys_blocked.store(3, Ordering::Relaxed);
+        metrics.update(LiveStats {
+            total_attempts: 3000,
+            worker_attempts: vec![1000, 2000],
+            worker_rates: vec![10.0, 20.5],
+            gpu_rate: 0.0,
+            total_rate: 30.5,
+            eta_seconds: f64::INFINITY,
+            agent_rates: vec![("lab \"b\"".to_string(), 5.0)],
+            workers: 2,
+            paused: true,
+            cpu_percent: 30,
+        });
+        metrics
+    }
+
+    #[test]
+    fn test_render_exposition_format() {
+        let text = sample_metrics().render();
+        assert!(text.contains("# TYPE meshcore_keygen_attempts_total counter\n"));
+        assert!(text.contains("meshcore_keygen_attempts_total 3000\n"));
+        assert!(text.contains("meshcore_keygen_worker_attempts_total{worker=\"1\"} 2000\n"));
+        assert!(text.contains("meshcore_keygen_worker_rate{worker=\"1\"} 20.5\n"));
+        assert!(text.contains("meshcore_keygen_keys_rejected_total 2\n"));
+        assert!(text.contains("meshcore_keygen_duplicates_skipped_total 4\n"));
+        assert!(text.contains("meshcore_keygen_keys_blocked_total 3\n"));
+        assert!(text.contains("meshcore_keygen_agent_rate{agent=\"lab \\\"b\\\"\"} 5\n"));
+        assert!(text.contains("meshcore_keygen_eta_seconds +Inf\n"));
+        assert!(text.contains("meshcore_keygen_workers 2\n"));
+        assert!(text.contains("meshcore_keygen_paused 1\n"));
+        assert!(text.contains("meshcore_keygen_cpu_percent_limit 30\n"));
+    }
+
+    #[test]
+    fn test_http_endpoint() {
+        let stop = Arc::new(AtomicBool::new(false));
+        let addr = serve(
+            "127.0.0.1:0",
+            Arc::new(sample_metrics()),
+            None,
+            stop.clone(),
+        )
+        .unwrap();
+        let get = |path: &str| request(addr, "GET", path);
+
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("meshcore_keygen_keys_found_total 1"));
+        assert!(get("/other").starts_with("HTTP/1.1 404"));
+        assert!(get("/status").contains("\"workers\":2"));
+        assert!(request(addr, "POST", "/pause").starts_with("HTTP/1.1 403"));
+
+        stop.store(true, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn test_control_requests() {
+        assert_eq!(
+            parse_control("/workers?count=3"),
+            Some(PoolCommand::SetWorkers(3))
+        );
+        assert_eq!(parse_control("/workers?count=0"), None);
+        assert_eq!(
+            parse_control("/workers?count=1000000"),
+            Some(PoolCommand::SetWorkers(max_workers()))
+        );
+        assert_eq!(
+            parse_control("/workers/remove"),
+            Some(PoolCommand::RemoveWorker)
+        );
+        assert_eq!(
+            parse_control("/cpu-percent?value=30"),
+            Some(PoolCommand::SetCpuPercent(30))
+        );
+        assert_eq!(parse_control("/cpu-percent?value=150"), None);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let addr = serve(
+            "127.0.0.1:0",
+            Arc::new(sample_metrics()),
+            Some(tx),
+            stop.clone(),
+        )
+        .unwrap();
+
+        assert!(request(addr, "POST", "/pause").starts_with("HTTP/1.1 202"));
+        assert!(request(addr, "POST", "/workers?count=2").starts_with("HTTP/1.1 202"));
+        assert!(request(addr, "POST", "/reboot").starts_with("HTTP/1.1 404"));
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![PoolCommand::Pause, PoolCommand::SetWorkers(2)]
+        );
+
+        stop.store(true, Ordering::Relaxed);
+    }
+
+    fn request(addr: SocketAddr, method: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            method, path
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+}
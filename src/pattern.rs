@@ -2,32 +2,71 @@
 //!
 //! Supports various pattern modes:
 //! - Prefix: Keys starting with specific hex prefix
-//! - Vanity: First N chars match last N chars
+//! - Vanity: First N chars relate to another part of the key (see `symmetry`)
 //! - Pattern: Combined prefix and vanity matching
 //! - PrefixVanity: Prefix AND vanity constraints
+//! - Dictionary: Any word from a wordlist at the start or end
+//! - Regex: A regular expression over the 64 hex characters (see `hex_regex`)
+//! - Expression: AND/OR/NOT of prefix, suffix, infix, node-ID range and
+//!   vanity constraints (see `expr`)
+
+use serde::{Deserialize, Serialize};
+
+use crate::dictionary::{Anchor, Dictionary};
+use crate::expr::Expr;
+use crate::hex_regex::HexRegex;
+use crate::symmetry::{self, default_symmetries, Symmetry};
+
+/// Hex characters in a public key
+pub const KEY_NIBBLES: usize = 64;
+
+/// Hex character `i` of a public key, as a number
+#[inline(always)]
+pub fn key_nibble(public_bytes: &[u8; 32], i: usize) -> u8 {
+    let byte = public_bytes[i / 2];
+    if i.is_multiple_of(2) {
+        byte >> 4
+    } else {
+        byte & 0x0F
+    }
+}
 
 /// Pattern matching modes
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PatternMode {
     /// No pattern, accept any key
-    #[allow(dead_code)]
     Any,
     /// Key starts with specific hex prefix
     Prefix,
-    /// First N chars match last N chars (or palindromic)
+    /// First N chars match another part of the key, per `PatternConfig::symmetries`
     Vanity,
     /// Similar to Vanity but explicit pattern mode
     Pattern,
     /// Both prefix AND vanity must match
     PrefixVanity,
+    /// Any word of `PatternConfig::dictionary` at the start or end
+    Dictionary,
+    /// `PatternConfig::expression` holds
+    Expression,
+    /// `PatternConfig::regex` matches the key's hex
+    Regex,
 }
 
 /// Configuration for pattern matching
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PatternConfig {
     pub mode: PatternMode,
     pub prefix: Option<String>,
     pub vanity_length: u8,
+    /// Vanity rules, any of which may hold
+    #[serde(default = "default_symmetries")]
+    pub symmetries: Vec<Symmetry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dictionary: Option<Dictionary>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<Box<Expr>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<Box<HexRegex>>,
 }
 
 impl Default for PatternConfig {
@@ -36,6 +75,10 @@ impl Default for PatternConfig {
             mode: PatternMode::Pattern,
             prefix: None,
             vanity_length: 8,
+            symmetries: default_symmetries(),
+            dictionary: None,
+            expression: None,
+            regex: None,
         }
     }
 }
@@ -48,6 +91,10 @@ impl PatternConfig {
             mode: PatternMode::Prefix,
             prefix: Some(prefix.to_uppercase()),
             vanity_length: 8,
+            symmetries: default_symmetries(),
+            dictionary: None,
+            expression: None,
+            regex: None,
         }
     }
 
@@ -58,6 +105,10 @@ impl PatternConfig {
             mode: PatternMode::Vanity,
             prefix: None,
             vanity_length: length,
+            symmetries: default_symmetries(),
+            dictionary: None,
+            expression: None,
+            regex: None,
         }
     }
 
@@ -68,6 +119,49 @@ impl PatternConfig {
             mode: PatternMode::PrefixVanity,
             prefix: Some(prefix.to_uppercase()),
             vanity_length,
+            symmetries: default_symmetries(),
+            dictionary: None,
+            expression: None,
+            regex: None,
+        }
+    }
+
+    /// Create a new config matching any word of `dictionary`
+    pub fn with_dictionary(dictionary: Dictionary) -> Self {
+        Self {
+            mode: PatternMode::Dictionary,
+            prefix: None,
+            vanity_length: 0,
+            symmetries: default_symmetries(),
+            dictionary: Some(dictionary),
+            expression: None,
+            regex: None,
+        }
+    }
+
+    /// Create a new config matching `expression`
+    pub fn with_expression(expression: Expr) -> Self {
+        Self {
+            mode: PatternMode::Expression,
+            prefix: None,
+            vanity_length: 0,
+            symmetries: default_symmetries(),
+            dictionary: None,
+            expression: Some(Box::new(expression)),
+            regex: None,
+        }
+    }
+
+    /// Create a new config matching `regex`
+    pub fn with_regex(regex: HexRegex) -> Self {
+        Self {
+            mode: PatternMode::Regex,
+            prefix: None,
+            vanity_length: 0,
+            symmetries: default_symmetries(),
+            dictionary: None,
+            expression: None,
+            regex: Some(Box::new(regex)),
         }
     }
 
@@ -82,41 +176,136 @@ impl PatternConfig {
                 )
             }
             PatternMode::Vanity | PatternMode::Pattern => {
-                format!(
-                    "First {} chars == Last {} chars",
-                    self.vanity_length, self.vanity_length
-                )
+                if self.symmetries == default_symmetries() {
+                    format!(
+                        "First {} chars == Last {} chars (or reversed)",
+                        self.vanity_length, self.vanity_length
+                    )
+                } else {
+                    format!("Symmetry: {}", self.symmetry_description())
+                }
             }
             PatternMode::PrefixVanity => {
-                format!(
+                let mut description = format!(
                     "Prefix '{}' AND {}-char vanity",
                     self.prefix.as_ref().unwrap_or(&"?".to_string()),
                     self.vanity_length
-                )
+                );
+                if self.symmetries != default_symmetries() {
+                    description += &format!(" ({})", self.symmetry_description());
+                }
+                description
             }
+            PatternMode::Dictionary => match &self.dictionary {
+                Some(d) => format!(
+                    "Dictionary: {} word(s) at {}",
+                    d.words().len(),
+                    match d.anchor() {
+                        Anchor::Start => "start",
+                        Anchor::End => "end",
+                        Anchor::Both => "start or end",
+                    }
+                ),
+                None => "Dictionary: no words".to_string(),
+            },
+            PatternMode::Expression => match &self.expression {
+                Some(e) => format!("Expression: {}", e),
+                None => "Expression: none".to_string(),
+            },
+            PatternMode::Regex => match &self.regex {
+                Some(r) => format!("Regex: {}", r),
+                None => "Regex: none".to_string(),
+            },
         }
     }
 
+    /// Symmetry rules as text, e.g. "last 4 == first 4 or middle 4 == first 4"
+    fn symmetry_description(&self) -> String {
+        self.symmetries
+            .iter()
+            .map(|s| s.describe(self.vanity_length as usize))
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+
     /// Estimate the probability of finding a match
     #[allow(dead_code)]
     pub fn estimated_probability(&self) -> f64 {
         match &self.mode {
             PatternMode::Any => 1.0,
-            PatternMode::Prefix => {
-                let prefix_len = self.prefix.as_ref().map(|p| p.len()).unwrap_or(0);
-                1.0 / (16.0_f64.powi(prefix_len as i32))
-            }
+            PatternMode::Prefix => match self.prefix_nibbles() {
+                Some(prefix) => 1.0 / (16.0_f64.powi(prefix.len() as i32)),
+                None => 0.0,
+            },
             PatternMode::Vanity | PatternMode::Pattern => {
-                // First N chars matching last N chars
-                // Plus palindrome chance (roughly doubles the probability)
-                2.0 / (16.0_f64.powi(self.vanity_length as i32))
+                symmetry::probability(self.vanity_length as usize, &self.symmetries)
             }
             PatternMode::PrefixVanity => {
-                let prefix_len = self.prefix.as_ref().map(|p| p.len()).unwrap_or(0);
-                let prefix_prob = 1.0 / (16.0_f64.powi(prefix_len as i32));
-                let vanity_prob = 2.0 / (16.0_f64.powi(self.vanity_length as i32));
+                let Some(prefix) = self.prefix_nibbles() else {
+                    return 0.0;
+                };
+                // The vanity rule also covers the prefix characters
+                let prefix_prob = 1.0 / (16.0_f64.powi(prefix.len() as i32));
+                let vanity_prob = symmetry::probability_given(
+                    self.vanity_length as usize,
+                    &self.symmetries,
+                    &prefix,
+                );
                 prefix_prob * vanity_prob
             }
+            PatternMode::Dictionary => self.dictionary.as_ref().map_or(0.0, |d| d.probability()),
+            PatternMode::Expression => self.expression.as_ref().map_or(0.0, |e| e.probability()),
+            PatternMode::Regex => self.regex.as_ref().map_or(0.0, |r| r.probability()),
+        }
+    }
+}
+
+impl PatternConfig {
+    /// The prefix as nibbles; `None` if it has a non-hex character, which
+    /// no key can match
+    pub fn prefix_nibbles(&self) -> Option<Vec<u8>> {
+        self.prefix
+            .as_deref()
+            .unwrap_or("")
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8))
+            .collect()
+    }
+
+    /// Hex character ranges of a matching key that the pattern constrains,
+    /// for highlighting the matched nibbles in displays
+    #[allow(clippy::single_range_in_vec_init)]
+    pub fn highlight_ranges(&self, hex: &str) -> Vec<std::ops::Range<usize>> {
+        let len = hex.len();
+        let prefix_len = self.prefix.as_ref().map(|p| p.len()).unwrap_or(0).min(len);
+        let n = (self.vanity_length as usize).min(len / 2);
+        let mut vanity: Vec<std::ops::Range<usize>> = Vec::new();
+        for range in self.symmetries.iter().flat_map(|s| s.ranges(n)) {
+            if range.end <= len && !vanity.contains(&range) {
+                vanity.push(range);
+            }
+        }
+        match &self.mode {
+            PatternMode::Any => Vec::new(),
+            PatternMode::Prefix => vec![0..prefix_len],
+            PatternMode::Vanity | PatternMode::Pattern => vanity,
+            PatternMode::PrefixVanity => std::iter::once(0..prefix_len.max(n))
+                .chain(vanity.into_iter().filter(|r| r.start > 0))
+                .collect(),
+            PatternMode::Dictionary => self
+                .dictionary
+                .as_ref()
+                .and_then(|d| d.word_match(hex))
+                .map(|m| vec![m.offset..m.offset + m.hex.len()])
+                .unwrap_or_default(),
+            PatternMode::Expression => match (&self.expression, hex_to_bytes(hex)) {
+                (Some(e), Some(bytes)) => e.matched_ranges(&bytes),
+                _ => Vec::new(),
+            },
+            PatternMode::Regex => match (&self.regex, hex_to_bytes(hex)) {
+                (Some(r), Some(bytes)) => r.forced_ranges(&bytes),
+                _ => Vec::new(),
+            },
         }
     }
 }
@@ -138,17 +327,27 @@ pub fn matches_pattern(hex: &str, config: &PatternConfig) -> bool {
                 true
             }
         }
-        PatternMode::Vanity | PatternMode::Pattern => {
-            check_vanity_pattern(hex_bytes, config.vanity_length as usize)
-        }
+        PatternMode::Vanity | PatternMode::Pattern => check_vanity_pattern(hex_bytes, config),
         PatternMode::PrefixVanity => {
             if let Some(prefix) = &config.prefix {
                 if !hex_upper.starts_with(prefix) {
                     return false;
                 }
             }
-            check_vanity_pattern(hex_bytes, config.vanity_length as usize)
+            check_vanity_pattern(hex_bytes, config)
         }
+        PatternMode::Dictionary => config
+            .dictionary
+            .as_ref()
+            .is_some_and(|d| d.word_match(hex).is_some()),
+        PatternMode::Expression => match (&config.expression, hex_to_bytes(hex)) {
+            (Some(e), Some(bytes)) => e.matches_bytes(&bytes),
+            _ => false,
+        },
+        PatternMode::Regex => match (&config.regex, hex_to_bytes(hex)) {
+            (Some(r), Some(bytes)) => r.matches_bytes(&bytes),
+            _ => false,
+        },
     }
 }
 
@@ -167,107 +366,52 @@ pub fn matches_pattern_bytes(public_bytes: &[u8; 32], config: &PatternConfig) ->
                 true
             }
         }
-        PatternMode::Vanity | PatternMode::Pattern => {
-            check_vanity_pattern_bytes(public_bytes, config.vanity_length as usize)
-        }
+        PatternMode::Vanity | PatternMode::Pattern => symmetry::matches_bytes(
+            public_bytes,
+            config.vanity_length as usize,
+            &config.symmetries,
+        ),
         PatternMode::PrefixVanity => {
             if let Some(prefix) = &config.prefix {
                 if !matches_prefix_bytes(public_bytes, prefix) {
                     return false;
                 }
             }
-            check_vanity_pattern_bytes(public_bytes, config.vanity_length as usize)
+            symmetry::matches_bytes(
+                public_bytes,
+                config.vanity_length as usize,
+                &config.symmetries,
+            )
         }
+        PatternMode::Dictionary => config
+            .dictionary
+            .as_ref()
+            .is_some_and(|d| d.matches_bytes(public_bytes)),
+        PatternMode::Expression => config
+            .expression
+            .as_ref()
+            .is_some_and(|e| e.matches_bytes(public_bytes)),
+        PatternMode::Regex => config
+            .regex
+            .as_ref()
+            .is_some_and(|r| r.matches_bytes(public_bytes)),
     }
 }
 
-/// Check if first N hex chars equal last N hex chars (or are palindromic)
-#[inline(always)]
-fn check_vanity_pattern(hex_bytes: &[u8], n: usize) -> bool {
-    if hex_bytes.len() < n * 2 {
-        return false;
-    }
-
-    let first_n = &hex_bytes[..n];
-    let last_n = &hex_bytes[hex_bytes.len() - n..];
-
-    // Check if first N == last N
-    if first_n == last_n {
-        return true;
-    }
-
-    // Check if first N is palindrome of last N
-    first_n.iter().eq(last_n.iter().rev())
-}
-
-/// Check vanity pattern directly on bytes (faster than hex string)
-#[inline(always)]
-fn check_vanity_pattern_bytes(public_bytes: &[u8; 32], n_hex_chars: usize) -> bool {
-    // Each byte = 2 hex chars
-    // For n hex chars, we need n/2 bytes
-    let n_bytes = n_hex_chars.div_ceil(2);
-
-    match n_hex_chars {
-        2 => {
-            // Compare first byte with last byte
-            let first = public_bytes[0];
-            let last = public_bytes[31];
-            // First byte (2 hex chars) == Last byte (2 hex chars)
-            first == last ||
-            // Palindrome: first nibbles reversed
-            (first >> 4) == (last & 0x0F) && (first & 0x0F) == (last >> 4)
-        }
-        4 => {
-            // Compare first 2 bytes with last 2 bytes
-            let first = &public_bytes[..2];
-            let last = &public_bytes[30..32];
-            first == last ||
-            // Palindrome at nibble level
-            check_nibble_palindrome(first, last)
-        }
-        6 => {
-            // Compare first 3 bytes with last 3 bytes
-            let first = &public_bytes[..3];
-            let last = &public_bytes[29..32];
-            first == last || check_nibble_palindrome(first, last)
-        }
-        8 => {
-            // Compare first 4 bytes with last 4 bytes
-            let first = &public_bytes[..4];
-            let last = &public_bytes[28..32];
-            first == last || check_nibble_palindrome(first, last)
-        }
-        _ => {
-            // General case
-            let first = &public_bytes[..n_bytes];
-            let last = &public_bytes[32 - n_bytes..];
-            first == last || check_nibble_palindrome(first, last)
-        }
-    }
+/// Decode a 64-character public key hex string
+fn hex_to_bytes(hex: &str) -> Option<[u8; 32]> {
+    hex::decode(hex).ok()?.try_into().ok()
 }
 
-/// Check if two byte slices are palindromes at the nibble (hex char) level
+/// Check the vanity symmetries on an uppercase hex string
 #[inline(always)]
-fn check_nibble_palindrome(first: &[u8], last: &[u8]) -> bool {
-    if first.len() != last.len() {
-        return false;
-    }
-
-    // Convert to nibbles and check palindrome
-    let mut first_nibbles = Vec::with_capacity(first.len() * 2);
-    let mut last_nibbles = Vec::with_capacity(last.len() * 2);
-
-    for &b in first {
-        first_nibbles.push(b >> 4);
-        first_nibbles.push(b & 0x0F);
-    }
-
-    for &b in last {
-        last_nibbles.push(b >> 4);
-        last_nibbles.push(b & 0x0F);
-    }
-
-    first_nibbles.iter().eq(last_nibbles.iter().rev())
+fn check_vanity_pattern(hex_bytes: &[u8], config: &PatternConfig) -> bool {
+    symmetry::matches_chars(
+        |i| hex_bytes[i],
+        hex_bytes.len(),
+        config.vanity_length as usize,
+        &config.symmetries,
+    )
 }
 
 /// Check if public key bytes match a hex prefix
@@ -277,18 +421,11 @@ fn matches_prefix_bytes(public_bytes: &[u8; 32], prefix: &str) -> bool {
     let prefix_bytes = prefix_upper.as_bytes();
 
     for (i, &p) in prefix_bytes.iter().enumerate() {
-        let byte_idx = i / 2;
-        let is_high_nibble = i % 2 == 0;
-
-        if byte_idx >= 32 {
+        if i >= KEY_NIBBLES {
             return false;
         }
 
-        let nibble = if is_high_nibble {
-            public_bytes[byte_idx] >> 4
-        } else {
-            public_bytes[byte_idx] & 0x0F
-        };
+        let nibble = key_nibble(public_bytes, i);
 
         let expected = match p {
             b'0'..=b'9' => p - b'0',
@@ -305,6 +442,12 @@ fn matches_prefix_bytes(public_bytes: &[u8; 32], prefix: &str) -> bool {
     true
 }
 
+/// Public key bytes from 64 hex characters, for tests
+#[cfg(test)]
+pub fn key_bytes(hex: &str) -> [u8; 32] {
+    hex::decode(hex).unwrap().try_into().unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -383,6 +526,10 @@ mod tests {
             mode: PatternMode::Any,
             prefix: None,
             vanity_length: 8,
+            symmetries: default_symmetries(),
+            dictionary: None,
+            expression: None,
+            regex: None,
         };
 
         assert!(matches_pattern(
@@ -417,6 +564,101 @@ mod tests {
         assert!(matches_pattern_bytes(&bytes, &config));
     }
 
+    #[test]
+    fn test_highlight_ranges() {
+        let hex = "ab".repeat(32);
+        assert_eq!(
+            PatternConfig::with_prefix("ABC").highlight_ranges(&hex),
+            vec![0..3]
+        );
+        assert_eq!(
+            PatternConfig::with_vanity(4).highlight_ranges(&hex),
+            vec![0..4, 60..64]
+        );
+        assert_eq!(
+            PatternConfig::with_prefix_vanity("AB", 6).highlight_ranges(&hex),
+            vec![0..6, 58..64]
+        );
+    }
+
+    #[test]
+    fn test_dictionary_mode() {
+        use crate::dictionary::parse_wordlist;
+
+        let words = parse_wordlist("coffee\nbada55\n", true, 4);
+        let config = PatternConfig::with_dictionary(Dictionary::new(words, Anchor::Both));
+        let hex = format!("{}BADA55", "0".repeat(58));
+
+        let bytes = key_bytes(&hex);
+        assert!(matches_pattern_bytes(&bytes, &config));
+        assert!(matches_pattern(&hex.to_lowercase(), &config));
+        assert!(!matches_pattern(&"0".repeat(64), &config));
+        assert_eq!(config.highlight_ranges(&hex), vec![58..64]);
+        assert_eq!(
+            config.description(),
+            "Dictionary: 2 word(s) at start or end"
+        );
+    }
+
+    #[test]
+    fn test_selected_symmetries() {
+        let config = PatternConfig {
+            symmetries: vec![Symmetry::Equal],
+            ..PatternConfig::with_vanity(4)
+        };
+        // Reversed ends no longer count
+        let reversed = format!("ABCD{}DCBA", "0".repeat(56));
+        assert!(!matches_pattern(&reversed, &config));
+        assert!(matches_pattern(&reversed, &PatternConfig::with_vanity(4)));
+        assert_eq!(config.estimated_probability(), 1.0 / 65536.0);
+        assert_eq!(config.description(), "Symmetry: last 4 == first 4");
+
+        let middle = PatternConfig {
+            symmetries: vec![Symmetry::Middle, Symmetry::Equal],
+            ..PatternConfig::with_prefix_vanity("AB", 4)
+        };
+        let hex = format!("ABCD{}ABCD{}", "0".repeat(26), "0".repeat(30));
+        let bytes = key_bytes(&hex);
+        assert!(matches_pattern_bytes(&bytes, &middle));
+        assert_eq!(middle.highlight_ranges(&hex), vec![0..4, 30..34, 60..64]);
+        assert!(middle
+            .description()
+            .ends_with("(middle 4 == first 4 or last 4 == first 4)"));
+    }
+
+    #[test]
+    fn test_expression_mode() {
+        let expr = Expr::parse("node:10-3F & !suffix:00").unwrap();
+        let config = PatternConfig::with_expression(expr);
+        assert_eq!(config.description(), "Expression: node:10-3F & !suffix:00");
+
+        let hex = format!("2A{}BEEF", "1".repeat(58));
+        let bytes = key_bytes(&hex);
+        assert!(matches_pattern(&hex.to_lowercase(), &config));
+        assert!(matches_pattern_bytes(&bytes, &config));
+        assert!(!matches_pattern(&format!("{}00", &hex[..62]), &config));
+        assert_eq!(config.highlight_ranges(&hex), vec![0..2]);
+
+        // Round-trips to agents and split-key requests
+        let json = serde_json::to_string(&config).unwrap();
+        let decoded: PatternConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.expression, config.expression);
+    }
+
+    #[test]
+    fn test_regex_mode() {
+        let config = PatternConfig::with_regex(HexRegex::new("^(AB|CD)[0-9]{2}").unwrap());
+        assert_eq!(config.description(), "Regex: ^(AB|CD)[0-9]{2}");
+        let hex = format!("ab12{}", "e".repeat(60));
+        assert!(matches_pattern(&hex, &config));
+        assert!(!matches_pattern(
+            &format!("ab1e{}", "e".repeat(60)),
+            &config
+        ));
+        assert_eq!(config.highlight_ranges(&hex.to_uppercase()), vec![0..4]);
+        assert!((config.estimated_probability() - 2.0 / 256.0 * 100.0 / 256.0).abs() < 1e-12);
+    }
+
     #[test]
     fn test_description() {
         let config = PatternConfig::with_prefix("AB");
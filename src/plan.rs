@@ -0,0 +1,392 @@
+//! Search Planning
+//!
+//! The `plan` command answers "what can I find in 4 hours on this machine?".
+//! It runs the worker pool for a few seconds, as `--benchmark` does, then
+//! turns the measured rate into the chance of at least one match within the
+//! budget for a range of prefix lengths, vanity lengths and sets of
+//! alternative prefixes. It also gives how many keys of each could be
+//! stockpiled in that time. Keys found per pattern follow a Poisson
+//! distribution with mean `attempts × probability`.
+
+use clap::ValueEnum;
+use console::style;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::affinity::ThreadPolicy;
+use crate::explain::{format_count, format_seconds};
+use crate::pattern::PatternConfig;
+use crate::symmetry::{self, Symmetry};
+use crate::worker::WorkerPool;
+
+/// Longest prefix considered
+const MAX_PREFIX: usize = 16;
+
+/// Size of the alternative prefix sets considered
+const PREFIX_SET: usize = 4;
+
+/// Stockpile counts are met with at least this chance
+const STOCKPILE_CONFIDENCE: f64 = 0.9;
+
+/// Normal quantile for `STOCKPILE_CONFIDENCE`, for large means (the two
+/// must change together)
+const STOCKPILE_Z: f64 = 1.2816;
+
+/// Patterns easier than this many expected keys are left out
+const MAX_EXPECTED_KEYS: f64 = 10_000.0;
+
+/// Patterns with a smaller chance than this are left out
+const MIN_CHANCE: f64 = 0.01;
+
+/// Parse a time budget like `4h`, `90m`, `1h30m`, `2d` or plain seconds
+pub fn parse_budget(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let error = || format!("Invalid time budget '{}' (e.g. 4h, 90m, 1h30m)", value);
+    let seconds = match value.parse::<u64>() {
+        Ok(seconds) => seconds,
+        Err(_) => {
+            let mut total = 0u64;
+            let mut number = String::new();
+            for c in value.chars() {
+                if c.is_ascii_digit() {
+                    number.push(c);
+                    continue;
+                }
+                let unit = match c {
+                    'd' => 86_400,
+                    'h' => 3600,
+                    'm' => 60,
+                    's' => 1,
+                    _ => return Err(error()),
+                };
+                let n: u64 = number.parse().map_err(|_| error())?;
+                total = n
+                    .checked_mul(unit)
+                    .and_then(|seconds| total.checked_add(seconds))
+                    .ok_or_else(|| format!("Time budget '{}' is too long", value))?;
+                number.clear();
+            }
+            if !number.is_empty() {
+                return Err(error());
+            }
+            total
+        }
+    };
+    if seconds == 0 {
+        return Err(error());
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+/// One pattern and what the budget buys
+#[derive(Debug, Serialize)]
+pub struct Candidate {
+    /// `prefix`, `vanity`, `prefix_set` or `pattern` (the given flags)
+    pub family: &'static str,
+    pub label: String,
+    /// Hex characters fixed by the pattern (0 for the given pattern)
+    pub length: usize,
+    pub probability: f64,
+    /// Chance of at least one match within the budget
+    pub chance: f64,
+    pub expected_keys: f64,
+    /// Keys found with at least `STOCKPILE_CONFIDENCE` chance
+    pub stockpile: u64,
+}
+
+impl Candidate {
+    fn new(family: &'static str, label: String, length: usize, p: f64, attempts: f64) -> Self {
+        let expected_keys = attempts * p;
+        Self {
+            family,
+            label,
+            length,
+            probability: p,
+            chance: chance_within(p, attempts),
+            expected_keys,
+            stockpile: stockpile(expected_keys),
+        }
+    }
+
+    /// Confidence level in words
+    pub fn confidence(&self) -> &'static str {
+        match self.chance {
+            c if c >= 0.99 => "very likely",
+            c if c >= 0.9 => "likely",
+            c if c >= 0.5 => "even odds",
+            _ => "unlikely",
+        }
+    }
+}
+
+/// A plan for a time budget
+#[derive(Debug, Serialize)]
+pub struct Plan {
+    pub budget_seconds: u64,
+    pub workers: usize,
+    /// Measured keys per second
+    pub rate: f64,
+    pub attempts: f64,
+    pub candidates: Vec<Candidate>,
+    /// The hardest pattern of each family that is at least likely to finish
+    pub recommended: Vec<String>,
+}
+
+/// Chance of at least one match in `attempts` keys
+pub fn chance_within(probability: f64, attempts: f64) -> f64 {
+    if probability >= 1.0 {
+        return 1.0;
+    }
+    -(attempts * (-probability).ln_1p()).exp_m1()
+}
+
+/// Largest key count reached with at least `STOCKPILE_CONFIDENCE` chance
+/// when keys arrive as a Poisson process with mean `mean`
+pub fn stockpile(mean: f64) -> u64 {
+    // exp(-mean) underflows past about 745, and the normal approximation
+    // is close well before that
+    if mean > 500.0 {
+        return (mean - STOCKPILE_Z * mean.sqrt()).floor().max(0.0) as u64;
+    }
+    // P(X >= k) = 1 - P(X <= k - 1)
+    let mut k = 0u64;
+    let mut term = (-mean).exp();
+    let mut cdf = term;
+    while cdf <= 1.0 - STOCKPILE_CONFIDENCE {
+        k += 1;
+        term *= mean / k as f64;
+        cdf += term;
+    }
+    k
+}
+
+/// Keys per second of a `workers`-thread pool over `duration`, measured the
+/// way `--benchmark` runs: the real worker loop, nothing saved
+pub fn benchmark(workers: usize, policy: ThreadPolicy, duration: Duration) -> Result<f64, String> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let attempts = Arc::new(AtomicU64::new(0));
+    let mut pool = WorkerPool::new(
+        workers,
+        PatternConfig::default(),
+        tx,
+        attempts.clone(),
+        Arc::new(AtomicBool::new(false)),
+    );
+    pool.set_thread_policy(policy);
+    pool.start()?;
+
+    // Leave out thread start-up and the first partial batches
+    thread::sleep((duration / 5).min(Duration::from_secs(1)));
+    let (start, start_attempts) = (Instant::now(), attempts.load(Ordering::Relaxed));
+    thread::sleep(duration);
+    let rate =
+        (attempts.load(Ordering::Relaxed) - start_attempts) as f64 / start.elapsed().as_secs_f64();
+    pool.stop();
+    drop(rx);
+    Ok(rate)
+}
+
+/// Build the plan for `budget` at `rate` keys per second
+pub fn plan(
+    budget: Duration,
+    workers: usize,
+    rate: f64,
+    symmetries: &[Symmetry],
+    pattern: Option<&PatternConfig>,
+) -> Plan {
+    let attempts = rate * budget.as_secs_f64();
+    let modes = symmetries
+        .iter()
+        .filter_map(|s| s.to_possible_value())
+        .map(|v| v.get_name().to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let mut all = Vec::new();
+    for length in 1..=MAX_PREFIX {
+        let p = 16f64.powi(-(length as i32));
+        all.push(Candidate::new(
+            "prefix",
+            format!("prefix of {} chars", length),
+            length,
+            p,
+            attempts,
+        ));
+        all.push(Candidate::new(
+            "prefix_set",
+            format!("any of {} prefixes of {} chars", PREFIX_SET, length),
+            length,
+            (PREFIX_SET as f64 * p).min(1.0),
+            attempts,
+        ));
+    }
+    for length in 2..=8 {
+        all.push(Candidate::new(
+            "vanity",
+            format!("vanity {} ({})", length, modes),
+            length,
+            symmetry::probability(length, symmetries),
+            attempts,
+        ));
+    }
+
+    let mut recommended = Vec::new();
+    for family in ["prefix", "vanity", "prefix_set"] {
+        if let Some(best) = all
+            .iter()
+            .filter(|c| c.family == family && c.chance >= 0.9)
+            .max_by_key(|c| c.length)
+        {
+            recommended.push(best.label.clone());
+        }
+    }
+
+    let mut candidates: Vec<Candidate> = all
+        .into_iter()
+        .filter(|c| c.chance >= MIN_CHANCE && c.expected_keys <= MAX_EXPECTED_KEYS)
+        .collect();
+    candidates.sort_by(|a, b| a.family.cmp(b.family).then(a.length.cmp(&b.length)));
+    if let Some(config) = pattern {
+        candidates.insert(
+            0,
+            Candidate::new(
+                "pattern",
+                config.description(),
+                0,
+                config.estimated_probability(),
+                attempts,
+            ),
+        );
+    }
+
+    Plan {
+        budget_seconds: budget.as_secs(),
+        workers,
+        rate,
+        attempts,
+        candidates,
+        recommended,
+    }
+}
+
+/// Print a plan for humans
+pub fn print_plan(plan: &Plan) {
+    println!(
+        "{} {} keys/s with {} workers: {} attempts in {}",
+        style("ℹ").cyan(),
+        crate::format_compact_f64(plan.rate),
+        plan.workers,
+        format_count(plan.attempts),
+        format_seconds(plan.budget_seconds as f64)
+    );
+    if plan.candidates.is_empty() {
+        println!(
+            "{} Nothing worth planning fits this budget",
+            style("⚠").yellow()
+        );
+        return;
+    }
+
+    println!();
+    println!(
+        "  {:<36} {:>14} {:>7}  {:<12} {:>9} {:>9}",
+        "Pattern", "Odds", "Chance", "", "Expected", "90% sure"
+    );
+    for c in &plan.candidates {
+        let confidence = match c.confidence() {
+            "very likely" | "likely" => style(c.confidence()).green(),
+            "even odds" => style(c.confidence()).yellow(),
+            other => style(other).red(),
+        };
+        println!(
+            "  {:<36} {:>14} {:>6.1}%  {:<12} {:>9} {:>9}",
+            c.label,
+            if c.probability > 0.0 {
+                format!("1 in {}", format_count(1.0 / c.probability))
+            } else {
+                "never".to_string()
+            },
+            c.chance * 100.0,
+            confidence,
+            format_count(c.expected_keys),
+            c.stockpile
+        );
+    }
+    println!();
+
+    if plan.recommended.is_empty() {
+        println!(
+            "{} No built-in pattern is likely to finish; try a longer budget",
+            style("⚠").yellow()
+        );
+    } else {
+        println!(
+            "{} Likely to finish: {}",
+            style("✓").green(),
+            plan.recommended.join("; ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_budget() {
+        assert_eq!(parse_budget("4h").unwrap(), Duration::from_secs(4 * 3600));
+        assert_eq!(parse_budget("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_budget("2d").unwrap(), Duration::from_secs(172_800));
+        assert_eq!(parse_budget("90").unwrap(), Duration::from_secs(90));
+        for bad in [
+            "",
+            "0",
+            "4x",
+            "h",
+            "30m5",
+            "0h",
+            "999999999999999999d",
+            "18446744073709551615s1s",
+        ] {
+            assert!(parse_budget(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_chances_and_stockpile() {
+        // One expected match: 1 - 1/e
+        let p = 1.0 / 65536.0;
+        assert!((chance_within(p, 65536.0) - 0.632).abs() < 0.001);
+        assert_eq!(chance_within(1.0, 1.0), 1.0);
+
+        assert_eq!(stockpile(0.5), 0);
+        // P(X >= 1) = 1 - e^-3 ≈ 0.95, P(X >= 2) ≈ 0.80
+        assert_eq!(stockpile(3.0), 1);
+        assert_eq!(stockpile(10_000.0), 9871);
+        // Past the point where exp(-mean) underflows
+        assert_eq!(stockpile(800.0), 763);
+
+        // 2^28 attempts: a 6-char prefix is very likely, a 7-char one a coin flip
+        let plan = plan(
+            Duration::from_secs(16),
+            1,
+            (1 << 24) as f64,
+            &symmetry::default_symmetries(),
+            Some(&PatternConfig::with_prefix("ABCDEF")),
+        );
+        assert_eq!(plan.candidates[0].family, "pattern");
+        assert!(plan.recommended.contains(&"prefix of 6 chars".to_string()));
+        assert!(plan
+            .recommended
+            .contains(&"any of 4 prefixes of 7 chars".to_string()));
+        let prefix_7 = plan
+            .candidates
+            .iter()
+            .find(|c| c.label == "prefix of 7 chars")
+            .unwrap();
+        assert_eq!(prefix_7.confidence(), "even odds");
+    }
+}
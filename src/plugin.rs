@@ -0,0 +1,276 @@
+//! Matcher Plugins
+//!
+//! `--plugin FILE` loads a WebAssembly module (binary `.wasm` or text `.wat`)
+//! that decides which keys match. The module must export:
+//! - `memory`
+//! - `key_ptr() -> i32`: address of a 32-byte buffer the host writes each
+//!   public key into
+//! - `matches() -> i32`: non-zero if the key in the buffer matches
+//! - optionally `score() -> i32`: a score for `--best`, clamped to 0-255
+//!
+//! The module is compiled once and instantiated once per CPU worker. It is
+//! only called for keys that already passed the built-in pattern, which acts
+//! as a cheap prefilter. Plugins are sandboxed: they may not import anything,
+//! their memory is capped, and each call runs on a fixed fuel budget
+//! (`--plugin-fuel`, about one unit per instruction). A call that traps or
+//! runs out of fuel counts as no match, so a plugin cannot stall a worker.
+
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use wasmi::{
+    Config, Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc,
+};
+
+/// Largest linear memory a plugin may use
+const MAX_MEMORY_BYTES: usize = 16 << 20;
+
+/// Default fuel per call (`--plugin-fuel`)
+pub const DEFAULT_FUEL: u64 = 100_000;
+
+/// A compiled plugin, shared by all workers
+pub struct Plugin {
+    path: PathBuf,
+    engine: Engine,
+    module: Module,
+    fuel: u64,
+    has_score: bool,
+    /// SHA-256 of the compiled module
+    digest: [u8; 32],
+    /// Calls that trapped or ran out of fuel
+    failed_calls: AtomicU64,
+}
+
+impl Plugin {
+    /// Compile and check the module at `path`
+    pub fn load(path: &Path, fuel: u64) -> Result<Arc<Self>, String> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| format!("Failed to read plugin {}: {}", path.display(), e))?;
+        Self::from_bytes(path, &bytes, fuel)
+    }
+
+    fn from_bytes(path: &Path, bytes: &[u8], fuel: u64) -> Result<Arc<Self>, String> {
+        let error = |e: &dyn std::fmt::Display| format!("Plugin {}: {}", path.display(), e);
+        let wasm = wat::parse_bytes(bytes).map_err(|e| error(&e))?;
+
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &wasm).map_err(|e| error(&e))?;
+        if let Some(import) = module.imports().next() {
+            return Err(error(&format!(
+                "imports {}.{}, but plugins may not import anything",
+                import.module(),
+                import.name()
+            )));
+        }
+        let has_score = module.exports().any(|e| e.name() == "score");
+
+        let plugin = Arc::new(Self {
+            path: path.to_path_buf(),
+            engine,
+            module,
+            fuel,
+            has_score,
+            digest: Sha256::digest(&wasm).into(),
+            failed_calls: AtomicU64::new(0),
+        });
+        // Fail now rather than in every worker
+        plugin.instantiate().map_err(|e| error(&e))?;
+        Ok(plugin)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn fuel(&self) -> u64 {
+        self.fuel
+    }
+
+    /// SHA-256 of the compiled module, identifying it regardless of text format
+    pub fn digest(&self) -> [u8; 32] {
+        self.digest
+    }
+
+    /// Whether the module exports `score`
+    pub fn has_score(&self) -> bool {
+        self.has_score
+    }
+
+    /// Calls that trapped or ran out of fuel so far
+    pub fn failed_calls(&self) -> u64 {
+        self.failed_calls.load(Ordering::Relaxed)
+    }
+
+    /// A fresh instance for one thread
+    pub fn instantiate(self: &Arc<Self>) -> Result<PluginInstance, String> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY_BYTES)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.fuel).map_err(|e| e.to_string())?;
+
+        let instance = Linker::new(&self.engine)
+            .instantiate(&mut store, &self.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| e.to_string())?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or("missing export 'memory'")?;
+        let function = |name: &str| {
+            instance
+                .get_typed_func::<(), i32>(&store, name)
+                .map_err(|e| format!("export '{}' must be a function () -> i32: {}", name, e))
+        };
+        let matches = function("matches")?;
+        let score = self.has_score.then(|| function("score")).transpose()?;
+
+        let key_ptr = function("key_ptr")?
+            .call(&mut store, ())
+            .map_err(|e| format!("key_ptr() failed: {}", e))?;
+        let key_ptr = usize::try_from(key_ptr)
+            .ok()
+            .filter(|&ptr| ptr + 32 <= memory.data(&store).len())
+            .ok_or_else(|| format!("key_ptr() returned {}, outside memory", key_ptr))?;
+
+        Ok(PluginInstance {
+            plugin: self.clone(),
+            store,
+            memory,
+            key_ptr,
+            matches,
+            score,
+        })
+    }
+}
+
+impl std::fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Plugin")
+            .field("path", &self.path)
+            .field("fuel", &self.fuel)
+            .field("has_score", &self.has_score)
+            .finish()
+    }
+}
+
+/// One thread's instance of a plugin
+pub struct PluginInstance {
+    plugin: Arc<Plugin>,
+    store: Store<StoreLimits>,
+    memory: Memory,
+    key_ptr: usize,
+    matches: TypedFunc<(), i32>,
+    score: Option<TypedFunc<(), i32>>,
+}
+
+impl PluginInstance {
+    /// Run `func` on `public_bytes` with a fresh fuel budget
+    fn call(&mut self, func: TypedFunc<(), i32>, public_bytes: &[u8; 32]) -> Option<i32> {
+        let result = self
+            .memory
+            .write(&mut self.store, self.key_ptr, public_bytes)
+            .ok()
+            .and_then(|_| self.store.set_fuel(self.plugin.fuel).ok())
+            .and_then(|_| func.call(&mut self.store, ()).ok());
+        if result.is_none() {
+            self.plugin.failed_calls.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Whether the plugin accepts the key; failed calls never match
+    pub fn matches(&mut self, public_bytes: &[u8; 32]) -> bool {
+        let matches = self.matches;
+        self.call(matches, public_bytes).is_some_and(|r| r != 0)
+    }
+
+    /// The plugin's score, if it exports one
+    pub fn score(&mut self, public_bytes: &[u8; 32]) -> Option<u8> {
+        let score = self.score?;
+        self.call(score, public_bytes)
+            .map(|s| s.clamp(0, u8::MAX as i32) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Matches keys whose bytes sum to a multiple of 42; score is the first byte
+    const DIGIT_SUM: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "key_ptr") (result i32) i32.const 64)
+          (func $sum (result i32) (local $i i32) (local $s i32)
+            (loop $next
+              (local.set $s (i32.add (local.get $s)
+                (i32.load8_u (i32.add (i32.const 64) (local.get $i)))))
+              (local.set $i (i32.add (local.get $i) (i32.const 1)))
+              (br_if $next (i32.lt_u (local.get $i) (i32.const 32))))
+            (local.get $s))
+          (func (export "matches") (result i32)
+            (i32.eqz (i32.rem_u (call $sum) (i32.const 42))))
+          (func (export "score") (result i32)
+            (i32.load8_u (i32.const 64))))
+    "#;
+
+    fn compile(source: &str, fuel: u64) -> Result<Arc<Plugin>, String> {
+        Plugin::from_bytes(Path::new("test.wat"), source.as_bytes(), fuel)
+    }
+
+    #[test]
+    fn test_plugin_matches_and_scores() {
+        let plugin = compile(DIGIT_SUM, DEFAULT_FUEL).unwrap();
+        assert!(plugin.has_score());
+        let mut instance = plugin.instantiate().unwrap();
+
+        let mut key = [1u8; 32];
+        key[0] = 11; // 11 + 31 = 42
+        assert!(instance.matches(&key));
+        assert_eq!(instance.score(&key), Some(11));
+        key[1] = 2;
+        assert!(!instance.matches(&key));
+
+        // Too little fuel for the loop: no match, counted as a failure
+        let starved = compile(DIGIT_SUM, 50).unwrap();
+        assert!(!starved.instantiate().unwrap().matches(&[0; 32]));
+        assert_eq!(starved.failed_calls(), 1);
+        assert_eq!(plugin.failed_calls(), 0);
+    }
+
+    #[test]
+    fn test_plugin_sandbox() {
+        let spin = r#"(module (memory (export "memory") 1)
+            (func (export "key_ptr") (result i32) i32.const 0)
+            (func (export "matches") (result i32) (loop $l (br $l)) i32.const 1))"#;
+        let mut instance = compile(spin, 10_000).unwrap().instantiate().unwrap();
+        assert!(!instance.matches(&[0; 32]));
+
+        let imports = r#"(module (import "env" "clock" (func))
+            (memory (export "memory") 1)
+            (func (export "key_ptr") (result i32) i32.const 0)
+            (func (export "matches") (result i32) i32.const 1))"#;
+        assert!(compile(imports, DEFAULT_FUEL)
+            .unwrap_err()
+            .contains("may not import"));
+
+        let huge = r#"(module (memory (export "memory") 1024)
+            (func (export "key_ptr") (result i32) i32.const 0)
+            (func (export "matches") (result i32) i32.const 1))"#;
+        assert!(compile(huge, DEFAULT_FUEL).is_err());
+
+        let outside = r#"(module (memory (export "memory") 1)
+            (func (export "key_ptr") (result i32) i32.const 65530)
+            (func (export "matches") (result i32) i32.const 1))"#;
+        assert!(compile(outside, DEFAULT_FUEL)
+            .unwrap_err()
+            .contains("outside memory"));
+        assert!(compile("(module)", DEFAULT_FUEL).is_err());
+    }
+}
@@ -0,0 +1,285 @@
+//! Scheduled Run Windows
+//!
+//! `--schedule` restricts searching to configured hours, e.g.
+//! `"mon-fri 22:00-07:00; sat-sun"`. Outside every window the main loop pauses
+//! the worker pool and resumes it when the next window opens. Time spent
+//! paused (for any reason) is excluded from elapsed time, rates and
+//! `--max-time` through `ActiveClock`.
+
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDateTime, Timelike};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Minutes in a day; also the only valid end time of `24:00`
+const DAY_MINUTES: u32 = 24 * 60;
+
+/// How far ahead `next_change` looks (a week plus the overnight spill)
+const LOOKAHEAD_MINUTES: i64 = 8 * DAY_MINUTES as i64;
+
+const DAY_NAMES: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// One recurring window: on each selected day, from `start` to `end` minutes
+/// after midnight. Windows with `end <= start` run overnight into the next
+/// day; the days always refer to the day the window opens.
+#[derive(Debug, Clone, PartialEq)]
+struct Window {
+    days: [bool; 7],
+    start: u32,
+    end: u32,
+}
+
+impl Window {
+    fn contains(&self, t: &NaiveDateTime) -> bool {
+        let day = t.weekday().num_days_from_monday() as usize;
+        let minute = t.hour() * 60 + t.minute();
+        if self.start < self.end {
+            self.days[day] && minute >= self.start && minute < self.end
+        } else {
+            (self.days[day] && minute >= self.start)
+                || (self.days[(day + 6) % 7] && minute < self.end)
+        }
+    }
+}
+
+/// Parsed `--schedule` specification
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    spec: String,
+    windows: Vec<Window>,
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+impl Schedule {
+    /// Parse windows separated by `;`, each `[DAYS] [HH:MM-HH:MM]`
+    ///
+    /// DAYS is a comma-separated list of day names (`mon`..`sun`), ranges
+    /// (`mon-fri`) or `weekdays`/`weekends`/`daily`; omitted means every day.
+    /// An omitted time range means the whole day.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let windows = spec
+            .split(';')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(parse_window)
+            .collect::<Result<Vec<_>, _>>()?;
+        if windows.is_empty() {
+            return Err("Schedule has no windows".to_string());
+        }
+        Ok(Self {
+            spec: spec.trim().to_string(),
+            windows,
+        })
+    }
+
+    /// Whether searching is allowed at local time `t`
+    pub fn is_active(&self, t: &NaiveDateTime) -> bool {
+        self.windows.iter().any(|w| w.contains(t))
+    }
+
+    /// Start of the next minute at which `is_active` flips, if within a week
+    pub fn next_change(&self, t: &NaiveDateTime) -> Option<NaiveDateTime> {
+        let current = self.is_active(t);
+        let minute_start = t.with_second(0)?.with_nanosecond(0)?;
+        (1..=LOOKAHEAD_MINUTES)
+            .map(|m| minute_start + ChronoDuration::minutes(m))
+            .find(|candidate| self.is_active(candidate) != current)
+    }
+}
+
+fn parse_window(part: &str) -> Result<Window, String> {
+    let mut days = None;
+    let mut times = None;
+    for token in part.split_whitespace() {
+        if token.contains(':') {
+            if times.replace(parse_time_range(token)?).is_some() {
+                return Err(format!("More than one time range in '{}'", part));
+            }
+        } else if days.replace(parse_days(token)?).is_some() {
+            return Err(format!("More than one day list in '{}'", part));
+        }
+    }
+    let (start, end) = times.unwrap_or((0, DAY_MINUTES));
+    Ok(Window {
+        days: days.unwrap_or([true; 7]),
+        start,
+        end,
+    })
+}
+
+fn parse_days(token: &str) -> Result<[bool; 7], String> {
+    // Any prefix of at least three letters: mon, tues, wednesday
+    let day_index = |name: &str| {
+        let name = name.to_lowercase();
+        DAY_NAMES
+            .iter()
+            .position(|d| name.len() >= 3 && d.starts_with(&name))
+            .ok_or_else(|| format!("Unknown day '{}'", name))
+    };
+
+    let mut days = [false; 7];
+    for item in token.split(',').filter(|i| !i.is_empty()) {
+        match item.to_lowercase().as_str() {
+            "daily" | "*" => days = [true; 7],
+            "weekdays" => days[..5].fill(true),
+            "weekends" => days[5..].fill(true),
+            _ => match item.split_once('-') {
+                Some((from, to)) => {
+                    let (from, to) = (day_index(from)?, day_index(to)?);
+                    // Ranges may wrap around the week, e.g. fri-mon
+                    let mut day = from;
+                    loop {
+                        days[day] = true;
+                        if day == to {
+                            break;
+                        }
+                        day = (day + 1) % 7;
+                    }
+                }
+                None => days[day_index(item)?] = true,
+            },
+        }
+    }
+    if !days.contains(&true) {
+        return Err(format!("No days in '{}'", token));
+    }
+    Ok(days)
+}
+
+fn parse_time_range(token: &str) -> Result<(u32, u32), String> {
+    let (start, end) = token
+        .split_once('-')
+        .ok_or_else(|| format!("Time range '{}' must be HH:MM-HH:MM", token))?;
+    let (start, end) = (parse_time(start)?, parse_time(end)?);
+    if start == end || start == DAY_MINUTES {
+        return Err(format!("Empty time range '{}'", token));
+    }
+    Ok((start, end))
+}
+
+fn parse_time(value: &str) -> Result<u32, String> {
+    let invalid = || format!("Invalid time '{}' (expected HH:MM)", value);
+    let (hours, minutes) = value.split_once(':').ok_or_else(invalid)?;
+    let hours: u32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+    // Check the hours before multiplying so a huge value cannot wrap around
+    if hours > 24 || minutes >= 60 || hours * 60 + minutes > DAY_MINUTES {
+        return Err(invalid());
+    }
+    Ok(hours * 60 + minutes)
+}
+
+/// Wall-clock time that only advances while the search is running
+#[derive(Debug)]
+pub struct ActiveClock {
+    /// Active time carried over (e.g. from a checkpoint) plus finished stretches
+    accumulated: Duration,
+    running_since: Option<Instant>,
+}
+
+impl ActiveClock {
+    /// A running clock that starts at `offset`
+    pub fn start(offset: Duration) -> Self {
+        Self {
+            accumulated: offset,
+            running_since: Some(Instant::now()),
+        }
+    }
+
+    /// Stop or restart the clock; repeated calls with the same state are no-ops
+    pub fn set_running(&mut self, running: bool) {
+        match (running, self.running_since) {
+            (true, None) => self.running_since = Some(Instant::now()),
+            (false, Some(since)) => {
+                self.accumulated += since.elapsed();
+                self.running_since = None;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.accumulated + self.running_since.map_or(Duration::ZERO, |s| s.elapsed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    /// 2024-01-01 was a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_overnight_weekdays_and_weekend() {
+        let schedule = Schedule::parse("mon-fri 22:00-07:00; sat,sun").unwrap();
+
+        assert!(!schedule.is_active(&at(1, 12, 0))); // Mon noon
+        assert!(schedule.is_active(&at(1, 22, 0))); // Mon 22:00
+        assert!(schedule.is_active(&at(2, 6, 59))); // Tue 06:59, Mon night
+        assert!(!schedule.is_active(&at(2, 7, 0)));
+        assert!(!schedule.is_active(&at(1, 3, 0))); // Mon 03:00, Sunday window is whole-day only
+        assert!(schedule.is_active(&at(6, 12, 0))); // Sat noon
+        assert!(schedule.is_active(&at(7, 23, 59))); // Sun late
+
+        assert_eq!(schedule.next_change(&at(1, 12, 30)), Some(at(1, 22, 0)));
+        assert_eq!(schedule.next_change(&at(2, 3, 0)), Some(at(2, 7, 0)));
+        // Fri night runs straight into the weekend and out on Monday
+        assert_eq!(schedule.next_change(&at(5, 23, 0)), Some(at(8, 0, 0)));
+    }
+
+    #[test]
+    fn test_parse_errors_and_aliases() {
+        assert_eq!(
+            Schedule::parse("weekdays 09:00-17:00").unwrap().windows,
+            Schedule::parse("mon-fri 09:00-17:00").unwrap().windows
+        );
+        assert!(Schedule::parse("fri-mon").unwrap().is_active(&at(1, 10, 0)));
+        assert!(Schedule::parse("Tues,wednesday")
+            .unwrap()
+            .is_active(&at(3, 10, 0)));
+        assert!(Schedule::parse("").is_err());
+        assert!(Schedule::parse("funday").is_err());
+        assert!(Schedule::parse("10:00-10:00").is_err());
+        assert!(Schedule::parse("25:00-07:00").is_err());
+        // 71582789 hours wraps to 00:44 if multiplied out before the range check
+        assert!(Schedule::parse("71582789:00-07:00").is_err());
+        assert!(Schedule::parse("mon 10:00-11:00 12:00-13:00").is_err());
+        assert_eq!(
+            Schedule::parse("daily").unwrap().next_change(&at(1, 0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_active_clock_excludes_paused_time() {
+        let mut clock = ActiveClock::start(Duration::from_secs(100));
+        std::thread::sleep(Duration::from_millis(20));
+        clock.set_running(false);
+        let paused_at = clock.elapsed();
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(clock.elapsed(), paused_at);
+        clock.set_running(false);
+        clock.set_running(true);
+        assert!(clock.elapsed() >= Duration::from_millis(100_020));
+        assert!(clock.elapsed() < Duration::from_millis(100_060));
+    }
+}
@@ -0,0 +1,375 @@
+//! Best-of Scoring
+//!
+//! `--best N` ranks every key a CPU worker generates, not just exact matches,
+//! so a run that ends without a match (e.g. `--pattern 8` under `--max-time`)
+//! still leaves the N most "vanity" keys behind. Each key gets several scores
+//! in hex characters:
+//! - `vanity`: first N equal to the last N (or their reverse)
+//! - `repeat`: longest run of one repeated nibble anywhere
+//! - `prefix`: leading characters matching `--prefix`
+//! - `word`: longest `--dictionary` word at an allowed end
+//! - `plugin`: the `score` export of a `--plugin`, filled in by the worker
+//!
+//! Keys are ranked by the selected score, then by the sum of all of them.
+//! Workers collect candidates in a bounded per-thread heap and merge it into
+//! the shared `BestKeys` once per batch.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::dictionary::Dictionary;
+use crate::keygen::KeyInfo;
+use crate::pattern::{key_nibble, PatternConfig, PatternMode, KEY_NIBBLES};
+use crate::symmetry::{self, Symmetry};
+
+/// Score that orders the best keys (`--score`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ScoreKind {
+    Vanity,
+    Repeat,
+    Prefix,
+    Word,
+    Plugin,
+}
+
+impl ScoreKind {
+    /// The score that fits the search pattern
+    pub fn for_pattern(pattern: &PatternConfig) -> Self {
+        match pattern.mode {
+            PatternMode::Prefix => Self::Prefix,
+            PatternMode::Dictionary => Self::Word,
+            _ => Self::Vanity,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Vanity => "vanity",
+            Self::Repeat => "repeat",
+            Self::Prefix => "prefix",
+            Self::Word => "word",
+            Self::Plugin => "plugin",
+        }
+    }
+}
+
+/// All scores of one key, in hex characters
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Score {
+    pub vanity: u8,
+    pub repeat: u8,
+    pub prefix: u8,
+    pub word: u8,
+    pub plugin: u8,
+}
+
+impl Score {
+    pub fn get(&self, kind: ScoreKind) -> u8 {
+        match kind {
+            ScoreKind::Vanity => self.vanity,
+            ScoreKind::Repeat => self.repeat,
+            ScoreKind::Prefix => self.prefix,
+            ScoreKind::Word => self.word,
+            ScoreKind::Plugin => self.plugin,
+        }
+    }
+
+    pub fn total(&self) -> u16 {
+        self.vanity as u16
+            + self.repeat as u16
+            + self.prefix as u16
+            + self.word as u16
+            + self.plugin as u16
+    }
+}
+
+impl std::fmt::Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "vanity {}, repeat {}, prefix {}, word {}",
+            self.vanity, self.repeat, self.prefix, self.word
+        )?;
+        if self.plugin > 0 {
+            write!(f, ", plugin {}", self.plugin)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sort key: the selected score, then the total
+pub type Rank = (u8, u16);
+
+/// Scores keys against the search's prefix, symmetries and dictionary
+#[derive(Clone, Debug)]
+pub struct Scorer {
+    kind: ScoreKind,
+    prefix: Vec<u8>,
+    symmetries: Vec<Symmetry>,
+    dictionary: Option<Dictionary>,
+}
+
+impl Scorer {
+    pub fn new(kind: ScoreKind, pattern: &PatternConfig) -> Self {
+        let prefix = pattern
+            .prefix
+            .as_deref()
+            .unwrap_or("")
+            .chars()
+            .map_while(|c| c.to_digit(16).map(|d| d as u8))
+            .collect();
+        Self {
+            kind,
+            prefix,
+            symmetries: pattern.symmetries.clone(),
+            dictionary: pattern.dictionary.clone(),
+        }
+    }
+
+    pub fn kind(&self) -> ScoreKind {
+        self.kind
+    }
+
+    pub fn score(&self, public_bytes: &[u8; 32]) -> Score {
+        let mut nibbles = [0u8; KEY_NIBBLES];
+        for (i, n) in nibbles.iter_mut().enumerate() {
+            *n = key_nibble(public_bytes, i);
+        }
+
+        Score {
+            vanity: vanity_len(&nibbles, &self.symmetries) as u8,
+            repeat: longest_run(&nibbles) as u8,
+            prefix: nibbles
+                .iter()
+                .zip(&self.prefix)
+                .take_while(|(n, p)| n == p)
+                .count() as u8,
+            word: self
+                .dictionary
+                .as_ref()
+                .map_or(0, |d| d.match_len(public_bytes)) as u8,
+            plugin: 0,
+        }
+    }
+
+    pub fn rank(&self, score: &Score) -> Rank {
+        (score.get(self.kind), score.total())
+    }
+}
+
+/// Longest N for which any of `symmetries` holds
+fn vanity_len(nibbles: &[u8; KEY_NIBBLES], symmetries: &[Symmetry]) -> usize {
+    (1..=KEY_NIBBLES / 2)
+        .rev()
+        .find(|&n| symmetry::matches_chars(|i| nibbles[i], KEY_NIBBLES, n, symmetries))
+        .unwrap_or(0)
+}
+
+fn longest_run(nibbles: &[u8; KEY_NIBBLES]) -> usize {
+    let (mut best, mut run) = (1, 1);
+    for pair in nibbles.windows(2) {
+        run = if pair[0] == pair[1] { run + 1 } else { 1 };
+        best = best.max(run);
+    }
+    best
+}
+
+/// A candidate for the best keys
+#[derive(Clone, Debug)]
+pub struct ScoredKey {
+    pub key: KeyInfo,
+    pub score: Score,
+    pub rank: Rank,
+}
+
+impl PartialEq for ScoredKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank == other.rank
+    }
+}
+
+impl Eq for ScoredKey {}
+
+impl PartialOrd for ScoredKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScoredKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank.cmp(&other.rank)
+    }
+}
+
+/// Bounded heap keeping the `capacity` highest-ranked keys
+#[derive(Debug)]
+pub struct TopN {
+    capacity: usize,
+    heap: BinaryHeap<Reverse<ScoredKey>>,
+}
+
+impl TopN {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            heap: BinaryHeap::with_capacity(capacity + 1),
+        }
+    }
+
+    /// Lowest rank still kept once full; anything at or below it is rejected
+    pub fn threshold(&self) -> Option<Rank> {
+        (self.heap.len() >= self.capacity)
+            .then(|| self.heap.peek().map(|Reverse(k)| k.rank))
+            .flatten()
+    }
+
+    pub fn accepts(&self, rank: Rank) -> bool {
+        self.threshold().is_none_or(|t| rank > t)
+    }
+
+    pub fn push(&mut self, key: ScoredKey) {
+        if !self.accepts(key.rank) {
+            return;
+        }
+        self.heap.push(Reverse(key));
+        if self.heap.len() > self.capacity {
+            self.heap.pop();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Take all keys, leaving the heap empty
+    pub fn drain(&mut self) -> impl Iterator<Item = ScoredKey> + '_ {
+        self.heap.drain().map(|Reverse(k)| k)
+    }
+
+    /// Keys, best first
+    pub fn sorted(&self) -> Vec<ScoredKey> {
+        let mut keys: Vec<ScoredKey> = self.heap.iter().map(|Reverse(k)| k.clone()).collect();
+        keys.sort_by(|a, b| b.cmp(a));
+        keys
+    }
+}
+
+/// The best keys across all workers
+#[derive(Debug)]
+pub struct BestKeys {
+    scorer: Scorer,
+    capacity: usize,
+    top: Mutex<TopN>,
+}
+
+impl BestKeys {
+    pub fn new(capacity: usize, scorer: Scorer) -> Self {
+        Self {
+            scorer,
+            capacity,
+            top: Mutex::new(TopN::new(capacity)),
+        }
+    }
+
+    pub fn scorer(&self) -> &Scorer {
+        &self.scorer
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Rank a worker's candidates must beat to be worth keeping
+    pub fn threshold(&self) -> Option<Rank> {
+        self.top.lock().unwrap().threshold()
+    }
+
+    /// Merge a worker's candidates; returns the new threshold
+    pub fn merge(&self, keys: impl Iterator<Item = ScoredKey>) -> Option<Rank> {
+        let mut top = self.top.lock().unwrap();
+        for key in keys {
+            top.push(key);
+        }
+        top.threshold()
+    }
+
+    /// Best keys so far, best first
+    pub fn snapshot(&self) -> Vec<ScoredKey> {
+        self.top.lock().unwrap().sorted()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dictionary::{parse_wordlist, Anchor};
+    use crate::pattern::key_bytes;
+
+    #[test]
+    fn test_scores() {
+        let scorer = Scorer::new(ScoreKind::Vanity, &PatternConfig::with_prefix("ABC9"));
+
+        // First 5 == last 5, and a 7-long run of 0s in the middle
+        let key = format!("ABC12{}0000000{}5ABC12", "34".repeat(12), "56".repeat(11));
+        assert_eq!(
+            scorer.score(&key_bytes(&key)),
+            Score {
+                vanity: 5,
+                repeat: 7,
+                prefix: 3,
+                word: 0,
+                plugin: 0
+            }
+        );
+
+        // Mirrored ends count the same way
+        let mirrored = format!("ABCD{}DCBA", "12".repeat(28));
+        assert_eq!(scorer.score(&key_bytes(&mirrored)).vanity, 4);
+
+        // Only the configured symmetries count
+        let mut rotation = PatternConfig::with_prefix("ABC9");
+        rotation.symmetries = vec![Symmetry::Rotation];
+        let scorer = Scorer::new(ScoreKind::Vanity, &rotation);
+        // AB…BA is a rotation of two, ABCD…DCBA of none longer
+        assert_eq!(scorer.score(&key_bytes(&mirrored)).vanity, 2);
+        let rotated = format!("ABCD{}CDAB", "12".repeat(28));
+        assert_eq!(scorer.score(&key_bytes(&rotated)).vanity, 4);
+
+        let words = parse_wordlist("c0ffee", false, 4);
+        let pattern = PatternConfig::with_dictionary(Dictionary::new(words, Anchor::End));
+        let scorer = Scorer::new(ScoreKind::for_pattern(&pattern), &pattern);
+        let score = scorer.score(&key_bytes(&format!("{}C0FFEE", "12".repeat(29))));
+        assert_eq!(scorer.kind(), ScoreKind::Word);
+        assert_eq!(score.word, 6);
+        assert_eq!(scorer.rank(&score), (6, score.total()));
+    }
+
+    #[test]
+    fn test_top_n_keeps_best_ranks() {
+        let scored = |rank: Rank| ScoredKey {
+            key: crate::keygen::generate_meshcore_keypair(),
+            score: Score::default(),
+            rank,
+        };
+
+        let best = BestKeys::new(3, Scorer::new(ScoreKind::Repeat, &PatternConfig::default()));
+        let mut local = TopN::new(best.capacity());
+        for rank in [(2, 5), (4, 4), (1, 9), (4, 6)] {
+            local.push(scored(rank));
+        }
+        assert_eq!(local.threshold(), Some((2, 5)));
+        assert!(!local.accepts((2, 5)));
+
+        assert_eq!(best.merge(local.drain()), Some((2, 5)));
+        assert!(local.is_empty());
+        best.merge([scored((3, 0)), scored((0, 0))].into_iter());
+
+        let ranks: Vec<Rank> = best.snapshot().iter().map(|k| k.rank).collect();
+        assert_eq!(ranks, vec![(4, 6), (4, 4), (3, 0)]);
+    }
+}
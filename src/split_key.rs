@@ -0,0 +1,277 @@
+//! Split-Key Vanity Search
+//!
+//! Lets untrusted helpers search for a vanity key without ever seeing the
+//! private key:
+//! 1. The requester picks a secret clamped scalar `a` and publishes `A = a·G`
+//!    plus the pattern (the split request)
+//! 2. Helpers walk `A + k·G` for offsets `k = 8·j` and return the first `k`
+//!    whose point matches the pattern
+//! 3. The requester combines `s = a + k`; since `a` leaves headroom below
+//!    2^255 and `k` is a multiple of 8, `s` is still a clamped MeshCore scalar
+//!
+//! Helpers learn `A`, `k` and the final public key, none of which reveal `a`.
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::keygen::{self, KeyInfo};
+use crate::pattern::{matches_pattern_bytes, PatternConfig};
+
+/// Current split request format
+const REQUEST_VERSION: u32 = 1;
+
+/// Offsets are multiples of 8 below 2^64; each helper starts somewhere below
+/// 2^63 so independent helpers practically never overlap
+const START_RANGE: u64 = 1 << 60;
+
+/// Points checked between counter updates and stop checks
+const BATCH_SIZE: u64 = 10_000;
+
+/// What the requester publishes to helpers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitRequest {
+    pub version: u32,
+    /// Compressed `A = a·G` as hex
+    pub public_point: String,
+    pub pattern: PatternConfig,
+}
+
+/// What the requester keeps: the secret scalar and the request it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitSecret {
+    /// Clamped secret scalar `a` as 32-byte little-endian hex
+    pub secret_scalar: String,
+    pub request: SplitRequest,
+}
+
+/// A helper's answer
+#[derive(Debug, Clone, Serialize)]
+pub struct SplitSolution {
+    /// Offset `k` as 16 hex chars (big-endian u64)
+    pub offset: String,
+    /// Public key of the combined keypair
+    pub public_key: String,
+}
+
+/// Generate a new secret and its request
+///
+/// The secret comes from the normal MeshCore generator (so it is clamped),
+/// with bit 253 cleared so `a + k < 2^255` for every 64-bit offset.
+pub fn new_request(pattern: PatternConfig) -> SplitSecret {
+    let mut scalar = [0u8; 32];
+    scalar.copy_from_slice(&keygen::generate_meshcore_keypair().private_bytes[..32]);
+    scalar[31] &= !0x20;
+
+    let point = &Scalar::from_bytes_mod_order(scalar) * ED25519_BASEPOINT_TABLE;
+    SplitSecret {
+        secret_scalar: hex::encode(scalar),
+        request: SplitRequest {
+            version: REQUEST_VERSION,
+            public_point: hex::encode(point.compress().to_bytes()),
+            pattern,
+        },
+    }
+}
+
+/// Decode and sanity-check the request's public point
+fn request_point(request: &SplitRequest) -> Result<EdwardsPoint, String> {
+    if request.version != REQUEST_VERSION {
+        return Err(format!(
+            "Unsupported split request version {}",
+            request.version
+        ));
+    }
+    let bytes = crate::audit::parse_public_hex(&request.public_point)?;
+    let point = CompressedEdwardsY(bytes)
+        .decompress()
+        .ok_or("Request public point is not on the curve")?;
+    if !point.is_torsion_free() {
+        return Err("Request public point has a torsion component".to_string());
+    }
+    Ok(point)
+}
+
+/// Public key for offset `k`, or `None` if it does not satisfy the request
+pub fn check_offset(request: &SplitRequest, offset: u64) -> Result<Option<[u8; 32]>, String> {
+    if !offset.is_multiple_of(8) {
+        return Ok(None);
+    }
+    let point = request_point(request)? + &Scalar::from(offset) * ED25519_BASEPOINT_TABLE;
+    let public_bytes = point.compress().to_bytes();
+    let ok = keygen::is_valid_meshcore_prefix(&public_bytes)
+        && matches_pattern_bytes(&public_bytes, &request.pattern);
+    Ok(ok.then_some(public_bytes))
+}
+
+/// Search for an offset with `workers` threads until found or `should_stop` is set
+///
+/// Each thread starts at a random multiple of 8 and steps by `8·G`, so a step
+/// is one point addition instead of a full scalar multiplication.
+pub fn search(
+    request: &SplitRequest,
+    workers: usize,
+    total_attempts: &AtomicU64,
+    should_stop: &AtomicBool,
+) -> Result<Option<SplitSolution>, String> {
+    let base = request_point(request)?;
+    let step = &Scalar::from(8u64) * ED25519_BASEPOINT_TABLE;
+    let found: Mutex<Option<SplitSolution>> = Mutex::new(None);
+
+    thread::scope(|scope| {
+        for _ in 0..workers.max(1) {
+            let found = &found;
+            scope.spawn(move || {
+                let mut j = rand::thread_rng().next_u64() % START_RANGE;
+                let mut point = base + &Scalar::from(8 * j) * ED25519_BASEPOINT_TABLE;
+
+                while !should_stop.load(Ordering::Relaxed) {
+                    for _ in 0..BATCH_SIZE {
+                        let public_bytes = point.compress().to_bytes();
+                        if keygen::is_valid_meshcore_prefix(&public_bytes)
+                            && matches_pattern_bytes(&public_bytes, &request.pattern)
+                        {
+                            let mut slot = found.lock().unwrap();
+                            if slot.is_none() {
+                                *slot = Some(SplitSolution {
+                                    offset: format!("{:016x}", 8 * j),
+                                    public_key: hex::encode(public_bytes),
+                                });
+                            }
+                            should_stop.store(true, Ordering::Relaxed);
+                            return;
+                        }
+                        point += step;
+                        j += 1;
+                    }
+                    total_attempts.fetch_add(BATCH_SIZE, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+
+    Ok(found.into_inner().unwrap())
+}
+
+/// Parse an offset as returned by a helper (hex, optional `0x`)
+pub fn parse_offset(value: &str) -> Result<u64, String> {
+    let trimmed = value.trim();
+    let digits = trimmed.strip_prefix("0x").unwrap_or(trimmed);
+    u64::from_str_radix(digits, 16).map_err(|e| format!("Invalid offset '{}': {}", value, e))
+}
+
+/// Form the final keypair `s = a + k` and check it
+///
+/// The private key's second half (used only as the signing nonce prefix) is
+/// fresh randomness. The result must satisfy the request pattern,
+/// `verify_key` and `validate_for_meshcore`.
+pub fn combine(secret: &SplitSecret, offset: u64) -> Result<KeyInfo, String> {
+    let expected_public = check_offset(&secret.request, offset)?
+        .ok_or("Offset does not produce a key matching the request pattern")?;
+
+    let a: [u8; 32] = hex::decode(&secret.secret_scalar)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or("Secret scalar must be 32 bytes of hex")?;
+
+    // Little-endian add of the 64-bit offset
+    let mut s = a;
+    let mut carry = offset as u128;
+    for byte in s.iter_mut() {
+        carry += *byte as u128;
+        *byte = carry as u8;
+        carry >>= 8;
+    }
+    if carry != 0 || s[0] & 7 != 0 || s[31] & 0xC0 != 0x40 {
+        return Err("Combined scalar is no longer a clamped MeshCore scalar".to_string());
+    }
+
+    let mut private_bytes = [0u8; 64];
+    private_bytes[..32].copy_from_slice(&s);
+    rand::thread_rng().fill_bytes(&mut private_bytes[32..]);
+
+    let key = KeyInfo {
+        public_hex: hex::encode(expected_public),
+        private_hex: hex::encode(private_bytes),
+        public_bytes: expected_public,
+        private_bytes,
+    };
+    if !keygen::verify_key(&key) {
+        return Err("Secret does not belong to this request (public key mismatch)".to_string());
+    }
+    let validation = keygen::validate_for_meshcore(&key);
+    if !validation.valid {
+        return Err(validation.reason.unwrap_or_default());
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn one_char_prefix() -> PatternConfig {
+        PatternConfig::with_prefix("A")
+    }
+
+    #[test]
+    fn test_secret_leaves_headroom() {
+        for _ in 0..32 {
+            let secret = new_request(PatternConfig::default());
+            let scalar = hex::decode(&secret.secret_scalar).unwrap();
+            assert_eq!(scalar[0] & 7, 0);
+            assert_eq!(scalar[31] & 0xE0, 0x40);
+        }
+    }
+
+    #[test]
+    fn test_search_and_combine() {
+        let secret = new_request(one_char_prefix());
+        let attempts = AtomicU64::new(0);
+        let stop = AtomicBool::new(false);
+
+        let solution = search(&secret.request, 2, &attempts, &stop)
+            .unwrap()
+            .unwrap();
+        let offset = parse_offset(&solution.offset).unwrap();
+        assert!(offset.is_multiple_of(8));
+
+        let key = combine(&secret, offset).unwrap();
+        assert_eq!(key.public_hex, solution.public_key);
+        assert!(key.public_hex.starts_with('a'));
+        assert!(keygen::check_key_health(&key).healthy);
+    }
+
+    #[test]
+    fn test_combine_rejects_bad_offsets() {
+        let secret = new_request(one_char_prefix());
+        // Not a multiple of 8
+        assert!(combine(&secret, 3).is_err());
+
+        // Matching offset for a different secret's request
+        let other = new_request(one_char_prefix());
+        let stop = AtomicBool::new(false);
+        let solution = search(&other.request, 1, &AtomicU64::new(0), &stop)
+            .unwrap()
+            .unwrap();
+        let mut mixed = other.clone();
+        mixed.secret_scalar = secret.secret_scalar.clone();
+        assert!(combine(&mixed, parse_offset(&solution.offset).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_point() {
+        let mut request = new_request(one_char_prefix()).request;
+        // Order-2 point (0, -1) is on the curve but not in the prime subgroup
+        let mut torsion = [0xffu8; 32];
+        torsion[0] = 0xec;
+        torsion[31] = 0x7f;
+        request.public_point = hex::encode(torsion);
+        assert!(check_offset(&request, 0).is_err());
+    }
+}
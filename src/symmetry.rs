@@ -0,0 +1,419 @@
+//! Vanity Symmetry Modes
+//!
+//! `--pattern`/`--vanity N` compare the first N hex characters of a key with
+//! another part of it. `--symmetry` selects how, and several modes may be
+//! combined (a key matches if any of them holds):
+//! - `equal`: the last N are the first N (`ABCD…ABCD`)
+//! - `reverse`: the last N are the first N backwards (`ABCD…DCBA`)
+//! - `either`: `equal` or `reverse`, the historical default
+//! - `middle`: the N in the middle of the key are the first N
+//! - `rotation`: the last N are the first N cyclically shifted (`ABCD…CDAB`)
+//! - `palindrome`: the whole key reads the same backwards (ignores N)
+//!
+//! Every mode is a set of alternative systems of nibble equalities, so the
+//! exact match probability of any combination follows from
+//! inclusion–exclusion: a system that ties `u` independent pairs of nibbles
+//! together holds with probability 16^-u.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::pattern::{key_nibble, KEY_NIBBLES};
+
+/// Vanity lengths accepted anywhere, as for `--vanity` and `--pattern`; the
+/// exact odds take exponential time in N
+pub const VANITY_LENGTHS: std::ops::RangeInclusive<usize> = 2..=8;
+
+/// Inclusion–exclusion over more distinct systems than this would take too
+/// long; past it the odds fall back to the union bound
+const MAX_EXACT_SYSTEMS: usize = 16;
+
+/// How the first N characters must relate to the rest of the key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Symmetry {
+    Equal,
+    Reverse,
+    Either,
+    Middle,
+    Rotation,
+    Palindrome,
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Equal => "equal",
+            Self::Reverse => "reverse",
+            Self::Either => "either",
+            Self::Middle => "middle",
+            Self::Rotation => "rotation",
+            Self::Palindrome => "palindrome",
+        })
+    }
+}
+
+/// The historical vanity rule
+pub fn default_symmetries() -> Vec<Symmetry> {
+    vec![Symmetry::Either]
+}
+
+impl Symmetry {
+    /// What a match looks like, for pattern descriptions
+    pub fn describe(&self, n: usize) -> String {
+        match self {
+            Self::Equal => format!("last {} == first {}", n, n),
+            Self::Reverse => format!("last {} == first {} reversed", n, n),
+            Self::Either => format!("last {} == first {} or its reverse", n, n),
+            Self::Middle => format!("middle {} == first {}", n, n),
+            Self::Rotation => format!("last {} == first {} rotated", n, n),
+            Self::Palindrome => "whole key is a palindrome".to_string(),
+        }
+    }
+
+    /// Hex character ranges a match constrains
+    #[allow(clippy::single_range_in_vec_init)]
+    pub fn ranges(&self, n: usize) -> Vec<std::ops::Range<usize>> {
+        match self {
+            Self::Middle => {
+                let m = middle_start(n);
+                vec![0..n, m..m + n]
+            }
+            Self::Palindrome => vec![0..KEY_NIBBLES],
+            _ => vec![0..n, KEY_NIBBLES - n..KEY_NIBBLES],
+        }
+    }
+
+    /// Alternative equality systems: the mode holds if any system holds
+    fn systems(&self, n: usize) -> Vec<Vec<(usize, usize)>> {
+        let last = KEY_NIBBLES - n;
+        match self {
+            Self::Equal => vec![(0..n).map(|i| (i, last + i)).collect()],
+            Self::Reverse => vec![(0..n).map(|i| (i, KEY_NIBBLES - 1 - i)).collect()],
+            Self::Either => [Self::Equal, Self::Reverse]
+                .iter()
+                .flat_map(|s| s.systems(n))
+                .collect(),
+            Self::Middle => {
+                let m = middle_start(n);
+                vec![(0..n).map(|i| (i, m + i)).collect()]
+            }
+            Self::Rotation => (1..n)
+                .map(|k| (0..n).map(|j| ((j + k) % n, last + j)).collect())
+                .collect(),
+            Self::Palindrome => vec![(0..KEY_NIBBLES / 2)
+                .map(|i| (i, KEY_NIBBLES - 1 - i))
+                .collect()],
+        }
+    }
+}
+
+/// First character of the middle N of a key (centred, rounding left)
+fn middle_start(n: usize) -> usize {
+    middle_start_in(KEY_NIBBLES, n)
+}
+
+fn middle_start_in(len: usize, n: usize) -> usize {
+    (len - n) / 2
+}
+
+/// Whether any of `symmetries` holds for the `len` characters read through
+/// `char_at` (nibbles or hex digits, only compared for equality)
+#[inline(always)]
+pub fn matches_chars(
+    char_at: impl Fn(usize) -> u8,
+    len: usize,
+    n: usize,
+    symmetries: &[Symmetry],
+) -> bool {
+    len >= n * 2 && symmetries.iter().any(|&s| matches_one(&char_at, len, n, s))
+}
+
+#[inline(always)]
+fn matches_one(char_at: &impl Fn(usize) -> u8, len: usize, n: usize, symmetry: Symmetry) -> bool {
+    let last = len - n;
+    match symmetry {
+        Symmetry::Equal => (0..n).all(|i| char_at(i) == char_at(last + i)),
+        Symmetry::Reverse => (0..n).all(|i| char_at(i) == char_at(len - 1 - i)),
+        Symmetry::Either => {
+            matches_one(char_at, len, n, Symmetry::Equal)
+                || matches_one(char_at, len, n, Symmetry::Reverse)
+        }
+        Symmetry::Middle => {
+            let m = middle_start_in(len, n);
+            (0..n).all(|i| char_at(i) == char_at(m + i))
+        }
+        Symmetry::Rotation => {
+            (1..n).any(|k| (0..n).all(|j| char_at((j + k) % n) == char_at(last + j)))
+        }
+        Symmetry::Palindrome => (0..len / 2).all(|i| char_at(i) == char_at(len - 1 - i)),
+    }
+}
+
+/// Whether any of `symmetries` holds on raw public key bytes
+///
+/// Byte-aligned cases compare whole bytes (a reversed byte is its nibbles
+/// swapped); the rest fall back to nibble reads without allocating.
+#[inline(always)]
+pub fn matches_bytes(public_bytes: &[u8; 32], n: usize, symmetries: &[Symmetry]) -> bool {
+    n * 2 <= KEY_NIBBLES
+        && symmetries
+            .iter()
+            .any(|&s| matches_bytes_one(public_bytes, n, s))
+}
+
+#[inline(always)]
+fn matches_bytes_one(b: &[u8; 32], n: usize, symmetry: Symmetry) -> bool {
+    let half = n / 2;
+    match symmetry {
+        Symmetry::Equal if n.is_multiple_of(2) => b[..half] == b[32 - half..],
+        Symmetry::Reverse if n.is_multiple_of(2) => {
+            (0..half).all(|i| b[i] == b[31 - i].rotate_left(4))
+        }
+        Symmetry::Either if n.is_multiple_of(2) => {
+            b[..half] == b[32 - half..] || (0..half).all(|i| b[i] == b[31 - i].rotate_left(4))
+        }
+        // The middle starts on a byte boundary when N is a multiple of 4
+        Symmetry::Middle if n.is_multiple_of(4) => b[..half] == b[16 - n / 4..16 + n / 4],
+        Symmetry::Palindrome => (0..16).all(|i| b[i] == b[31 - i].rotate_left(4)),
+        _ => matches_one(&|i| key_nibble(b, i), KEY_NIBBLES, n, symmetry),
+    }
+}
+
+/// Probability that a random key matches any of `symmetries` (exact for the
+/// lengths the CLI accepts)
+pub fn probability(n: usize, symmetries: &[Symmetry]) -> f64 {
+    probability_given(n, symmetries, &[])
+}
+
+/// Probability that any of `symmetries` holds for a key that starts with
+/// the nibbles `fixed` (e.g. a `--prefix`): exact by inclusion–exclusion up
+/// to `MAX_EXACT_SYSTEMS` systems, the union bound beyond
+pub fn probability_given(n: usize, symmetries: &[Symmetry], fixed: &[u8]) -> f64 {
+    let systems = distinct_systems(n, symmetries);
+    if systems.len() > MAX_EXACT_SYSTEMS {
+        let bound: f64 = systems
+            .iter()
+            .map(|s| all_hold(&[s.as_slice()], fixed))
+            .sum();
+        return bound.min(1.0);
+    }
+
+    // Inclusion–exclusion over every non-empty subset of systems
+    let mut total = 0.0;
+    for subset in 1u32..(1 << systems.len()) {
+        let chosen: Vec<&[(usize, usize)]> = systems
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| subset & (1 << index) != 0)
+            .map(|(_, system)| system.as_slice())
+            .collect();
+        let sign = if subset.count_ones() % 2 == 1 {
+            1.0
+        } else {
+            -1.0
+        };
+        total += sign * all_hold(&chosen, fixed);
+    }
+    total
+}
+
+/// Probability that every one of `systems` holds for a key starting with `fixed`
+fn all_hold(systems: &[&[(usize, usize)]], fixed: &[u8]) -> f64 {
+    let mut parent: Vec<usize> = (0..KEY_NIBBLES).collect();
+    for &(a, b) in systems.iter().copied().flatten() {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        parent[ra] = rb;
+    }
+
+    // Each free nibble tied to another nibble costs a factor of 16;
+    // two different fixed nibbles tied together rule the systems out
+    let mut root_value: Vec<Option<u8>> = vec![None; KEY_NIBBLES];
+    let mut root_size = [0i32; KEY_NIBBLES];
+    for (i, &value) in fixed.iter().enumerate().take(KEY_NIBBLES) {
+        let root = find(&mut parent, i);
+        match root_value[root] {
+            Some(v) if v != value => return 0.0,
+            _ => root_value[root] = Some(value),
+        }
+    }
+    let mut ties = 0;
+    for i in 0..KEY_NIBBLES {
+        root_size[find(&mut parent, i)] += 1;
+    }
+    for (root, &size) in root_size.iter().enumerate().filter(|(_, &k)| k > 1) {
+        let pinned = (0..fixed.len().min(KEY_NIBBLES))
+            .filter(|&i| find(&mut parent, i) == root)
+            .count() as i32;
+        ties += if pinned > 0 { size - pinned } else { size - 1 };
+    }
+    16f64.powi(-ties)
+}
+
+/// Make `nibbles` satisfy one randomly chosen system of `symmetries`,
+/// copying from the first N characters outwards
+pub fn impose(
+    nibbles: &mut [u8; KEY_NIBBLES],
+    n: usize,
+    symmetries: &[Symmetry],
+    rng: &mut impl rand::Rng,
+) {
+    let systems = distinct_systems(n, symmetries);
+    if systems.is_empty() {
+        return;
+    }
+    for &(a, b) in &systems[rng.gen_range(0..systems.len())] {
+        nibbles[b] = nibbles[a];
+    }
+}
+
+fn distinct_systems(n: usize, symmetries: &[Symmetry]) -> Vec<Vec<(usize, usize)>> {
+    let mut systems: Vec<Vec<(usize, usize)>> = Vec::new();
+    for s in symmetries {
+        for system in s.systems(n) {
+            if !systems.contains(&system) {
+                systems.push(system);
+            }
+        }
+    }
+    systems
+}
+
+fn find(parent: &mut [usize], mut x: usize) -> usize {
+    while parent[x] != x {
+        parent[x] = parent[parent[x]];
+        x = parent[x];
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::key_bytes;
+
+    fn both_paths(hex: &str, n: usize, symmetries: &[Symmetry]) -> bool {
+        let nibbles: Vec<u8> = hex.chars().map(|c| c.to_digit(16).unwrap() as u8).collect();
+        let by_nibbles = matches_chars(|i| nibbles[i], 64, n, symmetries);
+        assert_eq!(
+            by_nibbles,
+            matches_bytes(&key_bytes(hex), n, symmetries),
+            "{}",
+            hex
+        );
+        by_nibbles
+    }
+
+    #[test]
+    fn test_each_mode_on_both_paths() {
+        let fill = |len: usize| "12".repeat(32)[..len].to_string();
+
+        // Byte-aligned fast paths (N = 4)
+        let equal = format!("ABCD{}ABCD", fill(56));
+        let reverse = format!("ABCD{}DCBA", fill(56));
+        let rotated = format!("ABCD{}CDAB", fill(56));
+        let middle = format!("ABCD{}ABCD{}", fill(26), fill(30));
+        assert!(both_paths(&equal, 4, &[Symmetry::Equal]));
+        assert!(!both_paths(&equal, 4, &[Symmetry::Reverse]));
+        assert!(both_paths(&reverse, 4, &[Symmetry::Reverse]));
+        assert!(!both_paths(&reverse, 4, &[Symmetry::Equal]));
+        assert!(both_paths(&reverse, 4, &[Symmetry::Either]));
+        assert!(both_paths(&middle, 4, &[Symmetry::Middle]));
+        assert!(both_paths(&rotated, 4, &[Symmetry::Rotation]));
+        assert!(!both_paths(&equal, 4, &[Symmetry::Rotation]));
+        assert!(both_paths(
+            &rotated,
+            4,
+            &[Symmetry::Middle, Symmetry::Rotation]
+        ));
+        assert!(!both_paths(
+            &rotated,
+            4,
+            &[Symmetry::Either, Symmetry::Middle]
+        ));
+
+        // Odd N goes through nibble reads
+        let equal = format!("ABC{}ABC", fill(58));
+        let reverse = format!("ABC{}CBA", fill(58));
+        let middle = format!("ABC{}ABC{}", fill(27), fill(31));
+        assert!(both_paths(&equal, 3, &[Symmetry::Equal]));
+        assert!(!both_paths(&equal, 3, &[Symmetry::Reverse]));
+        assert!(both_paths(&reverse, 3, &[Symmetry::Either]));
+        assert!(both_paths(&middle, 3, &[Symmetry::Middle]));
+
+        let palindrome = format!(
+            "{}{}",
+            "0123456789ABCDEF".repeat(2),
+            "FEDCBA9876543210".repeat(2)
+        );
+        assert!(both_paths(&palindrome, 2, &[Symmetry::Palindrome]));
+        assert!(!both_paths(&reverse, 2, &[Symmetry::Palindrome]));
+    }
+
+    #[test]
+    fn test_exact_probabilities() {
+        let close = |a: f64, b: f64| (a - b).abs() <= b * 1e-12;
+        for n in 2..=8 {
+            let single = 16f64.powi(-(n as i32));
+            assert!(close(probability(n, &[Symmetry::Equal]), single));
+            assert!(close(probability(n, &[Symmetry::Middle]), single));
+            // Both hold only when the last N is itself a palindrome
+            let both = 16f64.powi(-((n + n / 2) as i32));
+            assert!(close(
+                probability(n, &[Symmetry::Either]),
+                2.0 * single - both
+            ));
+            assert!(close(
+                probability(n, &[Symmetry::Equal, Symmetry::Reverse]),
+                probability(n, &[Symmetry::Either])
+            ));
+        }
+        // Strings of 4 with primitive period 1, 2 and 4 have 1, 2 and 3 other rotations
+        let rotations = 16.0 + 240.0 * 2.0 + 65280.0 * 3.0;
+        assert!(close(
+            probability(4, &[Symmetry::Rotation]),
+            rotations / 16f64.powi(8)
+        ));
+        assert!(close(
+            probability(2, &[Symmetry::Palindrome]),
+            16f64.powi(-32)
+        ));
+
+        // A fixed prefix: its own nibbles no longer cost anything
+        let prefix = [0xA, 0xB];
+        assert!(close(
+            probability_given(4, &[Symmetry::Either], &prefix),
+            probability(4, &[Symmetry::Either])
+        ));
+        let zeros = [0u8; 40];
+        assert!(close(
+            probability_given(2, &[Symmetry::Palindrome], &zeros),
+            16f64.powi(-24)
+        ));
+        let mut broken = zeros;
+        broken[39] = 1; // mirrors position 24
+        assert_eq!(probability_given(2, &[Symmetry::Palindrome], &broken), 0.0);
+
+        // Too many systems for inclusion–exclusion: the union bound instead
+        let every = [
+            Symmetry::Equal,
+            Symmetry::Reverse,
+            Symmetry::Middle,
+            Symmetry::Rotation,
+            Symmetry::Palindrome,
+        ];
+        let bound = probability(32, &every);
+        assert!(bound > 0.0 && bound < 1e-30);
+
+        let mut rng = rand::thread_rng();
+        let mut nibbles = [7u8; KEY_NIBBLES];
+        nibbles[..4].copy_from_slice(&[1, 2, 3, 4]);
+        impose(&mut nibbles, 4, &[Symmetry::Rotation], &mut rng);
+        assert!(matches_chars(
+            |i| nibbles[i],
+            KEY_NIBBLES,
+            4,
+            &[Symmetry::Rotation]
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+//! Linux CPU Topology
+//!
+//! Reads what the kernel exposes about the CPUs this process may actually use:
+//! - online CPUs, narrowed to the cgroup v2 cpuset (`cpuset.cpus.effective`)
+//! - the cgroup v2 `cpu.max` quota, tightest along the cgroup's ancestors
+//! - Intel hybrid performance/efficiency cores from `/sys/devices/cpu_core`
+//!   and `/sys/devices/cpu_atom`
+//! - SMT siblings from each CPU's `topology/thread_siblings_list`
+//!
+//! Everything is read relative to a root directory so tests can use a fake
+//! tree. When the files are missing (other platforms, minimal containers) the
+//! counts fall back to `num_cpus`.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What this process can run on
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuTopology {
+    /// Usable logical CPU ids (online ∩ cpuset)
+    pub cpus: Vec<usize>,
+    /// Physical cores among `cpus` (SMT siblings counted once)
+    pub physical_cores: usize,
+    /// Usable performance-core CPU ids on hybrid parts (empty otherwise)
+    pub perf_cpus: Vec<usize>,
+    /// Usable efficiency-core CPU ids on hybrid parts (empty otherwise)
+    pub efficiency_cpus: Vec<usize>,
+    /// cgroup `cpu.max` quota in CPUs, if limited
+    pub quota: Option<f64>,
+}
+
+impl CpuTopology {
+    /// Topology of the running system
+    pub fn detect() -> Self {
+        Self::detect_from(Path::new("/"))
+    }
+
+    /// Topology as seen under `root` (which contains `sys/` and `proc/`)
+    pub fn detect_from(root: &Path) -> Self {
+        let cpu_dir = root.join("sys/devices/system/cpu");
+        let cgroup = cgroup_dir(root);
+
+        let mut cpus: BTreeSet<usize> = read_cpu_list(&cpu_dir.join("online"))
+            .unwrap_or_else(|| (0..num_cpus::get()).collect())
+            .into_iter()
+            .collect();
+        if let Some(cpuset) = cgroup.as_deref().and_then(effective_cpuset) {
+            let allowed: BTreeSet<usize> = cpuset.into_iter().collect();
+            let narrowed: BTreeSet<usize> = cpus.intersection(&allowed).copied().collect();
+            if !narrowed.is_empty() {
+                cpus = narrowed;
+            }
+        }
+
+        let usable = |path: PathBuf| -> Vec<usize> {
+            read_cpu_list(&path)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|c| cpus.contains(c))
+                .collect()
+        };
+        let perf_cpus = usable(root.join("sys/devices/cpu_core/cpus"));
+        let efficiency_cpus = usable(root.join("sys/devices/cpu_atom/cpus"));
+
+        // One sibling set per physical core; CPUs without topology info count alone
+        let sibling_sets: BTreeSet<Vec<usize>> = cpus
+            .iter()
+            .map(|&cpu| {
+                read_cpu_list(&cpu_dir.join(format!("cpu{}/topology/thread_siblings_list", cpu)))
+                    .unwrap_or_else(|| vec![cpu])
+            })
+            .collect();
+        let physical_cores = if sibling_sets.is_empty() {
+            num_cpus::get_physical()
+        } else {
+            sibling_sets.len()
+        };
+
+        Self {
+            cpus: cpus.into_iter().collect(),
+            physical_cores,
+            perf_cpus,
+            efficiency_cpus,
+            quota: cgroup.as_deref().and_then(cpu_quota),
+        }
+    }
+
+    /// Intel hybrid part with both core types usable
+    pub fn is_hybrid(&self) -> bool {
+        !self.perf_cpus.is_empty() && !self.efficiency_cpus.is_empty()
+    }
+
+    pub fn has_smt(&self) -> bool {
+        self.physical_cores < self.cpus.len()
+    }
+
+    /// CPUs worth of work this process can actually get: usable CPUs, capped
+    /// by the quota (rounded up, at least 1)
+    pub fn available(&self) -> usize {
+        let cpus = self.cpus.len().max(1);
+        match self.quota {
+            Some(quota) => cpus.min(quota.ceil().max(1.0) as usize),
+            None => cpus,
+        }
+    }
+
+    /// One-line summary of anything beyond a plain CPU count
+    pub fn describe(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.is_hybrid() {
+            parts.push(format!(
+                "{} performance + {} efficiency CPUs",
+                self.perf_cpus.len(),
+                self.efficiency_cpus.len()
+            ));
+        }
+        if self.has_smt() {
+            parts.push(format!("SMT ({} physical cores)", self.physical_cores));
+        }
+        if let Some(quota) = self.quota {
+            parts.push(format!("cgroup quota {:.1} CPUs", quota));
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+/// Parse a kernel CPU list such as `0-3,8,10-11`
+pub fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = BTreeSet::new();
+    for item in list.trim().split(',').filter(|i| !i.is_empty()) {
+        match item.split_once('-') {
+            Some((from, to)) => {
+                let (from, to): (usize, usize) = (from.parse().ok()?, to.parse().ok()?);
+                if from > to {
+                    return None;
+                }
+                cpus.extend(from..=to);
+            }
+            None => {
+                cpus.insert(item.parse().ok()?);
+            }
+        }
+    }
+    Some(cpus.into_iter().collect())
+}
+
+fn read_cpu_list(path: &Path) -> Option<Vec<usize>> {
+    let cpus = parse_cpu_list(&fs::read_to_string(path).ok()?)?;
+    (!cpus.is_empty()).then_some(cpus)
+}
+
+/// This process's cgroup v2 directory, from `proc/self/cgroup`
+fn cgroup_dir(root: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(root.join("proc/self/cgroup")).ok()?;
+    let path = content.lines().find_map(|line| line.strip_prefix("0::"))?;
+    let dir = root
+        .join("sys/fs/cgroup")
+        .join(path.trim().trim_start_matches('/'));
+    dir.is_dir().then_some(dir)
+}
+
+/// Directories from the process's cgroup up to the cgroup root
+fn cgroup_ancestors(dir: &Path) -> impl Iterator<Item = &Path> {
+    dir.ancestors()
+        .take_while(|d| d.join("cgroup.controllers").exists() || d.join("cpu.max").exists())
+}
+
+/// First `cpuset.cpus.effective` found walking up from the cgroup
+fn effective_cpuset(dir: &Path) -> Option<Vec<usize>> {
+    cgroup_ancestors(dir).find_map(|d| read_cpu_list(&d.join("cpuset.cpus.effective")))
+}
+
+/// Tightest `cpu.max` quota (`<quota> <period>`) along the cgroup's ancestors
+fn cpu_quota(dir: &Path) -> Option<f64> {
+    cgroup_ancestors(dir)
+        .filter_map(|d| {
+            let content = fs::read_to_string(d.join("cpu.max")).ok()?;
+            let mut fields = content.split_whitespace();
+            let quota: f64 = fields.next()?.parse().ok()?; // "max" fails to parse
+            let period: f64 = fields.next()?.parse().ok()?;
+            (period > 0.0).then(|| quota / period)
+        })
+        .reduce(f64::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(
+            parse_cpu_list("0-3,8,10-11\n"),
+            Some(vec![0, 1, 2, 3, 8, 10, 11])
+        );
+        assert_eq!(parse_cpu_list("5"), Some(vec![5]));
+        assert_eq!(parse_cpu_list("3-1"), None);
+        assert_eq!(parse_cpu_list("a"), None);
+    }
+
+    #[test]
+    fn test_hybrid_smt_tree() {
+        // 2 hyperthreaded P-cores (CPUs 0-3) and 4 E-cores (CPUs 4-7)
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(root, "sys/devices/system/cpu/online", "0-7\n");
+        write(root, "sys/devices/cpu_core/cpus", "0-3\n");
+        write(root, "sys/devices/cpu_atom/cpus", "4-7\n");
+        for cpu in 0..8 {
+            let siblings = match cpu {
+                0 | 1 => "0-1".to_string(),
+                2 | 3 => "2-3".to_string(),
+                _ => cpu.to_string(),
+            };
+            write(
+                root,
+                &format!(
+                    "sys/devices/system/cpu/cpu{}/topology/thread_siblings_list",
+                    cpu
+                ),
+                &siblings,
+            );
+        }
+
+        let topology = CpuTopology::detect_from(root);
+        assert!(topology.is_hybrid());
+        assert_eq!(topology.perf_cpus, vec![0, 1, 2, 3]);
+        assert_eq!(topology.efficiency_cpus.len(), 4);
+        assert_eq!(topology.physical_cores, 6);
+        assert!(topology.has_smt());
+        assert_eq!(topology.available(), 8);
+        assert!(topology
+            .describe()
+            .unwrap()
+            .contains("4 performance + 4 efficiency"));
+    }
+
+    #[test]
+    fn test_cgroup_cpuset_and_quota() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(root, "sys/devices/system/cpu/online", "0-15\n");
+        write(root, "proc/self/cgroup", "0::/docker/abc\n");
+        write(root, "sys/fs/cgroup/cgroup.controllers", "cpuset cpu\n");
+        write(
+            root,
+            "sys/fs/cgroup/docker/cgroup.controllers",
+            "cpuset cpu\n",
+        );
+        write(root, "sys/fs/cgroup/docker/cpu.max", "400000 100000\n");
+        write(
+            root,
+            "sys/fs/cgroup/docker/abc/cgroup.controllers",
+            "cpuset cpu\n",
+        );
+        write(root, "sys/fs/cgroup/docker/abc/cpu.max", "250000 100000\n");
+        write(
+            root,
+            "sys/fs/cgroup/docker/abc/cpuset.cpus.effective",
+            "2-7\n",
+        );
+
+        let topology = CpuTopology::detect_from(root);
+        assert_eq!(topology.cpus, vec![2, 3, 4, 5, 6, 7]);
+        assert_eq!(topology.quota, Some(2.5));
+        assert_eq!(topology.available(), 3);
+        assert!(!topology.is_hybrid());
+
+        // Unlimited quota
+        write(root, "sys/fs/cgroup/docker/abc/cpu.max", "max 100000\n");
+        write(root, "sys/fs/cgroup/docker/cpu.max", "max 100000\n");
+        assert_eq!(CpuTopology::detect_from(root).available(), 6);
+    }
+}
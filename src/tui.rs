@@ -0,0 +1,540 @@
+//! Full-Screen Dashboard for `--beautiful`
+//!
+//! Multi-panel terminal UI drawn with ratatui: per-core rate bars (from the
+//! main loop's sliding window), a rate-over-time sparkline, probability and
+//! luck gauges and a scrolling list of found keys with the matched nibbles
+//! highlighted. Keyboard input is polled between refreshes and returned to
+//! the main loop as `DashboardAction`s.
+
+use std::collections::VecDeque;
+use std::io;
+use std::ops::Range;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Bar, BarChart, BarGroup, Block, Gauge, List, ListItem, Paragraph, Sparkline,
+};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::format_compact_f64;
+use crate::worker::PoolCommand;
+
+/// Rate samples kept for the sparkline
+const RATE_HISTORY: usize = 240;
+
+/// Found keys kept for the list
+const MAX_KEYS: usize = 500;
+
+/// CPU cap change per `[` / `]` press, in percent
+const CPU_STEP: u8 = 10;
+
+/// What the user asked for from the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DashboardAction {
+    Pool(PoolCommand),
+    Quit,
+}
+
+/// One refresh worth of statistics from the main loop
+pub struct DashboardStats<'a> {
+    pub pattern: &'a str,
+    pub mode: &'a str,
+    pub elapsed: Duration,
+    pub attempts: u64,
+    pub total_rate: f64,
+    pub gpu_rate: f64,
+    /// Sliding-window average rate per CPU worker
+    pub core_rates: &'a [f64],
+    /// Index of the first performance core when hybrid colouring applies
+    pub perf_core_start: Option<usize>,
+    pub found: usize,
+    pub target: usize,
+    /// Probability that one attempt matches
+    pub probability: f64,
+    /// Attempts since the last key was found
+    pub attempts_since_last: u64,
+    pub eta_seconds: f64,
+    pub paused: bool,
+    /// CPU cap in percent (100 = uncapped)
+    pub cpu_percent: u8,
+    /// Latest governor, schedule or warning message, shown next to the status
+    pub notice: Option<&'a str>,
+}
+
+struct FoundKey {
+    index: usize,
+    public_hex: String,
+    highlight: Vec<Range<usize>>,
+}
+
+/// Keyboard state kept between polls
+#[derive(Debug)]
+struct Controls {
+    /// Found keys scrolled past
+    scroll: usize,
+    /// CPU cap from the last refresh; `[` and `]` step from here
+    cpu_percent: u8,
+}
+
+impl Controls {
+    /// Translate one key press, given the length of the found-key list
+    fn handle(&mut self, key: KeyEvent, keys: usize) -> Option<DashboardAction> {
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+        let action = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => DashboardAction::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                DashboardAction::Quit
+            }
+            KeyCode::Char('p') | KeyCode::Char(' ') => {
+                DashboardAction::Pool(PoolCommand::TogglePause)
+            }
+            KeyCode::Char('r') => DashboardAction::Pool(PoolCommand::Resume),
+            KeyCode::Char('+') | KeyCode::Char('a') => {
+                DashboardAction::Pool(PoolCommand::AddWorker)
+            }
+            KeyCode::Char('-') | KeyCode::Char('x') => {
+                DashboardAction::Pool(PoolCommand::RemoveWorker)
+            }
+            KeyCode::Char('[') => {
+                self.cpu_percent = self.cpu_percent.saturating_sub(CPU_STEP).max(CPU_STEP);
+                DashboardAction::Pool(PoolCommand::SetCpuPercent(self.cpu_percent))
+            }
+            KeyCode::Char(']') => {
+                self.cpu_percent = (self.cpu_percent + CPU_STEP).min(100);
+                DashboardAction::Pool(PoolCommand::SetCpuPercent(self.cpu_percent))
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.scroll = (self.scroll + 1).min(keys.saturating_sub(1));
+                return None;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.scroll = self.scroll.saturating_sub(1);
+                return None;
+            }
+            _ => return None,
+        };
+        Some(action)
+    }
+}
+
+/// Terminal dashboard; the terminal is restored when it is dropped
+pub struct Dashboard {
+    terminal: DefaultTerminal,
+    rate_history: VecDeque<u64>,
+    keys: VecDeque<FoundKey>,
+    controls: Controls,
+}
+
+impl Dashboard {
+    /// Switch to the alternate screen in raw mode
+    pub fn start() -> io::Result<Self> {
+        Ok(Self {
+            terminal: ratatui::try_init()?,
+            rate_history: VecDeque::with_capacity(RATE_HISTORY),
+            keys: VecDeque::new(),
+            controls: Controls {
+                scroll: 0,
+                cpu_percent: 100,
+            },
+        })
+    }
+
+    /// Add a found key to the top of the list
+    pub fn record_key(&mut self, index: usize, public_hex: &str, highlight: Vec<Range<usize>>) {
+        self.keys.push_front(FoundKey {
+            index,
+            public_hex: public_hex.to_uppercase(),
+            highlight,
+        });
+        self.keys.truncate(MAX_KEYS);
+    }
+
+    /// Redraw every panel
+    pub fn draw(&mut self, stats: &DashboardStats) -> io::Result<()> {
+        if self.rate_history.len() == RATE_HISTORY {
+            self.rate_history.pop_front();
+        }
+        self.rate_history.push_back(stats.total_rate as u64);
+        self.controls.cpu_percent = stats.cpu_percent;
+
+        let history: Vec<u64> = self.rate_history.iter().copied().collect();
+        let keys = &self.keys;
+        let scroll = self.controls.scroll.min(keys.len().saturating_sub(1));
+        self.terminal
+            .draw(|frame| render(frame, stats, &history, keys, scroll))?;
+        Ok(())
+    }
+
+    /// Wait up to `timeout` for keyboard input and translate it into actions
+    pub fn poll_actions(&mut self, timeout: Duration) -> io::Result<Vec<DashboardAction>> {
+        let mut actions = Vec::new();
+        let mut wait = timeout;
+        while event::poll(wait)? {
+            wait = Duration::ZERO;
+            if let Event::Key(key) = event::read()? {
+                actions.extend(self.controls.handle(key, self.keys.len()));
+            }
+        }
+        Ok(actions)
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+fn render(
+    frame: &mut Frame,
+    stats: &DashboardStats,
+    history: &[u64],
+    keys: &VecDeque<FoundKey>,
+    scroll: usize,
+) {
+    let [header, charts, gauges, list, footer] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Length(12),
+        Constraint::Length(3),
+        Constraint::Min(4),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    render_header(frame, header, stats);
+
+    let [cores, sparkline] =
+        Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(charts);
+    render_cores(frame, cores, stats);
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::bordered().title(format!(
+                " Rate over time (peak {}/s) ",
+                format_compact_f64(history.iter().copied().max().unwrap_or(0) as f64)
+            )))
+            .data(
+                history
+                    .iter()
+                    .rev()
+                    .take(sparkline.width.saturating_sub(2) as usize)
+                    .rev()
+                    .copied(),
+            )
+            .style(Style::default().fg(Color::Cyan)),
+        sparkline,
+    );
+
+    render_gauges(frame, gauges, stats);
+    render_keys(frame, list, keys, scroll);
+
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(" p", Style::default().fg(Color::Yellow)),
+            Span::raw(" pause/resume  "),
+            Span::styled("r", Style::default().fg(Color::Yellow)),
+            Span::raw(" resume  "),
+            Span::styled("+", Style::default().fg(Color::Yellow)),
+            Span::raw(" add worker  "),
+            Span::styled("-", Style::default().fg(Color::Yellow)),
+            Span::raw(" remove worker  "),
+            Span::styled("[/]", Style::default().fg(Color::Yellow)),
+            Span::raw(" CPU cap  "),
+            Span::styled("↑/↓", Style::default().fg(Color::Yellow)),
+            Span::raw(" scroll keys  "),
+            Span::styled("q", Style::default().fg(Color::Yellow)),
+            Span::raw(" quit"),
+        ])),
+        footer,
+    );
+}
+
+fn render_header(frame: &mut Frame, area: Rect, stats: &DashboardStats) {
+    let status = if stats.paused {
+        Span::styled(
+            "PAUSED",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    } else {
+        Span::styled(
+            "RUNNING",
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        )
+    };
+    let eta = if stats.eta_seconds.is_finite() {
+        format_duration(stats.eta_seconds)
+    } else {
+        "∞".to_string()
+    };
+    let secs = stats.elapsed.as_secs();
+
+    let mut first = vec![
+        status,
+        Span::raw(format!(
+            "  {}  {}  │  {} workers  │  elapsed {:02}:{:02}:{:02}",
+            stats.mode,
+            stats.pattern,
+            stats.core_rates.len(),
+            secs / 3600,
+            (secs / 60) % 60,
+            secs % 60
+        )),
+    ];
+    if stats.cpu_percent < 100 {
+        first.push(Span::styled(
+            format!("  │  CPU cap {}%", stats.cpu_percent),
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+    if let Some(notice) = stats.notice {
+        first.push(Span::styled(
+            format!("  │  {}", notice),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+
+    let lines = vec![
+        Line::from(first),
+        Line::from(vec![
+            Span::styled(
+                format!("{}/s", format_compact_f64(stats.total_rate)),
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                "  │  {} attempts  │  found {}/{}  │  GPU {}/s  │  ETA {}",
+                format_compact_f64(stats.attempts as f64),
+                stats.found,
+                stats.target,
+                format_compact_f64(stats.gpu_rate),
+                eta
+            )),
+        ]),
+    ];
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(" MeshCore Vanity Key Generator ")),
+        area,
+    );
+}
+
+fn render_cores(frame: &mut Frame, area: Rect, stats: &DashboardStats) {
+    let bars: Vec<Bar> = stats
+        .core_rates
+        .iter()
+        .enumerate()
+        .map(|(i, rate)| {
+            // Hybrid colouring: performance cores red, efficiency cores green
+            let color = match stats.perf_core_start {
+                Some(start) if i >= start => Color::Red,
+                _ => Color::Green,
+            };
+            Bar::default()
+                .value(*rate as u64)
+                .text_value(format_compact_f64(*rate))
+                .label(Line::from(format!("c{:02}", i + 1)))
+                .style(Style::default().fg(color))
+        })
+        .collect();
+
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let count = bars.len().max(1);
+    let bar_width = (inner_width / count).saturating_sub(1).clamp(1, 6) as u16;
+    frame.render_widget(
+        BarChart::default()
+            .block(Block::bordered().title(" Per-core rate "))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(bar_width)
+            .bar_gap(1),
+        area,
+    );
+}
+
+fn render_gauges(frame: &mut Frame, area: Rect, stats: &DashboardStats) {
+    let [progress, probability, luck] = Layout::horizontal([
+        Constraint::Ratio(1, 3),
+        Constraint::Ratio(1, 3),
+        Constraint::Ratio(1, 3),
+    ])
+    .areas(area);
+
+    let done = if stats.target > 0 {
+        (stats.found as f64 / stats.target as f64).min(1.0)
+    } else {
+        0.0
+    };
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title(" Progress "))
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio(done)
+            .label(format!("{}/{}", stats.found, stats.target)),
+        progress,
+    );
+
+    // Chance that the next key would have turned up by now
+    let chance = 1.0 - (-stats.probability * stats.attempts_since_last as f64).exp();
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title(" Chance of next key by now "))
+            .gauge_style(Style::default().fg(Color::Magenta))
+            .ratio(chance.clamp(0.0, 1.0))
+            .label(format!("{:.1}%", chance * 100.0)),
+        probability,
+    );
+
+    // Luck: expected attempts for the keys found so far vs attempts actually spent
+    let luck_factor = if stats.attempts > 0 && stats.probability > 0.0 {
+        stats.found as f64 / (stats.probability * stats.attempts as f64)
+    } else {
+        0.0
+    };
+    let color = if luck_factor >= 1.0 {
+        Color::Green
+    } else {
+        Color::Yellow
+    };
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title(" Luck (1.0 = average) "))
+            .gauge_style(Style::default().fg(color))
+            .ratio((luck_factor / 2.0).clamp(0.0, 1.0))
+            .label(format!("{:.2}x", luck_factor)),
+        luck,
+    );
+}
+
+fn render_keys(frame: &mut Frame, area: Rect, keys: &VecDeque<FoundKey>, scroll: usize) {
+    let items: Vec<ListItem> = keys
+        .iter()
+        .skip(scroll)
+        .map(|key| ListItem::new(key_line(key)))
+        .collect();
+
+    frame.render_widget(
+        List::new(items).block(Block::bordered().title(format!(" Found keys ({}) ", keys.len()))),
+        area,
+    );
+}
+
+/// One found key with its highlighted nibbles in bold yellow
+fn key_line(key: &FoundKey) -> Line<'static> {
+    let mut spans = vec![Span::styled(
+        format!("#{:<4} ", key.index),
+        Style::default().fg(Color::DarkGray),
+    )];
+    for (i, c) in key.public_hex.char_indices() {
+        let style = if key.highlight.iter().any(|r| r.contains(&i)) {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        spans.push(Span::styled(c.to_string(), style));
+    }
+    spans.push(Span::styled(
+        format!("  node {}", &key.public_hex[..2]),
+        Style::default().fg(Color::Magenta),
+    ));
+    Line::from(spans)
+}
+
+fn format_duration(seconds: f64) -> String {
+    let s = seconds.round() as u64;
+    match s {
+        0..=59 => format!("{}s", s),
+        60..=3599 => format!("{}m {:02}s", s / 60, s % 60),
+        3600..=86_399 => format!("{}h {:02}m", s / 3600, (s / 60) % 60),
+        _ => format!("{}d {:02}h", s / 86_400, (s / 3600) % 24),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_key_handling() {
+        let mut controls = Controls {
+            scroll: 0,
+            cpu_percent: 100,
+        };
+        let mut handle = |key| controls.handle(key, 3);
+        assert_eq!(
+            handle(press(KeyCode::Char('q'))),
+            Some(DashboardAction::Quit)
+        );
+        assert_eq!(
+            handle(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(DashboardAction::Quit)
+        );
+        assert_eq!(handle(press(KeyCode::Char('c'))), None);
+        assert_eq!(
+            handle(press(KeyCode::Char(' '))),
+            Some(DashboardAction::Pool(PoolCommand::TogglePause))
+        );
+        assert_eq!(
+            handle(press(KeyCode::Char('+'))),
+            Some(DashboardAction::Pool(PoolCommand::AddWorker))
+        );
+
+        // The CPU cap steps by 10% and stays within 10-100%
+        assert_eq!(
+            handle(press(KeyCode::Char(']'))),
+            Some(DashboardAction::Pool(PoolCommand::SetCpuPercent(100)))
+        );
+        for _ in 0..12 {
+            handle(press(KeyCode::Char('[')));
+        }
+        assert_eq!(
+            handle(press(KeyCode::Char(']'))),
+            Some(DashboardAction::Pool(PoolCommand::SetCpuPercent(20)))
+        );
+
+        // Scrolling stops at the last key and releases are ignored
+        for _ in 0..5 {
+            assert_eq!(handle(press(KeyCode::Down)), None);
+        }
+        let mut release = press(KeyCode::Char('q'));
+        release.kind = KeyEventKind::Release;
+        assert_eq!(handle(release), None);
+        assert_eq!(controls.scroll, 2);
+        controls.handle(press(KeyCode::Char('k')), 3);
+        assert_eq!(controls.scroll, 1);
+    }
+
+    #[test]
+    fn test_key_line_highlights_ranges() {
+        let key = FoundKey {
+            index: 7,
+            public_hex: format!("AB{}EF", "0".repeat(60)),
+            highlight: vec![0..2, 62..64],
+        };
+        let line = key_line(&key);
+        let bold: String = line
+            .spans
+            .iter()
+            .filter(|s| s.style.add_modifier.contains(Modifier::BOLD))
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(bold, "ABEF");
+        assert_eq!(line.spans[1].style.fg, Some(Color::Yellow));
+        assert_eq!(line.spans[3].style.fg, Some(Color::DarkGray));
+        assert_eq!(line.spans.last().unwrap().content, "  node AB");
+        assert_eq!(format_duration(3725.0), "1h 02m");
+    }
+}
@@ -3,14 +3,19 @@
 //! Manages multiple worker threads that generate and check keys in parallel.
 //! Supports both CPU and Metal GPU acceleration.
 
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 use crossbeam_channel::Sender;
 
+use crate::affinity::ThreadPolicy;
+use crate::inventory::NodeIdSet;
 use crate::keygen::{self, KeyInfo};
 use crate::pattern::{matches_pattern_bytes, PatternConfig};
+use crate::plugin::{Plugin, PluginInstance};
+use crate::score::{BestKeys, ScoreKind, ScoredKey, TopN};
 
 #[cfg(target_os = "macos")]
 use crate::metal_gpu;
@@ -18,6 +23,45 @@ use crate::metal_gpu;
 /// Batch size for key generation (number of keys per batch)
 const BATCH_SIZE: usize = 10_000;
 
+/// Keys generated between duty-cycle checks when `--cpu-percent` is set
+const DUTY_CHECK_KEYS: usize = 256;
+
+/// Longest busy stretch before a duty-cycled worker sleeps
+const DUTY_SLICE: Duration = Duration::from_millis(100);
+
+/// Duty cycle meaning "never sleep", in per-mille
+const FULL_DUTY: u32 = 1000;
+
+/// Most CPU workers per logical CPU a running pool grows to
+const MAX_WORKERS_PER_CPU: usize = 4;
+
+/// Most CPU workers a running pool grows to, whoever asks
+pub fn max_workers() -> usize {
+    num_cpus::get() * MAX_WORKERS_PER_CPU
+}
+
+/// Runtime changes to a running pool, sent by the dashboard, the HTTP control
+/// endpoint or power policies and applied by the main loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolCommand {
+    AddWorker,
+    RemoveWorker,
+    SetWorkers(usize),
+    Pause,
+    Resume,
+    TogglePause,
+    /// Target share of the whole machine's CPU, 1-100
+    SetCpuPercent(u8),
+}
+
+/// Control handle for one CPU worker thread
+struct WorkerHandle {
+    attempts: Arc<AtomicU64>,
+    // Asks just this worker to exit
+    retire: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
 /// Worker pool manages parallel key generation
 pub struct WorkerPool {
     num_workers: usize,
@@ -25,11 +69,32 @@ pub struct WorkerPool {
     result_sender: Sender<KeyInfo>,
     total_attempts: Arc<AtomicU64>,
     should_stop: Arc<AtomicBool>,
+    // CPU workers idle while set
+    paused: Arc<AtomicBool>,
+    // Overall CPU cap in percent (100 = uncapped)
+    cpu_percent: u8,
+    // Per-worker busy share in per-mille derived from `cpu_percent` and the worker count
+    duty: Arc<AtomicU32>,
+    // CPU placement and scheduling class applied by each CPU worker thread
+    thread_policy: Arc<ThreadPolicy>,
+    // Set by the first worker that fails to apply `thread_policy`, so the
+    // failure is reported once rather than per thread
+    policy_failed: Arc<AtomicBool>,
+    // Shared top-N for `--best`, fed by every CPU worker
+    best_keys: Option<Arc<BestKeys>>,
+    // `--plugin` matcher, instantiated by each CPU worker
+    plugin: Option<Arc<Plugin>>,
+    // `--fill-node-ids` node IDs that still need keys
+    node_ids: Option<Arc<NodeIdSet>>,
+    // Running CPU workers, in display order
+    cpu_workers: Vec<WorkerHandle>,
+    // Threads of removed workers, joined on stop
+    retired: Vec<JoinHandle<()>>,
+    // Used only for thread names, so they stay unique across add/remove
+    next_worker_id: usize,
     worker_handles: Vec<JoinHandle<()>>,
     #[cfg(target_os = "macos")]
     gpu_enabled: bool,
-    // Per-worker attempt counters for live stats
-    attempts_per_worker: Vec<Arc<AtomicU64>>,
     // Optional GPU attempts counter
     #[cfg(target_os = "macos")]
     gpu_attempts: Option<Arc<AtomicU64>>,
@@ -50,12 +115,20 @@ impl WorkerPool {
             result_sender,
             total_attempts,
             should_stop,
+            paused: Arc::new(AtomicBool::new(false)),
+            cpu_percent: 100,
+            duty: Arc::new(AtomicU32::new(FULL_DUTY)),
+            thread_policy: Arc::new(ThreadPolicy::default()),
+            policy_failed: Arc::new(AtomicBool::new(false)),
+            best_keys: None,
+            plugin: None,
+            node_ids: None,
+            cpu_workers: Vec::new(),
+            retired: Vec::new(),
+            next_worker_id: 0,
             worker_handles: Vec::new(),
             #[cfg(target_os = "macos")]
             gpu_enabled: false,
-            attempts_per_worker: (0..num_workers)
-                .map(|_| Arc::new(AtomicU64::new(0)))
-                .collect(),
             #[cfg(target_os = "macos")]
             gpu_attempts: None,
         }
@@ -73,9 +146,121 @@ impl WorkerPool {
         self.gpu_attempts = Some(counter);
     }
 
-    /// Snapshot of per-worker attempt counters (cloned Arcs)
+    /// Snapshot of per-worker attempt counters (cloned Arcs), one per running
+    /// CPU worker in display order; take a new snapshot after the count changes
     pub fn attempts_per_worker_snapshot(&self) -> Vec<Arc<AtomicU64>> {
-        self.attempts_per_worker.clone()
+        self.cpu_workers
+            .iter()
+            .map(|w| w.attempts.clone())
+            .collect()
+    }
+
+    /// Number of running CPU workers
+    pub fn worker_count(&self) -> usize {
+        self.cpu_workers.len()
+    }
+
+    /// Pause or resume all CPU workers without stopping their threads
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Cap the pool at `percent` of the whole machine's CPU time
+    ///
+    /// Each worker runs for part of every time slice and sleeps for the rest;
+    /// the share is spread across the current workers, so with fewer workers
+    /// than cores each one runs longer.
+    pub fn set_cpu_percent(&mut self, percent: u8) {
+        self.cpu_percent = percent.clamp(1, 100);
+        self.update_duty();
+    }
+
+    pub fn cpu_percent(&self) -> u8 {
+        self.cpu_percent
+    }
+
+    /// Pin and schedule CPU workers started from now on according to `policy`
+    pub fn set_thread_policy(&mut self, policy: ThreadPolicy) {
+        self.thread_policy = Arc::new(policy);
+    }
+
+    /// Score every key CPU workers generate into `best` (`--best`)
+    pub fn set_best_keys(&mut self, best: Arc<BestKeys>) {
+        self.best_keys = Some(best);
+    }
+
+    /// Require `plugin` to accept every match of CPU workers started from now on
+    pub fn set_plugin(&mut self, plugin: Arc<Plugin>) {
+        self.plugin = Some(plugin);
+    }
+
+    /// Only send matches whose node ID is in `node_ids` (`--fill-node-ids`)
+    pub fn set_node_ids(&mut self, node_ids: Arc<NodeIdSet>) {
+        self.node_ids = Some(node_ids);
+    }
+
+    fn update_duty(&self) {
+        let workers = self.num_workers.max(1) as u64;
+        let cores = num_cpus::get() as u64;
+        let duty = (self.cpu_percent as u64 * 10 * cores / workers).min(FULL_DUTY as u64);
+        self.duty.store(duty.max(1) as u32, Ordering::Relaxed);
+    }
+
+    /// Start one more CPU worker on a running pool; returns its index
+    pub fn add_worker(&mut self) -> Result<usize, String> {
+        if self.cpu_workers.len() >= max_workers() {
+            return Err(format!("Already at the limit of {} workers", max_workers()));
+        }
+        let handle = self.spawn_cpu_worker()?;
+        self.cpu_workers.push(handle);
+        self.num_workers = self.cpu_workers.len();
+        self.update_duty();
+        Ok(self.num_workers - 1)
+    }
+
+    /// Retire the most recently added CPU worker; its counter leaves the snapshot
+    ///
+    /// The thread finishes its current batch (already counted in the total) and
+    /// exits; it is joined in `stop`.
+    pub fn remove_worker(&mut self) -> bool {
+        let Some(worker) = self.cpu_workers.pop() else {
+            return false;
+        };
+        worker.retire.store(true, Ordering::Relaxed);
+        self.retired.push(worker.thread);
+        self.num_workers = self.cpu_workers.len();
+        self.update_duty();
+        true
+    }
+
+    /// Apply a runtime command; worker counts are clamped to `max_workers`
+    pub fn apply(&mut self, command: PoolCommand) -> Result<(), String> {
+        match command {
+            PoolCommand::AddWorker => {
+                self.add_worker()?;
+            }
+            PoolCommand::RemoveWorker => {
+                self.remove_worker();
+            }
+            PoolCommand::SetWorkers(count) => {
+                let count = count.min(max_workers());
+                while self.cpu_workers.len() < count {
+                    self.add_worker()?;
+                }
+                while self.cpu_workers.len() > count {
+                    self.remove_worker();
+                }
+            }
+            PoolCommand::Pause => self.set_paused(true),
+            PoolCommand::Resume => self.set_paused(false),
+            PoolCommand::TogglePause => self.set_paused(!self.is_paused()),
+            PoolCommand::SetCpuPercent(percent) => self.set_cpu_percent(percent),
+        }
+        Ok(())
     }
 
     #[cfg(not(target_os = "macos"))]
@@ -85,39 +270,91 @@ impl WorkerPool {
     }
 
     /// Start all worker threads
-    pub fn start(&mut self) {
+    pub fn start(&mut self) -> Result<(), String> {
         #[cfg(target_os = "macos")]
         if self.gpu_enabled {
             self.start_gpu_worker();
         }
 
-        for worker_id in 0..self.num_workers {
-            let handle = self.spawn_cpu_worker(worker_id);
-            self.worker_handles.push(handle);
+        self.update_duty();
+        for _ in 0..self.num_workers {
+            let handle = self.spawn_cpu_worker()?;
+            self.cpu_workers.push(handle);
         }
+        Ok(())
     }
 
     /// Spawn a CPU worker thread
-    fn spawn_cpu_worker(&self, worker_id: usize) -> JoinHandle<()> {
+    fn spawn_cpu_worker(&mut self) -> Result<WorkerHandle, String> {
+        let worker_id = self.next_worker_id;
+        self.next_worker_id += 1;
+
         let pattern_config = self.pattern_config.clone();
         let result_sender = self.result_sender.clone();
         let total_attempts = self.total_attempts.clone();
         let should_stop = self.should_stop.clone();
-        let worker_attempts = self.attempts_per_worker[worker_id].clone();
+        let paused = self.paused.clone();
+        let duty = self.duty.clone();
+        let thread_policy = self.thread_policy.clone();
+        let policy_failed = self.policy_failed.clone();
+        let best_keys = self.best_keys.clone();
+        let plugin = self.plugin.clone();
+        let node_ids = self.node_ids.clone();
+        // Removal takes the last worker, so a new one reuses the freed CPU slot
+        let slot = self.cpu_workers.len();
+        let attempts = Arc::new(AtomicU64::new(0));
+        let retire = Arc::new(AtomicBool::new(false));
+        let worker_attempts = attempts.clone();
+        let worker_retire = retire.clone();
 
-        thread::Builder::new()
+        let thread = thread::Builder::new()
             .name(format!("keygen-worker-{}", worker_id))
             .spawn(move || {
+                // CPU ids are validated before the pool starts, but the kernel can
+                // still refuse (hotplug, cgroup limits, missing privileges); the
+                // worker then runs unplaced
+                if let Err(e) = thread_policy.apply_to_current_thread(slot) {
+                    if !policy_failed.swap(true, Ordering::Relaxed) {
+                        eprintln!(
+                            "Warning: {} (worker {}; later failures are not shown)",
+                            e, worker_id
+                        );
+                    }
+                }
+                // Instantiated once when loaded; a worker never runs without it
+                let plugin = match plugin.as_ref().map(|p| p.instantiate()).transpose() {
+                    Ok(plugin) => plugin,
+                    Err(e) => {
+                        eprintln!(
+                            "Worker {} stopped: plugin failed to start: {}",
+                            worker_id, e
+                        );
+                        return;
+                    }
+                };
                 cpu_worker_loop(
-                    worker_id,
                     &pattern_config,
                     &result_sender,
                     &total_attempts,
                     &worker_attempts,
-                    &should_stop,
+                    best_keys.as_deref(),
+                    plugin,
+                    &WorkerControl {
+                        should_stop: &should_stop,
+                        retire: &worker_retire,
+                        paused: &paused,
+                        duty: &duty,
+                        node_ids: node_ids.as_deref(),
+                    },
                 );
             })
-            .expect("Failed to spawn worker thread")
+            .map_err(|e| format!("Failed to spawn worker thread: {}", e))?;
+
+        Ok(WorkerHandle {
+            attempts,
+            retire,
+            thread,
+        })
     }
 
     /// Start GPU worker (macOS only)
@@ -152,34 +389,140 @@ impl WorkerPool {
         self.should_stop.store(true, Ordering::Relaxed);
 
         // Wait for all workers to finish
-        for handle in self.worker_handles.drain(..) {
+        let cpu_threads = self.cpu_workers.drain(..).map(|w| w.thread);
+        for handle in self
+            .worker_handles
+            .drain(..)
+            .chain(cpu_threads)
+            .chain(self.retired.drain(..))
+        {
             let _ = handle.join();
         }
     }
 }
 
+/// Shared state a CPU worker checks as it runs
+struct WorkerControl<'a> {
+    should_stop: &'a AtomicBool,
+    retire: &'a AtomicBool,
+    paused: &'a AtomicBool,
+    duty: &'a AtomicU32,
+    /// Node IDs still wanted; keys for any other ID are not sent
+    node_ids: Option<&'a NodeIdSet>,
+}
+
+impl WorkerControl<'_> {
+    fn should_exit(&self) -> bool {
+        self.should_stop.load(Ordering::Relaxed) || self.retire.load(Ordering::Relaxed)
+    }
+
+    /// Sleep in short steps so stop requests are still noticed
+    fn sleep(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        while !self.should_exit() {
+            let left = until.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            thread::sleep(left.min(Duration::from_millis(50)));
+        }
+    }
+}
+
+/// Wall-clock duty cycle: after a busy stretch, sleep in proportion to it
+struct DutyCycle {
+    busy_since: Instant,
+}
+
+impl DutyCycle {
+    fn new() -> Self {
+        Self {
+            busy_since: Instant::now(),
+        }
+    }
+
+    /// Restart the busy stretch (after a pause)
+    fn reset(&mut self) {
+        self.busy_since = Instant::now();
+    }
+
+    /// Sleep if this worker has used up its share of the current slice
+    fn pace(&mut self, control: &WorkerControl) {
+        let duty = control.duty.load(Ordering::Relaxed);
+        if duty >= FULL_DUTY {
+            return;
+        }
+        let busy = self.busy_since.elapsed();
+        if busy < DUTY_SLICE * duty / FULL_DUTY {
+            return;
+        }
+        // busy / (busy + idle) = duty
+        control.sleep(busy * (FULL_DUTY - duty) / duty);
+        self.reset();
+    }
+}
+
 /// CPU worker loop - generates and checks keys continuously
 fn cpu_worker_loop(
-    _worker_id: usize,
     pattern_config: &PatternConfig,
     result_sender: &Sender<KeyInfo>,
     total_attempts: &AtomicU64,
     worker_attempts: &Arc<AtomicU64>,
-    should_stop: &AtomicBool,
+    best_keys: Option<&BestKeys>,
+    mut plugin: Option<PluginInstance>,
+    control: &WorkerControl,
 ) {
     let mut local_attempts: u64 = 0;
+    let mut duty_cycle = DutyCycle::new();
+    // Candidates for `--best` since the last merge, and the rank they must beat
+    let mut local_best = best_keys.map(|b| TopN::new(b.capacity()));
+    let mut best_threshold = best_keys.and_then(|b| b.threshold());
 
     loop {
         // Check if we should stop
-        if should_stop.load(Ordering::Relaxed) {
+        if control.should_exit() {
             break;
         }
 
+        if control.paused.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(50));
+            duty_cycle.reset();
+            continue;
+        }
+
         // Generate and check a batch of keys
-        for _ in 0..BATCH_SIZE {
+        for i in 0..BATCH_SIZE {
+            if i.is_multiple_of(DUTY_CHECK_KEYS) {
+                duty_cycle.pace(control);
+            }
+
             let key = keygen::generate_meshcore_keypair();
 
-            if matches_pattern_bytes(&key.public_bytes, pattern_config) {
+            if let (Some(best), Some(top)) = (best_keys, local_best.as_mut()) {
+                let mut score = best.scorer().score(&key.public_bytes);
+                if let Some(p) = plugin
+                    .as_mut()
+                    .filter(|_| best.scorer().kind() == ScoreKind::Plugin)
+                {
+                    score.plugin = p.score(&key.public_bytes).unwrap_or(0);
+                }
+                let rank = best.scorer().rank(&score);
+                if best_threshold.is_none_or(|t| rank > t) && top.accepts(rank) {
+                    top.push(ScoredKey {
+                        key: key.clone(),
+                        score,
+                        rank,
+                    });
+                }
+            }
+
+            // The built-in pattern prefilters keys for the plugin
+            if control
+                .node_ids
+                .is_none_or(|ids| ids.contains(key.public_bytes[0]))
+                && matches_pattern_bytes(&key.public_bytes, pattern_config)
+                && plugin.as_mut().is_none_or(|p| p.matches(&key.public_bytes))
+            {
                 // Found a matching key!
                 if result_sender.send(key).is_err() {
                     return; // Channel closed
@@ -194,8 +537,14 @@ fn cpu_worker_loop(
         worker_attempts.fetch_add(local_attempts, Ordering::Relaxed);
         local_attempts = 0;
 
+        if let (Some(best), Some(top)) = (best_keys, local_best.as_mut()) {
+            if !top.is_empty() {
+                best_threshold = best.merge(top.drain());
+            }
+        }
+
         // Check stop condition after each batch
-        if should_stop.load(Ordering::Relaxed) {
+        if control.should_exit() {
             break;
         }
     }
@@ -205,7 +554,6 @@ fn cpu_worker_loop(
 mod tests {
     use super::*;
     use crate::pattern::PatternMode;
-    use std::time::Duration;
 
     #[test]
     fn test_worker_pool_creation() {
@@ -230,10 +578,14 @@ mod tests {
             mode: PatternMode::Vanity,
             prefix: None,
             vanity_length: 2,
+            symmetries: crate::symmetry::default_symmetries(),
+            dictionary: None,
+            expression: None,
+            regex: None,
         };
 
         let mut pool = WorkerPool::new(2, config, tx, attempts.clone(), stop.clone());
-        pool.start();
+        pool.start().unwrap();
 
         // Wait for at least one key to be found
         let result = rx.recv_timeout(Duration::from_secs(10));
@@ -260,7 +612,7 @@ mod tests {
         let config = PatternConfig::default();
         let mut pool = WorkerPool::new(2, config, tx, attempts, stop.clone());
 
-        pool.start();
+        pool.start().unwrap();
 
         // Let it run briefly
         thread::sleep(Duration::from_millis(100));
@@ -270,4 +622,102 @@ mod tests {
 
         assert!(stop.load(Ordering::Relaxed));
     }
+
+    #[test]
+    fn test_worker_pool_pause_and_add_worker() {
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        let attempts = Arc::new(AtomicU64::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let mut pool = WorkerPool::new(1, PatternConfig::default(), tx, attempts.clone(), stop);
+        pool.set_paused(true);
+        pool.start().unwrap();
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(attempts.load(Ordering::Relaxed), 0);
+
+        assert_eq!(pool.add_worker(), Ok(1));
+        let counters = pool.attempts_per_worker_snapshot();
+        assert_eq!(counters.len(), 2);
+
+        pool.set_paused(false);
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while counters[1].load(Ordering::Relaxed) == 0 && std::time::Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        pool.stop();
+        assert!(counters[1].load(Ordering::Relaxed) > 0);
+    }
+
+    #[test]
+    fn test_worker_pool_runtime_scaling() {
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        let attempts = Arc::new(AtomicU64::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let mut pool = WorkerPool::new(2, PatternConfig::default(), tx, attempts, stop);
+        pool.start().unwrap();
+        assert_eq!(pool.attempts_per_worker_snapshot().len(), 2);
+
+        pool.apply(PoolCommand::SetWorkers(4)).unwrap();
+        assert_eq!(pool.worker_count(), 4);
+        pool.apply(PoolCommand::RemoveWorker).unwrap();
+        pool.apply(PoolCommand::SetWorkers(1)).unwrap();
+        assert_eq!(pool.attempts_per_worker_snapshot().len(), 1);
+
+        pool.apply(PoolCommand::TogglePause).unwrap();
+        assert!(pool.is_paused());
+        pool.apply(PoolCommand::Resume).unwrap();
+        assert!(!pool.is_paused());
+
+        // Retired threads are joined here without hanging
+        pool.stop();
+        assert_eq!(pool.worker_count(), 0);
+    }
+
+    #[test]
+    fn test_duty_cycle_spreads_cap_across_workers() {
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        let attempts = Arc::new(AtomicU64::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+        let cores = num_cpus::get() as u32;
+
+        let mut pool = WorkerPool::new(1, PatternConfig::default(), tx, attempts, stop);
+        pool.set_cpu_percent(10);
+        assert_eq!(
+            pool.duty.load(Ordering::Relaxed),
+            (100 * cores).min(FULL_DUTY)
+        );
+
+        pool.apply(PoolCommand::SetCpuPercent(100)).unwrap();
+        assert_eq!(pool.duty.load(Ordering::Relaxed), FULL_DUTY);
+        pool.apply(PoolCommand::SetCpuPercent(0)).unwrap();
+        assert_eq!(pool.cpu_percent(), 1);
+    }
+
+    #[test]
+    fn test_duty_cycle_sleeps_for_its_share() {
+        let stop = AtomicBool::new(false);
+        let retire = AtomicBool::new(false);
+        let paused = AtomicBool::new(false);
+        let duty = AtomicU32::new(250);
+        let control = WorkerControl {
+            should_stop: &stop,
+            retire: &retire,
+            paused: &paused,
+            duty: &duty,
+            node_ids: None,
+        };
+
+        let mut cycle = DutyCycle::new();
+        thread::sleep(Duration::from_millis(40));
+        let start = Instant::now();
+        cycle.pace(&control);
+        // 40ms busy at 25% duty means at least 120ms idle
+        assert!(start.elapsed() >= Duration::from_millis(110));
+
+        // Within the slice allowance nothing sleeps
+        let start = Instant::now();
+        cycle.pace(&control);
+        assert!(start.elapsed() < Duration::from_millis(20));
+    }
 }